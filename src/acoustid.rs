@@ -0,0 +1,100 @@
+// ─── AcoustID identification ──────────────────────────────────────────────────
+//
+// `--acoustid KEY` names the tracks that have no TITLE tag by their sound:
+// each one is fingerprinted with Chromaprint's `fpcalc` command-line tool and
+// looked up on the AcoustID web service (KEY is an application API key from
+// acoustid.org). The best-matching recording's artist and title then stand in
+// for the missing tags, so the report lists "Artist - Title" instead of
+// "track07.flac". The files themselves are not changed.
+//
+// Both `fpcalc` and `curl` must be on the PATH.
+
+use crate::json::Json;
+use crate::musicbrainz::curl;
+use crate::TrackResult;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const API: &str = "https://api.acoustid.org/v2/lookup";
+
+/// Lowest AcoustID match score (0–1) accepted as the track's recording.
+const MIN_SCORE: f64 = 0.8;
+
+/// Shortest interval between two requests (the service allows three a second).
+const REQUEST_INTERVAL: Duration = Duration::from_millis(334);
+
+/// The Chromaprint fingerprint and duration (whole seconds) of the file at `path`.
+fn fingerprint(path: &Path) -> Result<(String, u64), String> {
+    let output = Command::new("fpcalc")
+        .arg("-json")
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("cannot run fpcalc ({})", e))?;
+    if !output.status.success() {
+        return Err(format!("fpcalc: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let json = Json::parse(&String::from_utf8_lossy(&output.stdout)).map_err(|e| format!("fpcalc: {}", e))?;
+    match (json.get("fingerprint").and_then(Json::string), json.get("duration").and_then(Json::number)) {
+        (Some(fingerprint), Some(duration)) => Ok((fingerprint.to_string(), duration as u64)),
+        _ => Err("fpcalc printed no fingerprint".to_string()),
+    }
+}
+
+/// Artist and title of the best recording AcoustID matches the fingerprint
+/// to, if it is a close enough match.
+fn lookup(key: &str, fingerprint: &str, duration: u64) -> Result<Option<(Option<String>, String)>, String> {
+    // The fingerprint is too long for a query string
+    let form = format!("client={}&meta=recordings&duration={}&fingerprint={}", key, duration, fingerprint);
+    let answer = curl(&["--data", &form, API])?;
+    let Some(Json::Array(results)) = answer.get("results") else {
+        return Err("unexpected answer from AcoustID".to_string());
+    };
+    let best = results
+        .iter()
+        .filter(|r| r.get("score").and_then(Json::number).unwrap_or(0.0) >= MIN_SCORE)
+        .filter_map(|r| match r.get("recordings") {
+            Some(Json::Array(recordings)) => recordings.iter().find(|rec| rec.get("title").is_some()),
+            _ => None,
+        })
+        .next();
+    Ok(best.map(|recording| {
+        let artists = match recording.get("artists") {
+            Some(Json::Array(artists)) => artists.iter().filter_map(|a| a.get("name")?.string()).collect(),
+            _ => Vec::new(),
+        };
+        let title = recording.get("title").and_then(Json::string).unwrap_or_default().to_string();
+        ((!artists.is_empty()).then(|| artists.join(", ")), title)
+    }))
+}
+
+/// Looks tracks up with one API key, keeping to the request rate limit.
+pub struct Identifier {
+    key: String,
+    last_request: Option<Instant>,
+}
+
+impl Identifier {
+    pub fn new(key: &str) -> Identifier {
+        Identifier { key: key.to_string(), last_request: None }
+    }
+
+    /// Fills in the artist and title of `track`, read from the file at
+    /// `path`, when it has no TITLE tag and AcoustID knows the recording.
+    pub fn identify(&mut self, track: &mut TrackResult, path: &Path) -> Result<(), String> {
+        if track.tags.title.is_some() {
+            return Ok(());
+        }
+        let (fingerprint, duration) = fingerprint(path)?;
+        if let Some(wait) = self.last_request.and_then(|at| REQUEST_INTERVAL.checked_sub(at.elapsed())) {
+            std::thread::sleep(wait);
+        }
+        self.last_request = Some(Instant::now());
+        if let Some((artist, title)) = lookup(&self.key, &fingerprint, duration)? {
+            track.tags.artist = artist.or(track.tags.artist.take());
+            track.tags.title = Some(title);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+// ─── Analysis cache ───────────────────────────────────────────────────────────
+//
+// `--cache` keeps each folder's results in a `.dr-measure-cache` file next to
+// its music, so that running over a library again only analyses the files
+// that are new or have changed, and the reports are rewritten in seconds.
+//
+// A file's entry is used when its name, size and modification time are the
+// ones recorded and it was analysed by the same dr-measure version with the
+// same settings. Its tags and encoder vendor are read from the file again, as
+// they can change without the audio doing so (`--preserve-mtime` even keeps
+// the modification time). The cache is not used with `--verify`, which has to
+// decode every file to find damage, nor with `--waveforms` or
+// `--spectrograms`, whose images it doesn't hold.
+//
+// The file is a private binary format: a header naming the version and
+// settings, then one entry per file with its encoded results.
+
+use crate::clipping::{ClipRegion, Clipping};
+use crate::spectrum::Spectrum;
+use crate::{AnalysisOptions, BlockStats, Levels, Tags, TrackResult};
+use claxon::FlacReader;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+pub const FILE_NAME: &str = ".dr-measure-cache";
+
+const MAGIC: &[u8; 4] = b"DRMC";
+
+/// Whether results analysed with `opts` can be cached.
+pub fn applies(opts: &AnalysisOptions) -> bool {
+    !opts.verify && !opts.waveform && !opts.spectrogram
+}
+
+/// What the results depend on besides the audio: a cache written under
+/// another header is ignored.
+fn header(opts: &AnalysisOptions) -> String {
+    format!("{} {}\n{:?}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), opts)
+}
+
+/// Size and modification time (ns since the epoch) identifying a file's state.
+fn stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    Ok((metadata.len(), modified))
+}
+
+struct Entry {
+    size: u64,
+    modified: u64,
+    results: Vec<u8>,
+}
+
+/// The cached results of one folder.
+#[derive(Default)]
+pub struct Cache {
+    entries: HashMap<String, Entry>,
+}
+
+impl Cache {
+    /// The cache of `folder` for `opts`; empty when there is none or it was
+    /// written for other settings or can't be read.
+    pub fn load(folder: &Path, opts: &AnalysisOptions) -> Cache {
+        let Ok(bytes) = fs::read(folder.join(FILE_NAME)) else {
+            return Cache::default();
+        };
+        let mut input = &bytes[..];
+        let entries = (|| {
+            if input.get(..4)? != MAGIC {
+                return None;
+            }
+            input = &input[4..];
+            if String::read(&mut input)? != header(opts) {
+                return None;
+            }
+            let mut entries = HashMap::new();
+            for _ in 0..u64::read(&mut input)? {
+                let name = String::read(&mut input)?;
+                let (size, modified) = (u64::read(&mut input)?, u64::read(&mut input)?);
+                entries.insert(name, Entry { size, modified, results: Vec::read(&mut input)? });
+            }
+            Some(entries)
+        })();
+        Cache { entries: entries.unwrap_or_default() }
+    }
+
+    /// The cached results for the file at `path`, if it hasn't changed since.
+    pub fn get(&self, path: &Path) -> Option<TrackResult> {
+        let filename = path.file_name()?.to_string_lossy().into_owned();
+        let entry = self.entries.get(&filename)?;
+        if stamp(path).ok()? != (entry.size, entry.modified) {
+            return None;
+        }
+        let reader = FlacReader::open(path).ok()?;
+        let mut track = decode(&mut &entry.results[..])?;
+        track.filename = filename;
+        track.tags = Tags::read(&reader);
+        track.vendor = reader.vendor().map(str::to_string);
+        track.file_size = entry.size;
+        Some(track)
+    }
+}
+
+/// Records the results of `tracks` in the cache of `folder`, keeping the
+/// entries of other files that are still there.
+pub fn save(tracks: &[&TrackResult], folder: &Path, opts: &AnalysisOptions) -> io::Result<()> {
+    let mut cache = Cache::load(folder, opts);
+    cache.entries.retain(|name, _| folder.join(name).is_file());
+    for t in tracks {
+        let (size, modified) = stamp(&folder.join(&t.filename))?;
+        let mut results = Vec::new();
+        encode(t, &mut results);
+        cache.entries.insert(t.filename.clone(), Entry { size, modified, results });
+    }
+
+    let mut out = MAGIC.to_vec();
+    header(opts).write(&mut out);
+    (cache.entries.len() as u64).write(&mut out);
+    let mut names: Vec<&String> = cache.entries.keys().collect();
+    names.sort();
+    for name in names {
+        let entry = &cache.entries[name];
+        name.write(&mut out);
+        entry.size.write(&mut out);
+        entry.modified.write(&mut out);
+        entry.results.write(&mut out);
+    }
+    fs::write(folder.join(FILE_NAME), out)
+}
+
+/// A value stored in the cache, little-endian.
+trait Field: Sized {
+    fn write(&self, out: &mut Vec<u8>);
+    fn read(input: &mut &[u8]) -> Option<Self>;
+}
+
+fn read_bytes<const N: usize>(input: &mut &[u8]) -> Option<[u8; N]> {
+    let (bytes, rest) = input.split_first_chunk::<N>()?;
+    *input = rest;
+    Some(*bytes)
+}
+
+macro_rules! number_field {
+    ($($t:ty),*) => {$(
+        impl Field for $t {
+            fn write(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn read(input: &mut &[u8]) -> Option<Self> {
+                read_bytes(input).map(<$t>::from_le_bytes)
+            }
+        }
+    )*};
+}
+
+number_field!(u8, u32, u64, i32, f64);
+
+impl Field for bool {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u8).write(out);
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        Some(u8::read(input)? != 0)
+    }
+}
+
+impl<T: Field> Field for Vec<T> {
+    fn write(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).write(out);
+        for item in self {
+            item.write(out);
+        }
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        let len = u64::read(input)? as usize;
+        // Each item takes at least a byte, so a corrupt length can't ask for more
+        if len > input.len() {
+            return None;
+        }
+        (0..len).map(|_| T::read(input)).collect()
+    }
+}
+
+impl<T: Field> Field for Option<T> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.is_some().write(out);
+        if let Some(value) = self {
+            value.write(out);
+        }
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        if bool::read(input)? {
+            Some(Some(T::read(input)?))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+impl Field for String {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.as_bytes().to_vec().write(out);
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        String::from_utf8(Vec::read(input)?).ok()
+    }
+}
+
+impl Field for BlockStats {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.rms.write(out);
+        self.peak.write(out);
+        self.start.write(out);
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        Some(BlockStats { rms: f64::read(input)?, peak: f64::read(input)?, start: u64::read(input)? })
+    }
+}
+
+impl Field for Levels {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.dr.write(out);
+        self.peak_db.write(out);
+        self.rms_db.write(out);
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        Some(Levels { dr: f64::read(input)?, peak_db: f64::read(input)?, rms_db: f64::read(input)? })
+    }
+}
+
+impl Field for ClipRegion {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.start.write(out);
+        self.len.write(out);
+    }
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        Some(ClipRegion { start: u64::read(input)?, len: u64::read(input)? })
+    }
+}
+
+/// The measured values of `t`; what `Cache::get` reads from the file again
+/// (and the waveform, spectrogram and integrity verdict) is left out.
+fn encode(t: &TrackResult, out: &mut Vec<u8>) {
+    t.dr.write(out);
+    t.dr_exact.write(out);
+    t.channel_dr.write(out);
+    t.k_dr.write(out);
+    t.mid_side.as_ref().map(|levels| levels.to_vec()).write(out);
+    t.channel_blocks.write(out);
+    for value in [t.peak_db, t.rms_db, t.crest_db, t.plain_rms_db, t.noise_floor_db, t.duration_secs] {
+        value.write(out);
+    }
+    for value in [t.channels, t.sample_rate, t.bit_depth, t.effective_bits] {
+        value.write(out);
+    }
+    for value in [t.sample_peak, t.lufs, t.true_peak_db] {
+        value.write(out);
+    }
+    t.gating_blocks.write(out);
+    t.subblock_powers.write(out);
+    t.subblock_peaks.write(out);
+    t.clipping.regions.write(out);
+    t.clipping.clipped_frames.write(out);
+    t.intersample_overs.write(out);
+    t.clicks.write(out);
+    t.dc_offset.write(out);
+    t.subsonic_db.write(out);
+    t.correlation.write(out);
+    t.balance_db.write(out);
+    t.spectrum.cutoff_hz.write(out);
+    t.spectrum.lossy_suspect.write(out);
+    t.spectrum.upsampled_from.write(out);
+    t.audio_md5.to_vec().write(out);
+    t.leading_silence_secs.write(out);
+    t.trailing_silence_secs.write(out);
+    (t.gated_blocks as u64).write(out);
+}
+
+fn decode(input: &mut &[u8]) -> Option<TrackResult> {
+    let f = |input: &mut &[u8]| f64::read(input);
+    Some(TrackResult {
+        dr: i32::read(input)?,
+        dr_exact: f(input)?,
+        channel_dr: Vec::read(input)?,
+        k_dr: Option::read(input)?,
+        mid_side: match Option::<Vec<Levels>>::read(input)? {
+            Some(levels) => Some(levels.try_into().ok()?),
+            None => None,
+        },
+        channel_blocks: Vec::read(input)?,
+        peak_db: f(input)?,
+        rms_db: f(input)?,
+        crest_db: f(input)?,
+        plain_rms_db: f(input)?,
+        noise_floor_db: f(input)?,
+        duration_secs: f(input)?,
+        channels: u32::read(input)?,
+        sample_rate: u32::read(input)?,
+        bit_depth: u32::read(input)?,
+        effective_bits: u32::read(input)?,
+        sample_peak: f(input)?,
+        lufs: f(input)?,
+        true_peak_db: f(input)?,
+        gating_blocks: Vec::read(input)?,
+        subblock_powers: Vec::read(input)?,
+        subblock_peaks: Vec::read(input)?,
+        clipping: Clipping { regions: Vec::read(input)?, clipped_frames: u64::read(input)? },
+        intersample_overs: Vec::read(input)?,
+        clicks: Vec::read(input)?,
+        dc_offset: Vec::read(input)?,
+        subsonic_db: Option::read(input)?,
+        correlation: Option::read(input)?,
+        balance_db: Option::read(input)?,
+        spectrum: Spectrum {
+            cutoff_hz: f(input)?,
+            lossy_suspect: bool::read(input)?,
+            upsampled_from: Option::read(input)?,
+            spectrogram: None,
+        },
+        waveform: None,
+        audio_md5: Vec::<u8>::read(input)?.try_into().ok()?,
+        leading_silence_secs: f(input)?,
+        trailing_silence_secs: f(input)?,
+        gated_blocks: u64::read(input)? as usize,
+        // Filled in by Cache::get
+        filename: String::new(),
+        tags: Tags::default(),
+        file_size: 0,
+        vendor: None,
+        integrity: None,
+    })
+}
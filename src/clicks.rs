@@ -0,0 +1,77 @@
+// ─── Click / pop detection ────────────────────────────────────────────────────
+//
+// A click is an impulse: a sudden kink in the waveform that music rarely
+// produces. Per channel:
+//
+//  1. Take the second difference e[n] = x[n] - 2·x[n-1] + x[n-2], which is
+//     near zero for smooth (band-limited, mostly low-frequency) signals and
+//     spikes at a discontinuity.
+//  2. Track the local level of |e| with an exponential average over
+//     ENVELOPE_SECONDS.
+//  3. A sample whose |e| exceeds CLICK_RATIO times that level, and
+//     CLICK_MIN_LEVEL in absolute terms, is a click candidate.
+//
+// Candidates closer than MERGE_SECONDS (on any channel) form one click.
+
+const ENVELOPE_SECONDS: f64 = 0.02;
+const CLICK_RATIO: f64 = 12.0;
+const CLICK_MIN_LEVEL: f64 = 0.05;
+const MERGE_SECONDS: f64 = 0.005;
+/// The envelope settles before candidates are accepted.
+const WARMUP_SECONDS: f64 = 0.05;
+
+/// Streaming detector fed one normalised interleaved frame at a time.
+pub struct ClickDetector {
+    alpha: f64,
+    merge_frames: u64,
+    warmup_frames: u64,
+    frame: u64,
+    /// Per channel: (x[n-1], x[n-2], envelope of |e|)
+    state: Vec<(f64, f64, f64)>,
+    /// First frame of every click.
+    clicks: Vec<u64>,
+    last_candidate: Option<u64>,
+}
+
+impl ClickDetector {
+    pub fn new(channels: u32, sample_rate: u32) -> Self {
+        let sr = sample_rate as f64;
+        ClickDetector {
+            alpha: 1.0 / (ENVELOPE_SECONDS * sr).max(1.0),
+            merge_frames: (MERGE_SECONDS * sr).round() as u64,
+            warmup_frames: (WARMUP_SECONDS * sr).round() as u64,
+            frame: 0,
+            state: vec![(0.0, 0.0, 0.0); channels as usize],
+            clicks: Vec::new(),
+            last_candidate: None,
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        let mut candidate = false;
+        for (state, &x) in self.state.iter_mut().zip(frame) {
+            let (x1, x2, envelope) = *state;
+            let e = (x - 2.0 * x1 + x2).abs();
+            if e > CLICK_MIN_LEVEL && e > CLICK_RATIO * envelope {
+                candidate = true;
+            }
+            *state = (x, x1, envelope + self.alpha * (e - envelope));
+        }
+
+        if candidate && self.frame >= self.warmup_frames {
+            let merged = self
+                .last_candidate
+                .is_some_and(|last| self.frame - last <= self.merge_frames);
+            if !merged {
+                self.clicks.push(self.frame);
+            }
+            self.last_candidate = Some(self.frame);
+        }
+        self.frame += 1;
+    }
+
+    /// First frame of every click, in time order.
+    pub fn finish(self) -> Vec<u64> {
+        self.clicks
+    }
+}
@@ -0,0 +1,103 @@
+// ─── Clipping detection ───────────────────────────────────────────────────────
+//
+// A clipped region is a run of at least CLIP_RUN_MIN_SAMPLES consecutive
+// samples pinned at digital full scale (the largest or smallest code for the
+// bit depth) on one channel. Runs found on different channels that overlap in
+// time are merged, so a stereo flat top counts once.
+
+const CLIP_RUN_MIN_SAMPLES: u64 = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClipRegion {
+    /// First clipped frame.
+    pub start: u64,
+    /// Number of frames covered.
+    pub len: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Clipping {
+    /// Merged clipped regions in time order.
+    pub regions: Vec<ClipRegion>,
+    /// Frames covered by any clipped region.
+    pub clipped_frames: u64,
+}
+
+impl Clipping {
+    /// The `n` longest regions, in time order.
+    pub fn worst(&self, n: usize) -> Vec<ClipRegion> {
+        let mut worst = self.regions.clone();
+        worst.sort_by_key(|r| std::cmp::Reverse(r.len));
+        worst.truncate(n);
+        worst.sort_by_key(|r| r.start);
+        worst
+    }
+}
+
+/// Streaming detector fed one normalised interleaved frame at a time.
+pub struct ClipDetector {
+    positive_full_scale: f64,
+    negative_full_scale: f64,
+    frame: u64,
+    /// Per channel: (run start, run length)
+    runs: Vec<(u64, u64)>,
+    regions: Vec<ClipRegion>,
+}
+
+impl ClipDetector {
+    pub fn new(channels: u32, bits_per_sample: u32) -> Self {
+        let scale = (1i64 << (bits_per_sample - 1)) as f64;
+        ClipDetector {
+            positive_full_scale: (scale - 1.0) / scale,
+            negative_full_scale: -1.0,
+            frame: 0,
+            runs: vec![(0, 0); channels as usize],
+            regions: Vec::new(),
+        }
+    }
+
+    fn close_run(&mut self, ch: usize) {
+        let (start, len) = self.runs[ch];
+        if len >= CLIP_RUN_MIN_SAMPLES {
+            self.regions.push(ClipRegion { start, len });
+        }
+        self.runs[ch] = (0, 0);
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        for (ch, &x) in frame.iter().enumerate() {
+            if x >= self.positive_full_scale || x <= self.negative_full_scale {
+                let run = &mut self.runs[ch];
+                if run.1 == 0 {
+                    run.0 = self.frame;
+                }
+                run.1 += 1;
+            } else if self.runs[ch].1 > 0 {
+                self.close_run(ch);
+            }
+        }
+        self.frame += 1;
+    }
+
+    pub fn finish(mut self) -> Clipping {
+        for ch in 0..self.runs.len() {
+            self.close_run(ch);
+        }
+
+        self.regions.sort_by_key(|r| r.start);
+        let mut merged: Vec<ClipRegion> = Vec::new();
+        for r in self.regions {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.start + last.len => {
+                    last.len = last.len.max(r.start + r.len - last.start);
+                }
+                _ => merged.push(r),
+            }
+        }
+
+        Clipping {
+            clipped_frames: merged.iter().map(|r| r.len).sum(),
+            regions: merged,
+        }
+    }
+}
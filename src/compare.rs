@@ -0,0 +1,179 @@
+// ─── Folder comparison ────────────────────────────────────────────────────────
+//
+// `compare A B` measures two folders holding the same album (the original CD
+// and a remaster, say) and lines their tracks up side by side. Tracks are
+// paired by the number at the start of the file name when both have one, and
+// by the rest of the name otherwise; whatever is left over is listed as
+// unmatched.
+
+use crate::{album_lufs, create_text_output, format_dr, ReportOptions, TrackResult};
+use chrono::Local;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Track number and normalised title from a file name such as
+/// "01 - Title.flac" or "1-03 Title.flac" (disc 1, track 3).
+pub fn track_key(filename: &str) -> (Option<u32>, String) {
+    let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+    let digits = |s: &str| s.chars().take_while(char::is_ascii_digit).count();
+
+    let mut rest = stem;
+    let mut number = None;
+    let n = digits(rest);
+    if n > 0 {
+        number = rest[..n].parse().ok();
+        rest = &rest[n..];
+        // Disc-track numbering: fold the disc into the number
+        if let Some(after) = rest.strip_prefix(['-', '.']) {
+            let m = digits(after);
+            if m > 0 {
+                number = number.zip(after[..m].parse::<u32>().ok()).map(|(disc, track)| disc * 1000 + track);
+                rest = &after[m..];
+            }
+        }
+    }
+
+    let title = rest.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+    (number, title)
+}
+
+struct Pairing<'a> {
+    pairs: Vec<(&'a TrackResult, &'a TrackResult)>,
+    only_a: Vec<&'a TrackResult>,
+    only_b: Vec<&'a TrackResult>,
+}
+
+fn pair_tracks<'a>(a: &[&'a TrackResult], b: &[&'a TrackResult]) -> Pairing<'a> {
+    let keys_b: Vec<_> = b.iter().map(|t| track_key(&t.filename)).collect();
+    let mut used = vec![false; b.len()];
+    let mut pairs = Vec::new();
+    let mut only_a = Vec::new();
+
+    for ta in a {
+        let (number, title) = track_key(&ta.filename);
+        let by_number = |(i, (n, _)): (usize, &(Option<u32>, String))| (number.is_some() && *n == number).then_some(i);
+        let by_title = |(i, (_, t)): (usize, &(Option<u32>, String))| (!title.is_empty() && *t == title).then_some(i);
+        let found = keys_b
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !used[i])
+            .find_map(by_number)
+            .or_else(|| keys_b.iter().enumerate().filter(|&(i, _)| !used[i]).find_map(by_title));
+        match found {
+            Some(i) => {
+                used[i] = true;
+                pairs.push((*ta, b[i]));
+            }
+            None => only_a.push(*ta),
+        }
+    }
+    let only_b = b.iter().zip(&used).filter(|(_, &u)| !u).map(|(t, _)| *t).collect();
+
+    Pairing { pairs, only_a, only_b }
+}
+
+pub fn write_comparison(
+    tracks: [&[&TrackResult]; 2],
+    errors: &[(String, String)],
+    folders: [&Path; 2],
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let mut f = BufWriter::new(create_text_output(output_path, opts.ascii)?);
+    let [a, b] = tracks;
+    let pairing = pair_tracks(a, b);
+    let dr = |value: f64| format_dr(value, opts.precision, opts.dr.algorithm);
+
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let folder_str = |folder: &Path| {
+        folder.canonicalize().unwrap_or_else(|_| folder.to_path_buf()).display().to_string()
+    };
+
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f, "  Dynamic Range Comparison")?;
+    writeln!(f, "  Generated : {}", timestamp)?;
+    writeln!(f, "  A         : {}", folder_str(folders[0]))?;
+    writeln!(f, "  B         : {}", folder_str(folders[1]))?;
+    if let Some(label) = opts.dr.algorithm.label() {
+        writeln!(f, "  Algorithm : {}", label)?;
+    }
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f)?;
+
+    writeln!(
+        f,
+        "  {:<6} {:<6} {:>5}    {:>6}  {:>6}  {:>6}    {:>6}  {:>6}  {:>6}    {:>6}  {:>6}  {:>6}  File (A)",
+        "DR A", "DR B", "ΔDR", "RMS A", "RMS B", "ΔRMS", "Peak A", "Peak B", "ΔPeak", "LUFS A", "LUFS B", "ΔLUFS"
+    )?;
+    writeln!(f, "  {}", "─".repeat(113))?;
+    for (ta, tb) in &pairing.pairs {
+        let delta_dr = if opts.precision == 0 {
+            format!("{:+}", tb.dr - ta.dr)
+        } else {
+            format!("{:+.*}", opts.precision, tb.dr_exact - ta.dr_exact)
+        };
+        writeln!(
+            f,
+            "  {:<6} {:<6} {:>5}    {:>+6.1}  {:>+6.1}  {:>+6.1}    {:>+6.2}  {:>+6.2}  {:>+6.2}    {:>+6.1}  {:>+6.1}  {:>+6.1}  {}",
+            dr(ta.dr_exact),
+            dr(tb.dr_exact),
+            delta_dr,
+            ta.rms_db,
+            tb.rms_db,
+            tb.rms_db - ta.rms_db,
+            ta.peak_db,
+            tb.peak_db,
+            tb.peak_db - ta.peak_db,
+            ta.lufs,
+            tb.lufs,
+            tb.lufs - ta.lufs,
+            ta.filename
+        )?;
+    }
+    writeln!(f, "  {}", "─".repeat(113))?;
+    writeln!(f, "  Δ = B − A: a negative ΔDR with a positive ΔLUFS is a louder, more compressed B.")?;
+    writeln!(f)?;
+
+    if !pairing.pairs.is_empty() {
+        let (paired_a, paired_b): (Vec<&TrackResult>, Vec<&TrackResult>) = pairing.pairs.iter().copied().unzip();
+        // Mean of the rounded track values, as in the report, unless decimals are shown
+        let value = |t: &TrackResult| if opts.precision == 0 { t.dr as f64 } else { t.dr_exact };
+        let mean = |tracks: &[&TrackResult]| tracks.iter().map(|t| value(t)).sum::<f64>() / tracks.len() as f64;
+        let (dr_a, dr_b) = (mean(&paired_a), mean(&paired_b));
+        let (lufs_a, lufs_b) = (album_lufs(&paired_a), album_lufs(&paired_b));
+
+        writeln!(f, "  Summary (paired tracks)")?;
+        writeln!(f, "  ───────────────────────────────")?;
+        writeln!(f, "  Tracks paired : {}", pairing.pairs.len())?;
+        writeln!(f, "  Album DR      : {}  →  {}  ({:+.1})", dr(dr_a), dr(dr_b), dr_b - dr_a)?;
+        writeln!(f, "  Album LUFS    : {:+.1}  →  {:+.1}  ({:+.1} LU)", lufs_a, lufs_b, lufs_b - lufs_a)?;
+        writeln!(f)?;
+    }
+
+    if !pairing.only_a.is_empty() || !pairing.only_b.is_empty() {
+        writeln!(f, "  Unmatched")?;
+        writeln!(f, "  ───────────────────────────────")?;
+        for t in &pairing.only_a {
+            writeln!(f, "  only in A : {}  ({})", t.filename, dr(t.dr_exact))?;
+        }
+        for t in &pairing.only_b {
+            writeln!(f, "  only in B : {}  ({})", t.filename, dr(t.dr_exact))?;
+        }
+        writeln!(f)?;
+    }
+
+    if !errors.is_empty() {
+        writeln!(f, "  Errors")?;
+        writeln!(f, "  ───────────────────────────────")?;
+        for (name, err) in errors {
+            writeln!(f, "  ✗ {} — {}", name, err)?;
+        }
+        writeln!(f)?;
+    }
+
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f, "  DR Loudness Standard — https://www.dynamicrange.de")?;
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+
+    f.flush()
+}
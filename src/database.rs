@@ -0,0 +1,171 @@
+// ─── SQLite library database ──────────────────────────────────────────────────
+//
+// `--db library.sqlite` upserts one row per track into a `tracks` table, so
+// folders scanned at different times build up one queryable library:
+//
+//     SELECT folder, avg(dr) FROM tracks GROUP BY folder ORDER BY 2;
+//
+// Rows are keyed by the file's absolute path and a hash of its audio (the
+// STREAMINFO MD5, or an FNV-1a hash of the file when the encoder left that
+// unset). Rescanning an unchanged file replaces its row; a re-encoded or
+// retagged-and-rewritten file replaces the row of its old hash too.
+// `--missing-only` reads the paths and hashes back to skip unchanged files.
+//
+// The statements are fed to the `sqlite3` command-line shell, which must be on
+// the PATH.
+
+use crate::{loudness, rating, Algorithm, TrackResult};
+use chrono::Local;
+use clap::ValueEnum;
+use std::io::Write;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS tracks (
+    path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    file TEXT NOT NULL,
+    scanned_at TEXT NOT NULL,
+    algorithm TEXT NOT NULL,
+    dr INTEGER NOT NULL,
+    dr_exact REAL NOT NULL,
+    grade TEXT NOT NULL,
+    peak_db REAL,
+    true_peak_db REAL,
+    rms_db REAL,
+    crest_db REAL,
+    lufs REAL,
+    plr_db REAL,
+    replaygain_db REAL,
+    duration_secs REAL,
+    sample_rate INTEGER,
+    bit_depth INTEGER,
+    channels INTEGER,
+    clipped_regions INTEGER,
+    intersample_overs INTEGER,
+    lossy_suspect INTEGER,
+    correlation REAL,
+    PRIMARY KEY (path, hash)
+);";
+
+/// SQL literal for a number; NaN and infinities (silence) are NULL.
+fn real(x: f64) -> String {
+    if x.is_finite() { format!("{:.4}", x) } else { "NULL".to_string() }
+}
+
+fn text(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn audio_hash(audio_md5: &[u8; 16], path: &Path) -> std::io::Result<String> {
+    if *audio_md5 != [0; 16] {
+        return Ok(audio_md5.iter().map(|b| format!("{:02x}", b)).collect());
+    }
+    Ok(format!("fnv1a:{:016x}", fnv1a(&std::fs::read(path)?)))
+}
+
+fn track_hash(t: &TrackResult, path: &Path) -> std::io::Result<String> {
+    audio_hash(&t.audio_md5, path)
+}
+
+/// Runs the sqlite3 shell on the database at `db_path` with `sql` as input,
+/// returning what it prints.
+fn sqlite3(db_path: &Path, sql: &str) -> std::io::Result<String> {
+    let mut child = Command::new("sqlite3")
+        .arg("-bail")
+        .arg(db_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("cannot run sqlite3 ({})", e)))?;
+    child.stdin.take().expect("piped stdin").write_all(sql.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(std::io::Error::other(format!("sqlite3: {}", message)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The tracks already in the database at `db_path`: their audio hash by
+/// absolute path. A database that doesn't exist yet has none.
+pub fn scanned_tracks(db_path: &Path) -> std::io::Result<HashMap<PathBuf, String>> {
+    if !db_path.exists() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlite3(db_path, &format!("{}\n.separator \"\\t\"\nSELECT path, hash FROM tracks;\n", SCHEMA))?;
+    Ok(rows
+        .lines()
+        .filter_map(|row| row.rsplit_once('\t'))
+        .map(|(path, hash)| (PathBuf::from(path), hash.to_string()))
+        .collect())
+}
+
+/// Whether `scanned` holds the FLAC file at `path` with its current audio.
+pub fn is_scanned(path: &Path, scanned: &HashMap<PathBuf, String>) -> bool {
+    let Ok(path) = path.canonicalize() else { return false };
+    let Some(hash) = scanned.get(&path) else { return false };
+    let Ok(reader) = claxon::FlacReader::open(&path) else { return false };
+    audio_hash(&reader.streaminfo().md5sum, &path).is_ok_and(|current| current == *hash)
+}
+
+pub fn write_database(
+    tracks: &[&TrackResult],
+    folder: &Path,
+    db_path: &Path,
+    algorithm: Algorithm,
+) -> std::io::Result<()> {
+    let folder = folder.canonicalize()?;
+    let folder_str = folder.display().to_string();
+    let scanned_at = Local::now().to_rfc3339();
+    let algorithm = algorithm.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+
+    let mut sql = String::new();
+    sql.push_str(SCHEMA);
+    sql.push_str("\nBEGIN;\n");
+    for t in tracks {
+        let path = folder.join(&t.filename);
+        let path_str = path.display().to_string();
+        let hash = track_hash(t, &path)?;
+        // A changed file keeps its path but not its hash: drop the stale row
+        sql.push_str(&format!("DELETE FROM tracks WHERE path = {} AND hash <> {};\n", text(&path_str), text(&hash)));
+        sql.push_str(&format!(
+            "INSERT OR REPLACE INTO tracks VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+            text(&path_str),
+            text(&hash),
+            text(&folder_str),
+            text(&t.filename),
+            text(&scanned_at),
+            text(&algorithm),
+            t.dr,
+            real(t.dr_exact),
+            text(rating(t.dr).0),
+            real(t.peak_db),
+            real(t.true_peak_db),
+            real(t.rms_db),
+            real(t.crest_db),
+            real(t.lufs),
+            real(t.true_peak_db - t.lufs),
+            real(loudness::replaygain(t.lufs)),
+            real(t.duration_secs),
+            t.sample_rate,
+            t.bit_depth,
+            t.channels,
+            t.clipping.regions.len(),
+            t.intersample_overs.len(),
+            t.spectrum.lossy_suspect as u8,
+            t.correlation.map_or("NULL".to_string(), real),
+        ));
+    }
+    sql.push_str("COMMIT;\n");
+
+    sqlite3(db_path, &sql)?;
+    Ok(())
+}
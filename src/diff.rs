@@ -0,0 +1,208 @@
+// ─── Report diff ──────────────────────────────────────────────────────────────
+//
+// `diff OLD NEW` compares two JSON (or NDJSON) reports of the same folder and
+// lists the files added and removed and the metrics and tags that changed —
+// after retagging or re-ripping, say. Tracks are paired by file name, then
+// the leftover ones by disc and track number (renamed files). Nothing is
+// decoded; a folder stands for its dr_report.json.
+
+use crate::create_text_output;
+use crate::json::Json;
+use chrono::Local;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The track fields compared, with their labels.
+const FIELDS: [(&str, &str); 19] = [
+    ("error", "Error"),
+    ("dr_exact", "DR"),
+    ("peak_db", "Peak SP"),
+    ("true_peak_db", "Peak TP"),
+    ("rms_db", "RMS"),
+    ("crest_db", "Crest"),
+    ("lufs", "LUFS"),
+    ("noise_floor_db", "Floor"),
+    ("duration_secs", "Duration"),
+    ("sample_rate", "Sample rate"),
+    ("bit_depth", "Bit depth"),
+    ("channels", "Channels"),
+    ("clipped_regions", "Clipping"),
+    ("intersample_overs", "TP overs"),
+    ("lossy_suspect", "Lossy"),
+    ("tags.artist", "Artist"),
+    ("tags.title", "Title"),
+    ("tags.album", "Album"),
+    ("tags.track_number", "Track no."),
+];
+
+/// A report read back: its tracks (failed files included, with an "error"
+/// field) and, from a JSON document, the album and the run date.
+pub struct Report {
+    pub path: PathBuf,
+    tracks: Vec<Json>,
+    album: Option<Json>,
+    generated: Option<String>,
+}
+
+pub fn read_report(path: &Path) -> Result<Report, String> {
+    let path = if path.is_dir() { path.join("dr_report.json") } else { path.to_path_buf() };
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let not_json = || format!("{}: not a JSON or NDJSON report (write one with --format json)", path.display());
+
+    if let Ok(Json::Object(fields)) = Json::parse(&text) {
+        let mut report = Report { path: path.clone(), tracks: Vec::new(), album: None, generated: None };
+        let mut is_document = false;
+        for (key, value) in fields {
+            match (key.as_str(), value) {
+                ("tracks", Json::Array(tracks)) => {
+                    report.tracks.extend(tracks);
+                    is_document = true;
+                }
+                ("errors", Json::Array(errors)) => report.tracks.extend(errors),
+                ("album", album) => report.album = Some(album),
+                ("meta", meta) => report.generated = meta.get("generated").and_then(Json::string).map(str::to_string),
+                _ => {}
+            }
+        }
+        return if is_document { Ok(report) } else { Err(not_json()) };
+    }
+
+    // NDJSON: one track (or failed file) per line
+    let tracks = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Json::parse(line).ok().filter(|t| t.get("file").is_some()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(not_json)?;
+    Ok(Report { path, tracks, album: None, generated: None })
+}
+
+fn file(track: &Json) -> &str {
+    track.get("file").and_then(Json::string).unwrap_or("")
+}
+
+/// Disc and track number from the tags, for pairing renamed files.
+fn position(track: &Json) -> Option<(Option<i64>, i64)> {
+    let number = |path| track.path(path).and_then(Json::number).map(|n| n as i64);
+    Some((number("tags.disc_number"), number("tags.track_number")?))
+}
+
+fn dr_text(track: &Json) -> String {
+    match track.get("dr").and_then(Json::number) {
+        Some(dr) => format!("DR{}", dr),
+        None => "failed".to_string(),
+    }
+}
+
+/// "old → new", with the difference for numbers.
+fn change(old: Option<&Json>, new: Option<&Json>) -> String {
+    let text = |v: Option<&Json>| v.map_or("–".to_string(), Json::text);
+    match (old.and_then(Json::number), new.and_then(Json::number)) {
+        (Some(a), Some(b)) => format!("{} → {}  ({:+})", a, b, ((b - a) * 1000.0).round() / 1000.0),
+        _ => format!("{} → {}", text(old), text(new)),
+    }
+}
+
+pub fn write_diff(old: &Report, new: &Report, output_path: &Path, ascii: bool) -> std::io::Result<()> {
+    let mut f = BufWriter::new(create_text_output(output_path, ascii)?);
+
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f, "  Dynamic Range Report Diff")?;
+    writeln!(f, "  Generated : {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    for (label, report) in [("Old", old), ("New", new)] {
+        match &report.generated {
+            Some(run) => writeln!(f, "  {}       : {}  (run {})", label, report.path.display(), run)?,
+            None => writeln!(f, "  {}       : {}", label, report.path.display())?,
+        }
+    }
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f)?;
+
+    // Pair by name, then the rest by disc and track number
+    let mut pairs: Vec<(&Json, &Json)> = Vec::new();
+    let mut added: Vec<&Json> = new.tracks.iter().collect();
+    let mut removed: Vec<&Json> = Vec::new();
+    for o in &old.tracks {
+        match added.iter().position(|n| file(n) == file(o)) {
+            Some(i) => pairs.push((o, added.remove(i))),
+            None => removed.push(o),
+        }
+    }
+    removed.retain(|o| {
+        let found = position(o).and_then(|p| added.iter().position(|n| position(n) == Some(p)));
+        match found {
+            Some(i) => {
+                pairs.push((o, added.remove(i)));
+                false
+            }
+            None => true,
+        }
+    });
+
+    let mut differences = !added.is_empty() || !removed.is_empty();
+
+    if let (Some(a), Some(b)) = (&old.album, &new.album) {
+        let album_fields = [("dr", "Album DR"), ("tracks", "Tracks"), ("lufs", "LUFS")];
+        let changed: Vec<_> = album_fields.iter().filter(|(key, _)| a.get(key) != b.get(key)).collect();
+        if !changed.is_empty() {
+            differences = true;
+            writeln!(f, "  Album")?;
+            writeln!(f, "  ───────────────────────────────")?;
+            for (key, label) in changed {
+                writeln!(f, "  {:<11}: {}", label, change(a.get(key), b.get(key)))?;
+            }
+            writeln!(f)?;
+        }
+    }
+
+    for (title, sign, tracks) in [("Added", '+', &added), ("Removed", '-', &removed)] {
+        if tracks.is_empty() {
+            continue;
+        }
+        writeln!(f, "  {}", title)?;
+        writeln!(f, "  ───────────────────────────────")?;
+        for t in tracks.iter() {
+            writeln!(f, "  {} {}  ({})", sign, file(t), dr_text(t))?;
+        }
+        writeln!(f)?;
+    }
+
+    let mut unchanged = 0;
+    let mut changed_header = false;
+    for (o, n) in &pairs {
+        let changes: Vec<(&str, String)> = FIELDS
+            .iter()
+            .filter(|(path, _)| o.path(path) != n.path(path))
+            .map(|(path, label)| (*label, change(o.path(path), n.path(path))))
+            .collect();
+        let renamed = file(o) != file(n);
+        if changes.is_empty() && !renamed {
+            unchanged += 1;
+            continue;
+        }
+        differences = true;
+        if !changed_header {
+            writeln!(f, "  Changed")?;
+            writeln!(f, "  ───────────────────────────────")?;
+            changed_header = true;
+        }
+        if renamed {
+            writeln!(f, "  ~ {} → {}  (renamed)", file(o), file(n))?;
+        } else {
+            writeln!(f, "  ~ {}", file(o))?;
+        }
+        for (label, text) in changes {
+            writeln!(f, "      {:<12}{}", label, text)?;
+        }
+    }
+    if changed_header {
+        writeln!(f)?;
+    }
+
+    if differences {
+        writeln!(f, "  Unchanged : {} track(s)", unchanged)?;
+    } else {
+        writeln!(f, "  No differences ({} track(s))", unchanged)?;
+    }
+    f.flush()
+}
@@ -0,0 +1,670 @@
+// ─── Structured report ────────────────────────────────────────────────────────
+//
+// The machine-readable formats all serialise the same document: tool metadata,
+// one object per track, the album summary and the files that failed. It is
+// built once as a small value tree so every format carries the same fields
+// under the same names.
+//
+// The JSON and NDJSON layouts are described by schema/dr-report.schema.json
+// (printed by `dr-measure schema`); SCHEMA_VERSION goes up with any change
+// that could break a consumer — a field removed, renamed or retyped.
+
+use crate::export::{csv_field, json_string};
+use crate::flacmeta;
+use crate::{album_dr, album_dr_values, album_groups, album_levels, album_lufs, album_tags, album_year, append_output, create_output, disc_groups, loudness, rating, AlbumMethod, ReportOptions, TrackResult};
+use chrono::Local;
+use clap::ValueEnum;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// `schema_version` in the report metadata and on every NDJSON line.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON Schema of the JSON and NDJSON reports.
+pub const SCHEMA: &str = include_str!("../schema/dr-report.schema.json");
+
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// Non-finite numbers (the loudness of digital silence) become null.
+    Num(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(n: u32) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Num(x)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Value::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Value {
+    /// Plain-text rendering of a leaf value; arrays of leaves are joined
+    /// with ';'. Null is empty.
+    pub fn to_text(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::Num(x) if x.is_finite() => x.to_string(),
+            Value::Num(_) => String::new(),
+            Value::Str(s) => s.clone(),
+            Value::Array(items) => items.iter().map(Value::to_text).collect::<Vec<_>>().join(";"),
+            Value::Object(_) => String::new(),
+        }
+    }
+
+    /// Leaf values keyed by their dotted path ("mid_side.mid.dr").
+    fn flatten(&self, prefix: &str, out: &mut Vec<(String, String)>) {
+        match self {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    let path = if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+                    value.flatten(&path, out);
+                }
+            }
+            leaf => out.push((prefix.to_string(), leaf.to_text())),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Rounds to `decimals` places so the output doesn't carry float noise.
+fn num(x: f64, decimals: i32) -> Value {
+    let scale = 10f64.powi(decimals);
+    // + 0.0 turns -0 into 0
+    Value::Num((x * scale).round() / scale + 0.0)
+}
+
+fn track(t: &TrackResult) -> Value {
+    let mid_side = t.mid_side.map(|levels| {
+        let [mid, side] = levels.map(|l| {
+            Value::Object(vec![
+                ("dr", num(l.dr, 2)),
+                ("peak_db", num(l.peak_db, 2)),
+                ("rms_db", num(l.rms_db, 2)),
+            ])
+        });
+        Value::Object(vec![("mid", mid), ("side", side)])
+    });
+
+    Value::Object(vec![
+        ("file", t.filename.as_str().into()),
+        (
+            "tags",
+            Value::Object(vec![
+                ("artist", t.tags.artist.clone().into()),
+                ("album_artist", t.tags.album_artist.clone().into()),
+                ("title", t.tags.title.clone().into()),
+                ("album", t.tags.album.clone().into()),
+                ("track_number", t.tags.track_number.into()),
+                ("disc_number", t.tags.disc_number.into()),
+                ("year", t.tags.year.into()),
+            ]),
+        ),
+        ("dr", t.dr.into()),
+        ("dr_exact", num(t.dr_exact, 2)),
+        ("grade", rating(t.dr).0.into()),
+        ("channel_dr", t.channel_dr.iter().map(|&dr| num(dr, 2)).collect::<Vec<_>>().into()),
+        ("k_dr", t.k_dr.map(|dr| num(dr, 2)).into()),
+        ("peak_db", num(t.peak_db, 2)),
+        ("true_peak_db", num(t.true_peak_db, 2)),
+        ("rms_db", num(t.rms_db, 2)),
+        ("crest_db", num(t.crest_db, 2)),
+        ("noise_floor_db", num(t.noise_floor_db, 1)),
+        ("lufs", num(t.lufs, 2)),
+        ("plr_db", num(t.true_peak_db - t.lufs, 2)),
+        ("replaygain_db", num(loudness::replaygain(t.lufs), 2)),
+        ("sample_peak", num(t.sample_peak, 6)),
+        ("duration_secs", num(t.duration_secs, 3)),
+        ("sample_rate", t.sample_rate.into()),
+        ("bit_depth", t.bit_depth.into()),
+        ("effective_bits", t.effective_bits.into()),
+        ("channels", t.channels.into()),
+        ("file_size", Value::Int(t.file_size as i64)),
+        ("bitrate_kbps", num(t.bitrate_kbps(), 1)),
+        ("vendor", t.vendor.clone().into()),
+        ("integrity", t.integrity.as_ref().map(|i| i.key()).into()),
+        ("clipped_regions", t.clipping.regions.len().into()),
+        ("clipped_secs", num(t.clipping.clipped_frames as f64 / t.sample_rate as f64, 3)),
+        ("intersample_overs", t.intersample_overs.len().into()),
+        ("clicks", t.clicks.len().into()),
+        ("dc_offset", t.dc_offset.iter().map(|&dc| num(dc, 6)).collect::<Vec<_>>().into()),
+        ("subsonic_db", t.subsonic_db.map(|db| num(db, 1)).into()),
+        ("correlation", t.correlation.map(|c| num(c, 3)).into()),
+        ("balance_db", t.balance_db.map(|b| num(b, 2)).into()),
+        ("spectral_cutoff_hz", num(t.spectrum.cutoff_hz, 0)),
+        ("lossy_suspect", t.spectrum.lossy_suspect.into()),
+        ("upsampled_from", t.spectrum.upsampled_from.into()),
+        ("leading_silence_secs", num(t.leading_silence_secs, 3)),
+        ("trailing_silence_secs", num(t.trailing_silence_secs, 3)),
+        ("mid_side", mid_side.into()),
+    ])
+}
+
+fn album(tracks: &[&TrackResult], opts: &ReportOptions) -> Value {
+    if tracks.is_empty() {
+        return Value::Null;
+    }
+    let (dr_mean, dr_pooled) = album_dr_values(tracks, opts);
+    let headline = match opts.album_method {
+        AlbumMethod::Pooled => dr_pooled,
+        AlbumMethod::Mean => dr_mean,
+    };
+    let dr = opts.dr.algorithm.round(headline);
+    let (grade, label) = rating(dr);
+    let lufs = album_lufs(tracks);
+    let true_peak = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+    let sample_peak = tracks.iter().map(|t| t.sample_peak).fold(0.0f64, f64::max);
+    let (peak_db, rms_db) = album_levels(tracks);
+
+    let mut fields = vec![
+        ("tracks", tracks.len().into()),
+        ("dr", dr.into()),
+        ("dr_mean", num(dr_mean, 2)),
+        ("dr_pooled", num(dr_pooled, 2)),
+        ("dr_method", name(opts.album_method).into()),
+        ("dr_min", tracks.iter().map(|t| t.dr).min().into()),
+        ("dr_max", tracks.iter().map(|t| t.dr).max().into()),
+        ("grade", grade.into()),
+        ("rating", label.into()),
+        ("duration_secs", num(tracks.iter().map(|t| t.duration_secs).sum(), 3)),
+        ("file_size", Value::Int(tracks.iter().map(|t| t.file_size).sum::<u64>() as i64)),
+        ("peak_db", num(peak_db, 2)),
+        ("rms_db", num(rms_db, 2)),
+        ("lufs", num(lufs, 2)),
+        ("true_peak_db", num(true_peak, 2)),
+        ("replaygain_db", num(loudness::replaygain(lufs), 2)),
+        ("sample_peak", num(sample_peak, 6)),
+    ];
+    // A mixed folder's discs are listed under their own album
+    match album_groups(tracks) {
+        Some(albums) => {
+            let albums = albums.iter().map(|(_, group)| {
+                let (mean, pooled) = album_dr_values(group, opts);
+                let (artist, album) = album_tags(group.iter().copied()).unzip();
+                let mut fields = vec![
+                    ("artist", artist.into()),
+                    ("album", album.into()),
+                    ("year", album_year(group.iter().copied()).into()),
+                    ("tracks", group.len().into()),
+                    ("dr", opts.dr.algorithm.round(album_dr(group, opts)).into()),
+                    ("dr_mean", num(mean, 2)),
+                    ("dr_pooled", num(pooled, 2)),
+                ];
+                if let Some(discs) = disc_groups(group) {
+                    fields.push(("discs", disc_values(&discs, opts)));
+                }
+                Value::Object(fields)
+            });
+            fields.push(("albums", Value::Array(albums.collect())));
+        }
+        None => {
+            if let Some(discs) = disc_groups(tracks) {
+                fields.push(("discs", disc_values(&discs, opts)));
+            }
+        }
+    }
+    Value::Object(fields)
+}
+
+/// The DR of each disc of a multi-disc album.
+fn disc_values(discs: &[(Option<u32>, Vec<&TrackResult>)], opts: &ReportOptions) -> Value {
+    let discs = discs.iter().map(|(disc, group)| {
+        let (mean, pooled) = album_dr_values(group, opts);
+        Value::Object(vec![
+            ("disc", (*disc).into()),
+            ("tracks", group.len().into()),
+            ("dr", opts.dr.algorithm.round(album_dr(group, opts)).into()),
+            ("dr_mean", num(mean, 2)),
+            ("dr_pooled", num(pooled, 2)),
+        ])
+    });
+    Value::Array(discs.collect())
+}
+
+/// Command-line spelling of an option value.
+fn name(value: impl ValueEnum) -> String {
+    value.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+}
+
+fn meta(folder: &Path, opts: &ReportOptions) -> Value {
+    let folder = folder.canonicalize().unwrap_or_else(|_| folder.to_path_buf());
+    let mut fields = vec![
+        ("schema_version", SCHEMA_VERSION.into()),
+        ("tool", env!("CARGO_PKG_NAME").into()),
+        ("version", env!("CARGO_PKG_VERSION").into()),
+        ("generated", Local::now().to_rfc3339().into()),
+        ("folder", folder.display().to_string().into()),
+        ("algorithm", name(opts.dr.algorithm).into()),
+        ("block_seconds", opts.dr.block_seconds.into()),
+        ("top_ratio", opts.dr.top_ratio.into()),
+        ("nth_peak", opts.dr.nth_peak.into()),
+        ("standard_params", opts.dr.is_standard().into()),
+        ("quick", opts.quick.into()),
+    ];
+    if let Some(release) = &opts.release {
+        fields.push((
+            "release",
+            Value::Object(vec![
+                ("id", release.id.clone().into()),
+                ("title", release.title.clone().into()),
+                ("country", release.country.clone().into()),
+                ("year", release.year.into()),
+                ("label", release.label.clone().into()),
+                ("catalog_number", release.catalog_number.clone().into()),
+            ]),
+        ));
+    }
+    Value::Object(fields)
+}
+
+pub fn build(results: &[Result<TrackResult, (String, String)>], folder: &Path, opts: &ReportOptions) -> Value {
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    let errors: Vec<Value> = results
+        .iter()
+        .filter_map(|r| r.as_ref().err())
+        .map(|(file, error)| Value::Object(vec![("file", file.as_str().into()), ("error", error.as_str().into())]))
+        .collect();
+
+    Value::Object(vec![
+        ("meta", meta(folder, opts)),
+        ("tracks", Value::Array(tracks.iter().map(|t| track(t)).collect())),
+        ("album", album(&tracks, opts)),
+        ("errors", Value::Array(errors)),
+    ])
+}
+
+// ─── CSV ──────────────────────────────────────────────────────────────────────
+
+/// One row per track. Nested objects become dotted columns; a field that is
+/// null for some tracks and an object for others only gets the dotted ones.
+/// `selected` picks and orders the columns (top-level track fields); by
+/// default every field is written.
+/// With `append`, only the rows are added to the existing file at `path`.
+pub fn write_csv(document: &Value, path: &Path, selected: Option<Vec<&str>>, append: bool) -> std::io::Result<()> {
+    let rows: Vec<Vec<(String, String)>> = match document.get("tracks") {
+        Some(Value::Array(tracks)) => tracks
+            .iter()
+            .map(|t| {
+                let mut row = Vec::new();
+                t.flatten("", &mut row);
+                row
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut columns: Vec<&str> = Vec::new();
+    for (key, _) in rows.iter().flatten() {
+        if !columns.contains(&key.as_str()) {
+            columns.push(key);
+        }
+    }
+    let parents: Vec<&str> = columns
+        .iter()
+        .copied()
+        .filter(|c| columns.iter().any(|other| other.len() > c.len() && other.starts_with(c) && other[c.len()..].starts_with('.')))
+        .collect();
+    columns.retain(|c| !parents.contains(c));
+    if let Some(selected) = selected {
+        columns = selected;
+    }
+
+    let mut f = BufWriter::new(if append { append_output(path)? } else { create_output(path)? });
+    if !append {
+        writeln!(f, "{}", columns.iter().map(|c| csv_field(c)).collect::<Vec<_>>().join(","))?;
+    }
+    for row in &rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|c| row.iter().find(|(k, _)| k == c).map(|(_, v)| csv_field(v)).unwrap_or_default())
+            .collect();
+        writeln!(f, "{}", cells.join(","))?;
+    }
+    f.flush()
+}
+
+// ─── JSON ─────────────────────────────────────────────────────────────────────
+
+fn write_json_value(out: &mut String, value: &Value, indent: usize) {
+    let pad = |n: usize| "  ".repeat(n);
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Int(n) => out.push_str(&n.to_string()),
+        Value::Num(x) if x.is_finite() => out.push_str(&x.to_string()),
+        Value::Num(_) => out.push_str("null"),
+        Value::Str(s) => out.push_str(&json_string(s)),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            // Arrays of plain values stay on one line
+            if items.iter().all(|v| !matches!(v, Value::Array(_) | Value::Object(_))) {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_json_value(out, item, indent);
+                }
+                out.push(']');
+                return;
+            }
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad(indent + 1));
+                write_json_value(out, item, indent + 1);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad(indent));
+            out.push(']');
+        }
+        Value::Object(fields) if fields.is_empty() => out.push_str("{}"),
+        Value::Object(fields) => {
+            out.push_str("{\n");
+            for (i, (key, item)) in fields.iter().enumerate() {
+                out.push_str(&pad(indent + 1));
+                out.push_str(&json_string(key));
+                out.push_str(": ");
+                write_json_value(out, item, indent + 1);
+                out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad(indent));
+            out.push('}');
+        }
+    }
+}
+
+/// Single-line JSON, for NDJSON.
+fn write_json_compact(out: &mut String, value: &Value) {
+    match value {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_compact(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push('{');
+            for (i, (key, item)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&json_string(key));
+                out.push(':');
+                write_json_compact(out, item);
+            }
+            out.push('}');
+        }
+        leaf => write_json_value(out, leaf, 0),
+    }
+}
+
+/// One NDJSON line: the track object, or `{"file", "error"}` for a failure.
+pub fn ndjson_line(result: &Result<TrackResult, (String, String)>) -> String {
+    let mut fields = vec![("schema_version", SCHEMA_VERSION.into())];
+    match result {
+        Ok(t) => {
+            if let Value::Object(track) = track(t) {
+                fields.extend(track);
+            }
+        }
+        Err((file, error)) => fields.extend([("file", file.as_str().into()), ("error", error.as_str().into())]),
+    }
+    let value = Value::Object(fields);
+    let mut out = String::new();
+    write_json_compact(&mut out, &value);
+    out
+}
+
+/// All results as NDJSON at once, for reports that aren't streamed.
+pub fn write_ndjson(results: &[Result<TrackResult, (String, String)>], path: &Path) -> std::io::Result<()> {
+    let mut f = BufWriter::new(create_output(path)?);
+    for result in results {
+        writeln!(f, "{}", ndjson_line(result))?;
+    }
+    f.flush()
+}
+
+pub fn write_json(document: &Value, path: &Path) -> std::io::Result<()> {
+    let mut out = String::new();
+    write_json_value(&mut out, document, 0);
+    out.push('\n');
+    let mut f = BufWriter::new(create_output(path)?);
+    f.write_all(out.as_bytes())?;
+    f.flush()
+}
+
+/// `01 - Track.dr.json` for `01 - Track.flac`.
+pub fn sidecar_path(folder: &Path, filename: &str) -> PathBuf {
+    let stem = Path::new(filename).file_stem().map_or(filename.into(), |s| s.to_string_lossy());
+    folder.join(format!("{}.dr.json", stem))
+}
+
+/// Writes each track's object, with the report metadata, as JSON next to its
+/// audio file.
+pub fn write_sidecars(tracks: &[&TrackResult], folder: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    for t in tracks {
+        let document = Value::Object(vec![("meta", meta(folder, opts)), ("track", track(t))]);
+        write_json(&document, &sidecar_path(folder, &t.filename))?;
+    }
+    Ok(())
+}
+
+/// Vorbis comment holding a track's results after `--embed`.
+pub const EMBED_TAG: &str = "DR_MEASURE";
+
+/// Stores each track's sidecar document, as compact JSON, in a DR_MEASURE
+/// Vorbis comment of its FLAC file, replacing an earlier one.
+pub fn embed_results(tracks: &[&TrackResult], folder: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    for t in tracks {
+        let mut json = String::new();
+        write_json_compact(&mut json, &Value::Object(vec![("meta", meta(folder, opts)), ("track", track(t))]));
+        let path = folder.join(&t.filename);
+        flacmeta::edit_comments(&path, |comments| {
+            comments.retain(|(key, _)| key != EMBED_TAG);
+            comments.push((EMBED_TAG.to_string(), json));
+        })
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", t.filename, e)))?;
+    }
+    Ok(())
+}
+
+// ─── XML ──────────────────────────────────────────────────────────────────────
+//
+// Every field is an element named after its JSON key. Array items are
+// <track> and <error> inside <tracks> and <errors>, <value> elsewhere; null is
+// an empty element.
+
+pub fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            // Control characters other than tab and newlines aren't allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => out.push('\u{FFFD}'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn xml_item_name(array: &str) -> &'static str {
+    match array {
+        "tracks" => "track",
+        "errors" => "error",
+        _ => "value",
+    }
+}
+
+fn write_xml_element(out: &mut String, name: &str, value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Null => out.push_str(&format!("{}<{}/>\n", pad, name)),
+        Value::Num(x) if !x.is_finite() => out.push_str(&format!("{}<{}/>\n", pad, name)),
+        Value::Array(items) if items.is_empty() => out.push_str(&format!("{}<{}/>\n", pad, name)),
+        Value::Object(fields) if fields.is_empty() => out.push_str(&format!("{}<{}/>\n", pad, name)),
+        Value::Array(items) => {
+            out.push_str(&format!("{}<{}>\n", pad, name));
+            for item in items {
+                write_xml_element(out, xml_item_name(name), item, indent + 1);
+            }
+            out.push_str(&format!("{}</{}>\n", pad, name));
+        }
+        Value::Object(fields) => {
+            out.push_str(&format!("{}<{}>\n", pad, name));
+            for (key, item) in fields {
+                write_xml_element(out, key, item, indent + 1);
+            }
+            out.push_str(&format!("{}</{}>\n", pad, name));
+        }
+        leaf => out.push_str(&format!("{}<{}>{}</{}>\n", pad, name, xml_escape(&leaf.to_text()), name)),
+    }
+}
+
+pub fn write_xml(document: &Value, path: &Path) -> std::io::Result<()> {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_xml_element(&mut out, "dr-report", document, 0);
+    let mut f = BufWriter::new(create_output(path)?);
+    f.write_all(out.as_bytes())?;
+    f.flush()
+}
+
+// ─── YAML ─────────────────────────────────────────────────────────────────────
+//
+// Block style throughout, except lists of plain values, which stay inline.
+// Strings are double-quoted with JSON escapes, which YAML accepts as is.
+
+fn is_leaf(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => true,
+    }
+}
+
+/// Inline rendering of a leaf, or of a list of leaves.
+fn yaml_inline(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Num(x) if !x.is_finite() => "null".to_string(),
+        Value::Str(s) => json_string(s),
+        Value::Array(items) => format!("[{}]", items.iter().map(yaml_inline).collect::<Vec<_>>().join(", ")),
+        Value::Object(_) => "{}".to_string(),
+        leaf => leaf.to_text(),
+    }
+}
+
+fn inline_ok(value: &Value) -> bool {
+    is_leaf(value) || matches!(value, Value::Array(items) if items.iter().all(is_leaf))
+}
+
+fn write_yaml_fields(out: &mut String, fields: &[(&'static str, Value)], indent: usize, first_prefix: Option<&str>) {
+    for (i, (key, value)) in fields.iter().enumerate() {
+        // The first field of a list item shares the line with its "- "
+        let pad = match (i, first_prefix) {
+            (0, Some(prefix)) => prefix.to_string(),
+            _ => "  ".repeat(indent),
+        };
+        if inline_ok(value) {
+            out.push_str(&format!("{}{}: {}\n", pad, key, yaml_inline(value)));
+        } else {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            write_yaml_block(out, value, indent + 1);
+        }
+    }
+}
+
+fn write_yaml_block(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(fields) => write_yaml_fields(out, fields, indent, None),
+        Value::Array(items) => {
+            for item in items {
+                let prefix = format!("{}- ", "  ".repeat(indent));
+                match item {
+                    Value::Object(fields) if !fields.is_empty() => {
+                        write_yaml_fields(out, fields, indent + 1, Some(&prefix))
+                    }
+                    item if inline_ok(item) => out.push_str(&format!("{}{}\n", prefix, yaml_inline(item))),
+                    item => {
+                        out.push_str(&format!("{}-\n", "  ".repeat(indent)));
+                        write_yaml_block(out, item, indent + 1);
+                    }
+                }
+            }
+        }
+        leaf => out.push_str(&format!("{}{}\n", "  ".repeat(indent), yaml_inline(leaf))),
+    }
+}
+
+pub fn write_yaml(document: &Value, path: &Path) -> std::io::Result<()> {
+    let mut out = String::from("---\n");
+    write_yaml_block(&mut out, document, 0);
+    let mut f = BufWriter::new(create_output(path)?);
+    f.write_all(out.as_bytes())?;
+    f.flush()
+}
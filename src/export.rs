@@ -0,0 +1,341 @@
+// ─── Timeline exports ─────────────────────────────────────────────────────────
+//
+// Per-track time series written alongside the report for plotting. Each export
+// is CSV (one row per point, `file` column first) or, when the output path ends
+// in `.json`, an array of `{ "file": …, "points": [ … ] }` objects.
+//
+// Images, the `--export-blocks` CSVs and the `--labels` tracks are written
+// one file per track, named after the FLAC file.
+
+use crate::png::Image;
+use crate::loudness::{self, SUBBLOCK_SECONDS, SUBBLOCKS_PER_MOMENTARY, SUBBLOCKS_PER_SHORT_TERM};
+use crate::{channel_names, to_db, windowed_dr, BlockStats, DrParams, TrackResult};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// The fields of a CSV line, unquoted.
+pub fn csv_split(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// JSON has no infinities; silent stretches and windows that are not yet
+/// full are written as null.
+fn json_number(x: f64, decimals: usize) -> String {
+    if x.is_finite() {
+        format!("{:.*}", decimals, x)
+    } else {
+        "null".to_string()
+    }
+}
+
+/// CSV counterpart of `json_number`: an empty field.
+fn csv_number(x: f64, decimals: usize) -> String {
+    if x.is_finite() {
+        format!("{:.*}", decimals, x)
+    } else {
+        String::new()
+    }
+}
+
+enum Field {
+    Num(f64),
+    Text(String),
+}
+
+impl From<f64> for Field {
+    fn from(x: f64) -> Self {
+        Field::Num(x)
+    }
+}
+
+struct Series<'a> {
+    columns: &'a [&'a str],
+    /// Decimals for numeric fields
+    decimals: usize,
+    /// (time in seconds, fields) per point
+    points: Vec<(f64, Vec<Field>)>,
+}
+
+fn write_series<'a>(
+    path: &Path,
+    tracks: &[&'a TrackResult],
+    series: impl Fn(&'a TrackResult) -> Series<'a>,
+) -> std::io::Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    let json = is_json(path);
+
+    if json {
+        writeln!(f, "[")?;
+    }
+    for (i, t) in tracks.iter().enumerate() {
+        let s = series(t);
+        if json {
+            writeln!(f, "  {{")?;
+            writeln!(f, "    \"file\": {},", json_string(&t.filename))?;
+            writeln!(f, "    \"points\": [")?;
+            for (j, (time, values)) in s.points.iter().enumerate() {
+                let fields: Vec<String> = std::iter::once(format!("\"time\": {:.1}", time))
+                    .chain(
+                        s.columns
+                            .iter()
+                            .zip(values)
+                            .map(|(c, v)| {
+                                let v = match v {
+                                    Field::Num(x) => json_number(*x, s.decimals),
+                                    Field::Text(text) => json_string(text),
+                                };
+                                format!("{}: {}", json_string(c), v)
+                            }),
+                    )
+                    .collect();
+                let sep = if j + 1 < s.points.len() { "," } else { "" };
+                writeln!(f, "      {{ {} }}{}", fields.join(", "), sep)?;
+            }
+            writeln!(f, "    ]")?;
+            let sep = if i + 1 < tracks.len() { "," } else { "" };
+            writeln!(f, "  }}{}", sep)?;
+        } else {
+            if i == 0 {
+                writeln!(f, "file,time,{}", s.columns.join(","))?;
+            }
+            let name = csv_field(&t.filename);
+            for (time, values) in &s.points {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|v| match v {
+                        Field::Num(x) => csv_number(*x, s.decimals),
+                        Field::Text(text) => csv_field(text),
+                    })
+                    .collect();
+                writeln!(f, "{},{:.1},{}", name, time, values.join(","))?;
+            }
+        }
+    }
+    if json {
+        writeln!(f, "]")?;
+    }
+
+    f.flush()
+}
+
+/// Short-term PSR: max true peak over each 3 s window minus its short-term
+/// loudness, one point every 100 ms (time = end of the window).
+pub fn write_psr_timeline(tracks: &[&TrackResult], path: &Path) -> std::io::Result<()> {
+    write_series(path, tracks, |t| {
+        let short_term = loudness::short_term_loudness(&t.subblock_powers);
+        let points = short_term
+            .iter()
+            .enumerate()
+            .map(|(i, &lufs)| {
+                let window = &t.subblock_peaks[i..(i + SUBBLOCKS_PER_SHORT_TERM).min(t.subblock_peaks.len())];
+                let peak_db = to_db(window.iter().cloned().fold(0.0, f64::max));
+                let time = (i + SUBBLOCKS_PER_SHORT_TERM) as f64 * SUBBLOCK_SECONDS;
+                (time, vec![lufs.into(), peak_db.into(), (peak_db - lufs).into()])
+            })
+            .collect();
+        Series { columns: &["short_term_lufs", "peak_dbtp", "psr"], decimals: 2, points }
+    })
+}
+
+/// Momentary (400 ms) and short-term (3 s) loudness every 100 ms; the
+/// short-term value is empty until its window has filled.
+pub fn write_loudness_timeline(tracks: &[&TrackResult], path: &Path) -> std::io::Result<()> {
+    write_series(path, tracks, |t| {
+        let momentary = loudness::momentary_loudness(&t.subblock_powers);
+        let short_term = loudness::short_term_loudness(&t.subblock_powers);
+        let offset = SUBBLOCKS_PER_SHORT_TERM - SUBBLOCKS_PER_MOMENTARY;
+        let points = momentary
+            .iter()
+            .enumerate()
+            .map(|(i, &m)| {
+                let st = i.checked_sub(offset).map(|j| short_term[j]).unwrap_or(f64::NAN);
+                let time = (i + SUBBLOCKS_PER_MOMENTARY) as f64 * SUBBLOCK_SECONDS;
+                (time, vec![m.into(), st.into()])
+            })
+            .collect();
+        Series { columns: &["momentary_lufs", "short_term_lufs"], decimals: 2, points }
+    })
+}
+
+/// DR over a sliding window of `window` DR blocks, stepping one block (3 s)
+/// at a time (time = end of the window).
+pub fn write_dr_timeline(
+    tracks: &[&TrackResult],
+    path: &Path,
+    window: usize,
+    params: DrParams,
+) -> std::io::Result<()> {
+    write_series(path, tracks, |t| {
+        let rate = t.sample_rate.max(1) as f64;
+        let starts: Vec<u64> = t.channel_blocks.first().map(|b| b.iter().map(|b| b.start).collect()).unwrap_or_default();
+        let points = windowed_dr(&t.channel_blocks, window, params)
+            .into_iter()
+            .enumerate()
+            .map(|(i, dr)| (starts[i + window - 1] as f64 / rate + params.block_seconds, vec![dr.into()]))
+            .collect();
+        Series { columns: &["dr"], decimals: 2, points }
+    })
+}
+
+/// Every DR block of `t`, channel by channel: its index, start in the file
+/// (seconds), channel name and statistics.
+fn blocks(t: &TrackResult) -> Vec<(usize, f64, String, &BlockStats)> {
+    let names = channel_names(t.channel_blocks.len() as u32);
+    let rate = t.sample_rate.max(1) as f64;
+    let count = t.channel_blocks.first().map(|b| b.len()).unwrap_or(0);
+    let mut rows = Vec::new();
+    for i in 0..count {
+        for (ch, channel) in t.channel_blocks.iter().enumerate() {
+            let b = &channel[i];
+            rows.push((i, b.start as f64 / rate, names[ch].clone(), b));
+        }
+    }
+    rows
+}
+
+/// Raw DR block statistics: one point per block and channel, with the linear
+/// RMS (including the standard's factor of 2) and peak (time = block start).
+pub fn write_block_dump(tracks: &[&TrackResult], path: &Path) -> std::io::Result<()> {
+    write_series(path, tracks, |t| {
+        let points = blocks(t)
+            .into_iter()
+            .map(|(_, start, channel, b)| (start, vec![Field::Text(channel), b.rms.into(), b.peak.into()]))
+            .collect();
+        Series { columns: &["channel", "rms", "peak"], decimals: 6, points }
+    })
+}
+
+/// One `<track>.csv` per track in `dir` with every DR block's RMS and peak
+/// level, one row per block and channel.
+pub fn write_block_csvs(tracks: &[&TrackResult], dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for t in tracks {
+        let mut f = BufWriter::new(File::create(dir.join(Path::new(&t.filename).with_extension("csv")))?);
+        writeln!(f, "block,start,channel,rms_db,peak_db")?;
+        for (i, start, channel, b) in blocks(t) {
+            writeln!(f, "{},{:.3},{},{:.4},{:.4}", i, start, channel, to_db(b.rms), to_db(b.peak))?;
+        }
+        f.flush()?;
+    }
+    Ok(())
+}
+
+/// The `worst` longest clipped regions of every track, in time order, with
+/// their length in samples and milliseconds (time = region start).
+pub fn write_clip_log(tracks: &[&TrackResult], path: &Path, worst: usize) -> std::io::Result<()> {
+    write_series(path, tracks, |t| {
+        let rate = t.sample_rate as f64;
+        let points = t
+            .clipping
+            .worst(worst)
+            .iter()
+            .map(|r| {
+                let duration_ms = r.len as f64 * 1000.0 / rate;
+                (r.start as f64 / rate, vec![Field::Text(r.len.to_string()), duration_ms.into()])
+            })
+            .collect();
+        Series { columns: &["samples", "duration_ms"], decimals: 2, points }
+    })
+}
+
+/// One Audacity label track (`<track>.txt`, "start<TAB>end<TAB>label" in
+/// seconds) per track with clipped regions, intersample overs or clicks, for
+/// File → Import → Labels. Regions span their length; the rest are points.
+pub fn write_labels(tracks: &[&TrackResult], dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for t in tracks {
+        let rate = t.sample_rate.max(1) as f64;
+        let mut labels: Vec<(f64, f64, String)> = Vec::new();
+        for r in &t.clipping.regions {
+            labels.push((r.start as f64 / rate, (r.start + r.len) as f64 / rate, format!("clipping ({} samples)", r.len)));
+        }
+        for &frame in &t.intersample_overs {
+            labels.push((frame as f64 / rate, frame as f64 / rate, "intersample over".to_string()));
+        }
+        for &secs in &t.clicks {
+            labels.push((secs, secs, "click".to_string()));
+        }
+        if labels.is_empty() {
+            continue;
+        }
+        labels.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut f = BufWriter::new(File::create(dir.join(Path::new(&t.filename).with_extension("txt")))?);
+        for (start, end, label) in labels {
+            writeln!(f, "{:.6}\t{:.6}\t{}", start, end, label)?;
+        }
+        f.flush()?;
+    }
+    Ok(())
+}
+
+fn write_images(
+    tracks: &[&TrackResult],
+    dir: &Path,
+    render: impl Fn(&TrackResult) -> Option<Image>,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for t in tracks {
+        if let Some(image) = render(t) {
+            image.write(&dir.join(Path::new(&t.filename).with_extension("png")))?;
+        }
+    }
+    Ok(())
+}
+
+/// Min/max waveform thumbnail of every track.
+pub fn write_waveforms(tracks: &[&TrackResult], dir: &Path) -> std::io::Result<()> {
+    write_images(tracks, dir, |t| t.waveform.as_ref().map(|w| w.render()))
+}
+
+/// Spectrogram of every track.
+pub fn write_spectrograms(tracks: &[&TrackResult], dir: &Path) -> std::io::Result<()> {
+    write_images(tracks, dir, |t| t.spectrum.spectrogram.as_ref().map(|s| s.render()))
+}
@@ -0,0 +1,303 @@
+// ─── FLAC metadata editing ────────────────────────────────────────────────────
+//
+// claxon only reads, so the options that write tags go through this small
+// metadata editor. It rewrites the Vorbis comment block and leaves every other
+// block, and the audio, byte for byte as they were.
+//
+// As with metaflac, the file is updated in place when the new metadata fits in
+// the old (the PADDING block grows or shrinks to make up the difference), and
+// otherwise rewritten through a temporary file in the same folder, with
+// PADDING bytes of room for the next edit. With `--preserve-mtime` the file's
+// access and modification times are set back afterwards, so that backup and
+// sync tools that go by them don't copy a whole library again for its tags.
+
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const STREAMINFO: u8 = 0;
+const PADDING: u8 = 1;
+const VORBIS_COMMENT: u8 = 4;
+
+/// Padding left when a file has to be rewritten.
+const NEW_PADDING: usize = 4096;
+
+/// Set by --preserve-mtime.
+pub static PRESERVE_MTIME: AtomicBool = AtomicBool::new(false);
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// The metadata blocks of a FLAC file (type and body), and where its audio starts.
+struct Metadata {
+    blocks: Vec<(u8, Vec<u8>)>,
+    audio_offset: u64,
+}
+
+fn read_metadata(file: &mut impl Read) -> io::Result<Metadata> {
+    let mut marker = [0u8; 4];
+    file.read_exact(&mut marker)?;
+    if &marker != b"fLaC" {
+        return Err(invalid("not a FLAC file"));
+    }
+    let mut blocks = Vec::new();
+    let mut audio_offset = 4u64;
+    loop {
+        let mut header = [0u8; 4];
+        file.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut body = vec![0u8; len];
+        file.read_exact(&mut body)?;
+        blocks.push((header[0] & 0x7f, body));
+        audio_offset += 4 + len as u64;
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    if blocks.first().map(|(kind, _)| *kind) != Some(STREAMINFO) {
+        return Err(invalid("FLAC file without STREAMINFO"));
+    }
+    Ok(Metadata { blocks, audio_offset })
+}
+
+/// Vendor string and `KEY=value` entries of a Vorbis comment block.
+fn parse_comments(body: &[u8]) -> io::Result<(String, Vec<String>)> {
+    let mut at = 0usize;
+    let mut take = |len: usize| -> io::Result<&[u8]> {
+        let bytes = body.get(at..at + len).ok_or_else(|| invalid("truncated Vorbis comment block"))?;
+        at += len;
+        Ok(bytes)
+    };
+    let length = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+
+    let vendor_len = length(take(4)?);
+    let vendor = String::from_utf8_lossy(take(vendor_len)?).into_owned();
+    let count = length(take(4)?);
+    let mut comments = Vec::with_capacity(count.min(1024));
+    for _ in 0..count {
+        let len = length(take(4)?);
+        comments.push(String::from_utf8_lossy(take(len)?).into_owned());
+    }
+    Ok((vendor, comments))
+}
+
+fn build_comments(vendor: &str, comments: &[String]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    body.extend_from_slice(vendor.as_bytes());
+    body.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        body.extend_from_slice(comment.as_bytes());
+    }
+    body
+}
+
+/// Serialised metadata blocks, with a trailing PADDING block of `padding`
+/// bytes when given.
+fn serialise(blocks: &[(u8, Vec<u8>)], padding: Option<usize>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let padding_block = padding.map(|len| (PADDING, vec![0u8; len]));
+    let all: Vec<&(u8, Vec<u8>)> = blocks.iter().chain(padding_block.as_ref()).collect();
+    for (i, (kind, body)) in all.iter().enumerate() {
+        if body.len() >= 1 << 24 {
+            return Err(invalid("metadata block too large"));
+        }
+        let last = if i + 1 == all.len() { 0x80 } else { 0 };
+        out.push(kind | last);
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+    }
+    Ok(out)
+}
+
+/// The Vorbis comments of a file, as (KEY, value) pairs with the key in
+/// upper case, in file order. Comments the edit leaves alone are written back
+/// as they were, in their own case and without a "=" if they had none.
+pub type Comments = Vec<(String, String)>;
+
+/// An entry split into its upper-case key and value.
+fn split_entry(entry: &str) -> (String, String) {
+    match entry.split_once('=') {
+        Some((key, value)) => (key.to_ascii_uppercase(), value.to_string()),
+        None => (entry.to_ascii_uppercase(), String::new()),
+    }
+}
+
+/// Applies `edit` to the Vorbis comments of the FLAC file at `path` and saves
+/// them if they changed. Returns whether the file was written.
+pub fn edit_comments(path: &Path, edit: impl FnOnce(&mut Comments)) -> io::Result<bool> {
+    if !PRESERVE_MTIME.load(Ordering::Relaxed) {
+        return write_comments(path, edit);
+    }
+    let before = fs::metadata(path)?;
+    let times = FileTimes::new().set_accessed(before.accessed()?).set_modified(before.modified()?);
+    let written = write_comments(path, edit)?;
+    if written {
+        OpenOptions::new().write(true).open(path)?.set_times(times)?;
+    }
+    Ok(written)
+}
+
+fn write_comments(path: &Path, edit: impl FnOnce(&mut Comments)) -> io::Result<bool> {
+    let mut metadata = read_metadata(&mut BufReader::new(File::open(path)?))?;
+
+    let index = metadata.blocks.iter().position(|(kind, _)| *kind == VORBIS_COMMENT);
+    let (vendor, entries) = match index {
+        Some(i) => parse_comments(&metadata.blocks[i].1)?,
+        None => (format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")), Vec::new()),
+    };
+    let original: Comments = entries.iter().map(|entry| split_entry(entry)).collect();
+    let mut comments = original.clone();
+    edit(&mut comments);
+    if comments == original {
+        return Ok(false);
+    }
+
+    // Each comment kept is matched with the entry it came from
+    let mut unused: Vec<Option<&String>> = entries.iter().map(Some).collect();
+    let entries: Vec<String> = comments
+        .iter()
+        .map(|comment| {
+            let kept = original.iter().zip(&mut unused).find(|(o, entry)| *o == comment && entry.is_some());
+            match kept.and_then(|(_, entry)| entry.take()) {
+                Some(entry) => entry.clone(),
+                None => format!("{}={}", comment.0, comment.1),
+            }
+        })
+        .collect();
+    let body = build_comments(&vendor, &entries);
+    match index {
+        Some(i) => metadata.blocks[i].1 = body,
+        None => metadata.blocks.insert(1, (VORBIS_COMMENT, body)),
+    }
+    metadata.blocks.retain(|(kind, _)| *kind != PADDING);
+
+    // In place when the blocks still fit before the audio
+    let room = metadata.audio_offset as usize - 4;
+    let needed = serialise(&metadata.blocks, None)?.len();
+    let in_place = if needed == room {
+        Some(None)
+    } else if needed + 4 <= room {
+        Some(Some(room - needed - 4))
+    } else {
+        None
+    };
+    if let Some(padding) = in_place {
+        let bytes = serialise(&metadata.blocks, padding)?;
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.seek(SeekFrom::Start(4))?;
+        file.write_all(&bytes)?;
+        file.flush()?;
+        return Ok(true);
+    }
+
+    let temp = path.with_extension("flac.dr-measure-tmp");
+    let written = (|| {
+        let mut source = BufReader::new(File::open(path)?);
+        source.seek(SeekFrom::Start(metadata.audio_offset))?;
+        let mut out = BufWriter::new(File::create(&temp)?);
+        out.write_all(b"fLaC")?;
+        out.write_all(&serialise(&metadata.blocks, Some(NEW_PADDING))?)?;
+        io::copy(&mut source, &mut out)?;
+        out.flush()?;
+        fs::set_permissions(&temp, fs::metadata(path)?.permissions())?;
+        fs::rename(&temp, path)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written.map(|_| true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const AUDIO: &[u8] = b"\xff\xf8 not really FLAC frames, but never decoded here";
+
+    /// A FLAC file in the temporary folder with the given comment entries and
+    /// padding, removed when dropped.
+    struct TestFile(PathBuf);
+
+    impl TestFile {
+        fn new(name: &str, entries: &[&str], padding: Option<usize>) -> TestFile {
+            let path = std::env::temp_dir().join(format!("dr-measure-{}-{}.flac", name, std::process::id()));
+            let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+            let blocks = [(STREAMINFO, vec![7u8; 34]), (VORBIS_COMMENT, build_comments("test vendor", &entries))];
+            let mut bytes = b"fLaC".to_vec();
+            bytes.extend(serialise(&blocks, padding).unwrap());
+            bytes.extend_from_slice(AUDIO);
+            fs::write(&path, bytes).unwrap();
+            TestFile(path)
+        }
+
+        /// The comment entries, the padding length and the audio bytes.
+        fn read(&self) -> (Vec<String>, Option<usize>, Vec<u8>) {
+            let bytes = fs::read(&self.0).unwrap();
+            let metadata = read_metadata(&mut &bytes[..]).unwrap();
+            let comments = metadata.blocks.iter().find(|(kind, _)| *kind == VORBIS_COMMENT).unwrap();
+            let padding = metadata.blocks.iter().find(|(kind, _)| *kind == PADDING).map(|(_, body)| body.len());
+            assert_eq!(metadata.blocks[0], (STREAMINFO, vec![7u8; 34]));
+            (parse_comments(&comments.1).unwrap().1, padding, bytes[metadata.audio_offset as usize..].to_vec())
+        }
+    }
+
+    impl Drop for TestFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn set_dr(comments: &mut Comments) {
+        comments.retain(|(key, _)| key != "DYNAMIC_RANGE");
+        comments.push(("DYNAMIC_RANGE".to_string(), "9".to_string()));
+    }
+
+    #[test]
+    fn edits_in_place_within_the_padding() {
+        let file = TestFile::new("in-place", &["title=Song", "FOO", "dynamic_range=7"], Some(1024));
+        let len = fs::metadata(&file.0).unwrap().len();
+        assert!(edit_comments(&file.0, set_dr).unwrap());
+
+        let (entries, padding, audio) = file.read();
+        assert_eq!(entries, ["title=Song", "FOO", "DYNAMIC_RANGE=9"]);
+        // "dynamic_range=7" was 15 bytes, "DYNAMIC_RANGE=9" is too
+        assert_eq!(padding, Some(1024));
+        assert_eq!(audio, AUDIO);
+        assert_eq!(fs::metadata(&file.0).unwrap().len(), len);
+    }
+
+    #[test]
+    fn rewrites_when_the_padding_is_too_small() {
+        let file = TestFile::new("rewrite", &["Artist=Band", "FOO"], None);
+        assert!(edit_comments(&file.0, set_dr).unwrap());
+
+        let (entries, padding, audio) = file.read();
+        assert_eq!(entries, ["Artist=Band", "FOO", "DYNAMIC_RANGE=9"]);
+        assert_eq!(padding, Some(NEW_PADDING));
+        assert_eq!(audio, AUDIO);
+    }
+
+    #[test]
+    fn leaves_the_file_alone_when_nothing_changes() {
+        let file = TestFile::new("unchanged", &["title=Song", "FOO"], Some(16));
+        let before = fs::read(&file.0).unwrap();
+        assert!(!edit_comments(&file.0, |comments| comments.retain(|(key, _)| key != "BAR")).unwrap());
+        assert_eq!(fs::read(&file.0).unwrap(), before);
+    }
+
+    #[test]
+    fn removes_keys_whatever_their_case() {
+        let file = TestFile::new("remove", &["title=Song", "Dynamic_Range=9", "FOO"], Some(16));
+        assert!(edit_comments(&file.0, |comments| comments.retain(|(key, _)| key != "DYNAMIC_RANGE")).unwrap());
+
+        let (entries, padding, audio) = file.read();
+        assert_eq!(entries, ["title=Song", "FOO"]);
+        assert_eq!(padding, Some(16 + "Dynamic_Range=9".len() + 4));
+        assert_eq!(audio, AUDIO);
+    }
+}
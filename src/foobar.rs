@@ -0,0 +1,222 @@
+// ─── foobar2000 DR Meter log ──────────────────────────────────────────────────
+//
+// The layout of the logs written by foobar2000's foo_dr_meter, which the DR
+// database and several forums accept as the only submission format. The
+// columns keep the original's fixed widths, with Peak, RMS and Duration
+// right-aligned under their headings whatever the values, and the separators
+// and technical footer are the same; only the first line names dr-measure,
+// the program that wrote the log. Tracks are named "NN-Title" from their tags
+// like foo_dr_meter does, or after their file (without the extension) when
+// untagged.
+//
+// `--submission` writes the log as the DR database expects it: named
+// foo_dr.txt, with CRLF line ends and a UTF-8 byte order mark as foobar2000
+// saves it, and only for an album it can file: every track analysed and
+// tagged with its TITLE and the one ALBUM of the folder.
+
+use crate::{album_groups, album_tags, create_output, Algorithm, TrackResult};
+use chrono::Local;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+const RULE: &str = "--------------------------------------------------------------------------------";
+const DOUBLE_RULE: &str = "================================================================================";
+
+/// "m:ss" as foo_dr_meter prints durations (hours fold into minutes).
+fn foobar_duration(secs: f64) -> String {
+    let total = secs as u64;
+    format!("{}:{:02}", total / 60, total % 60)
+}
+
+/// A value shared by every track, with `unit`, or "-" when they differ.
+fn common(tracks: &[&TrackResult], unit: &str, value: impl Fn(&TrackResult) -> u32) -> String {
+    let first = value(tracks[0]);
+    if tracks.iter().all(|t| value(t) == first) {
+        format!("{}{}", first, unit)
+    } else {
+        "-".to_string()
+    }
+}
+
+/// Why `results` can't be submitted to the DR database, if they can't.
+fn submission_problem(results: &[Result<TrackResult, (String, String)>], tracks: &[&TrackResult]) -> Option<String> {
+    if let Some((name, _)) = results.iter().find_map(|r| r.as_ref().err()) {
+        return Some(format!("{} could not be analysed", name));
+    }
+    if album_groups(tracks).is_some() {
+        return Some("the folder holds tracks of more than one album (or untagged ones)".to_string());
+    }
+    if album_tags(tracks.iter().copied()).is_none() {
+        return Some("the tracks have no ALBUM tag".to_string());
+    }
+    if let Some(t) = tracks.iter().find(|t| t.tags.title.is_none()) {
+        return Some(format!("{} has no TITLE tag", t.filename));
+    }
+    None
+}
+
+pub fn write_foobar_log(
+    results: &[Result<TrackResult, (String, String)>],
+    folder: &Path,
+    output_path: &Path,
+    algorithm: Algorithm,
+    submission: bool,
+) -> std::io::Result<()> {
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    if submission {
+        if let Some(problem) = submission_problem(results, &tracks) {
+            return Err(std::io::Error::other(format!("not a DR database submission: {}", problem)));
+        }
+    }
+    let analyzed = match album_tags(tracks.iter().copied()) {
+        Some((artist, album)) => format!("{} / {}", artist, album),
+        None => folder
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| folder.display().to_string()),
+    };
+
+    let mut f = Vec::new();
+    writeln!(f, "{} {}, foo_dr_meter compatible log", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+    writeln!(f, "log date: {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(f)?;
+    writeln!(f, "{}", RULE)?;
+    writeln!(f, "Analyzed: {}", analyzed)?;
+    writeln!(f, "{}", RULE)?;
+    writeln!(f)?;
+    writeln!(f, "DR         Peak         RMS     Duration Track")?;
+    writeln!(f, "{}", RULE)?;
+    for t in &tracks {
+        let name = match (&t.tags.title, t.tags.track_number) {
+            (Some(title), Some(n)) => format!("{:02}-{}", n, title),
+            (Some(title), None) => title.clone(),
+            (None, _) => t.filename.rsplit_once('.').map_or(t.filename.as_str(), |(stem, _)| stem).to_string(),
+        };
+        // Peak, RMS and Duration end under the last letter of their headings
+        writeln!(
+            f,
+            "{:<5}{:>10.2} dB{:>9.2} dB{:>10} {}",
+            format!("DR{}", t.dr),
+            t.peak_db,
+            t.rms_db,
+            foobar_duration(t.duration_secs),
+            name
+        )?;
+    }
+    writeln!(f, "{}", RULE)?;
+    writeln!(f)?;
+
+    if !tracks.is_empty() {
+        // The official value is the rounded mean of the (rounded) track values
+        let mean = tracks.iter().map(|t| t.dr as f64).sum::<f64>() / tracks.len() as f64;
+        let total_secs: f64 = tracks.iter().map(|t| t.duration_secs).sum();
+        let total_bytes: u64 = tracks
+            .iter()
+            .filter_map(|t| fs::metadata(folder.join(&t.filename)).ok())
+            .map(|m| m.len())
+            .sum();
+        let bitrate = if total_secs > 0.0 && total_bytes > 0 {
+            format!("{} kbps", (total_bytes as f64 * 8.0 / total_secs / 1000.0).round())
+        } else {
+            "-".to_string()
+        };
+
+        writeln!(f, "Number of tracks:  {}", tracks.len())?;
+        writeln!(f, "Official DR value: DR{}", algorithm.round(mean))?;
+        writeln!(f)?;
+        writeln!(f, "Samplerate:        {}", common(&tracks, " Hz", |t| t.sample_rate))?;
+        writeln!(f, "Channels:          {}", common(&tracks, "", |t| t.channels))?;
+        writeln!(f, "Bits per sample:   {}", common(&tracks, "", |t| t.bit_depth))?;
+        writeln!(f, "Bitrate:           {}", bitrate)?;
+        writeln!(f, "Codec:             FLAC")?;
+    }
+    writeln!(f, "{}", DOUBLE_RULE)?;
+
+    // foo_dr_meter has no place for failures; keep them out of the log proper
+    let errors: Vec<&(String, String)> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    if !errors.is_empty() {
+        writeln!(f)?;
+        for (name, err) in errors {
+            writeln!(f, "Not analyzed: {} ({})", name, err)?;
+        }
+    }
+
+    let log = if submission {
+        let text = String::from_utf8_lossy(&f).replace('\n', "\r\n");
+        [&b"\xef\xbb\xbf"[..], text.as_bytes()].concat()
+    } else {
+        f
+    };
+    let mut out = create_output(output_path)?;
+    out.write_all(&log)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{peaky_tone, TestFlac};
+    use crate::{AnalysisOptions, DrParams};
+
+    #[test]
+    fn writes_a_submission_log() {
+        // A loud track and one whose peak is below -10 dB
+        let loud = peaky_tone(8);
+        let quiet: Vec<i16> = loud.iter().map(|x| x / 8).collect();
+        let files = [
+            TestFlac::new("foobar-1", 44100, &[loud.clone(), loud]),
+            TestFlac::new("foobar-2", 44100, &[quiet.clone(), quiet]),
+        ];
+        let dr = DrParams { algorithm: Algorithm::Dr14tt, ..DrParams::default() };
+        let opts = AnalysisOptions { dr, ..AnalysisOptions::default() };
+        let results: Vec<Result<TrackResult, (String, String)>> = files
+            .iter()
+            .zip(1..)
+            .map(|(file, n)| {
+                let mut track = file.analyse(&opts);
+                track.tags.artist = Some("Band".to_string());
+                track.tags.album = Some("Album".to_string());
+                track.tags.title = Some(format!("Song {}", n));
+                track.tags.track_number = Some(n);
+                Ok(track)
+            })
+            .collect();
+        let output = files[0].0.with_extension("txt");
+        write_foobar_log(&results, &std::env::temp_dir(), &output, Algorithm::Dr14tt, true).unwrap();
+        let log = fs::read(&output).unwrap();
+        let _ = fs::remove_file(&output);
+        let text = std::str::from_utf8(&log).unwrap();
+        let text = text.strip_prefix('\u{feff}').expect("byte order mark");
+        assert!(!text.replace("\r\n", "").contains('\n'), "bare LF in {:?}", text);
+        let mut lines: Vec<&str> = text.split("\r\n").collect();
+        assert!(lines.remove(1).starts_with("log date: "));
+        let version = format!("dr-measure {}, foo_dr_meter compatible log", env!("CARGO_PKG_VERSION"));
+        let expected = [
+            version.as_str(),
+            "",
+            RULE,
+            "Analyzed: Band / Album",
+            RULE,
+            "",
+            "DR         Peak         RMS     Duration Track",
+            RULE,
+            "DR18      -2.70 dB   -20.76 dB      0:08 01-Song 1",
+            "DR18     -20.77 dB   -38.83 dB      0:08 02-Song 2",
+            RULE,
+            "",
+            "Number of tracks:  2",
+            "Official DR value: DR18",
+            "",
+            "Samplerate:        44100 Hz",
+            "Channels:          2",
+            "Bits per sample:   16",
+            "Bitrate:           1412 kbps",
+            "Codec:             FLAC",
+            DOUBLE_RULE,
+            "",
+        ];
+        assert_eq!(lines, expected);
+    }
+}
@@ -0,0 +1,204 @@
+// ─── HTML report ──────────────────────────────────────────────────────────────
+//
+// A single self-contained page: the album summary, a DR bar per track, and the
+// track table, sortable by clicking a column header. Waveform thumbnails (with
+// --waveforms) are embedded as data URIs, so the file can be mailed as is.
+
+use crate::{
+    album_dr_values, album_lufs, create_output, document::xml_escape, format_dr, format_duration, rating, AlbumMethod, ReportOptions, TrackResult,
+};
+use chrono::Local;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// DR that fills the whole width of the bar chart.
+const CHART_MAX_DR: f64 = 20.0;
+
+const STYLE: &str = "
+body { font: 14px/1.4 system-ui, sans-serif; color: #222; max-width: 1100px; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ddd; }
+.meta { color: #666; }
+.cards { display: flex; gap: 1em; flex-wrap: wrap; }
+.card { border: 1px solid #ddd; border-radius: 6px; padding: 0.6em 1em; min-width: 8em; }
+.card b { display: block; font-size: 1.6em; }
+.chart .row { display: flex; align-items: center; margin: 2px 0; }
+.chart .name { width: 30%; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; padding-right: 0.5em; }
+.chart .bar { height: 1.1em; border-radius: 3px; }
+.chart .value { padding-left: 0.4em; font-variant-numeric: tabular-nums; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: 4px 8px; border-bottom: 1px solid #eee; text-align: right; white-space: nowrap; }
+th { cursor: pointer; background: #f6f6f6; user-select: none; }
+th:hover { background: #e8e8e8; }
+td.text, th.text { text-align: left; }
+td { font-variant-numeric: tabular-nums; }
+img.wave { display: block; height: 40px; width: 240px; }
+.grade { display: inline-block; width: 1.6em; text-align: center; border-radius: 3px; color: #fff; font-weight: bold; }
+.A { background: #2e8b57; } .B { background: #6aa84f; } .C { background: #d4a017; }
+.D { background: #e07b39; } .F { background: #c0392b; }
+.errors li { color: #c0392b; }
+footer { margin-top: 3em; color: #888; font-size: 0.9em; }
+";
+
+/// Sorts the table by the clicked column, using each cell's data-v value;
+/// a second click reverses the order.
+const SCRIPT: &str = "
+document.querySelectorAll('th').forEach((th, col) => th.addEventListener('click', () => {
+  const body = th.closest('table').tBodies[0];
+  const asc = th.dataset.order !== 'asc';
+  th.closest('tr').querySelectorAll('th').forEach(h => delete h.dataset.order);
+  th.dataset.order = asc ? 'asc' : 'desc';
+  const key = row => { const v = row.cells[col].dataset.v; return isNaN(v) ? v : parseFloat(v); };
+  [...body.rows]
+    .sort((a, b) => (key(a) < key(b) ? -1 : key(a) > key(b) ? 1 : 0) * (asc ? 1 : -1))
+    .forEach(row => body.appendChild(row));
+}));
+";
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Table cell whose sort key is the unformatted value.
+fn cell(sort: impl std::fmt::Display, shown: &str) -> String {
+    format!("<td data-v=\"{}\">{}</td>", sort, shown)
+}
+
+pub fn write_html(
+    results: &[Result<TrackResult, (String, String)>],
+    folder: &Path,
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    let errors: Vec<&(String, String)> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    let dr = |value: f64| format_dr(value, opts.precision, opts.dr.algorithm);
+    let folder_str = folder.canonicalize().unwrap_or_else(|_| folder.to_path_buf()).display().to_string();
+    let title = folder
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| folder_str.clone());
+
+    let mut f = BufWriter::new(create_output(output_path)?);
+    writeln!(f, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(f, "<title>Dynamic Range Report — {}</title>", xml_escape(&title))?;
+    writeln!(f, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(f, "<h1>Dynamic Range Report</h1>")?;
+    writeln!(
+        f,
+        "<p class=\"meta\">{} · generated {}{}</p>",
+        xml_escape(&folder_str),
+        Local::now().format("%Y-%m-%d %H:%M"),
+        opts.dr.algorithm.label().map(|l| format!(" · {}", l)).unwrap_or_default()
+    )?;
+
+    // Summary
+    if !tracks.is_empty() {
+        let (dr_mean, dr_pooled) = album_dr_values(&tracks, opts);
+        let album_value = match opts.album_method {
+            AlbumMethod::Pooled => dr_pooled,
+            AlbumMethod::Mean => dr_mean,
+        };
+        let dr_album = opts.dr.algorithm.round(album_value);
+        let (grade, label) = (rating(dr_album).0, opts.lang.strings().rating(dr_album));
+        let lufs = album_lufs(&tracks);
+        let true_peak = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+
+        writeln!(f, "<div class=\"cards\">")?;
+        writeln!(f, "<div class=\"card\">Album DR<b>{}</b></div>", dr(album_value))?;
+        writeln!(
+            f,
+            "<div class=\"card\">Rating<b><span class=\"grade {g}\">{g}</span></b>{}</div>",
+            xml_escape(label),
+            g = grade
+        )?;
+        writeln!(f, "<div class=\"card\">Loudness<b>{:.1} LUFS</b></div>", lufs)?;
+        writeln!(f, "<div class=\"card\">True peak<b>{:+.1} dBTP</b></div>", true_peak)?;
+        writeln!(f, "<div class=\"card\">Tracks<b>{}</b></div>", tracks.len())?;
+        writeln!(f, "</div>")?;
+
+        // DR bar chart
+        writeln!(f, "<h2>DR per track</h2>\n<div class=\"chart\">")?;
+        for t in &tracks {
+            let width = (t.dr_exact / CHART_MAX_DR * 70.0).clamp(0.5, 70.0);
+            writeln!(
+                f,
+                "<div class=\"row\"><span class=\"name\" title=\"{n}\">{n}</span>\
+                 <span class=\"bar {g}\" style=\"width: {:.1}%\"></span><span class=\"value\">{}</span></div>",
+                width,
+                dr(t.dr_exact),
+                n = xml_escape(&t.display_name()),
+                g = rating(t.dr).0
+            )?;
+        }
+        writeln!(f, "</div>")?;
+    }
+
+    // Track table
+    let waveforms = tracks.iter().any(|t| t.waveform.is_some());
+    writeln!(f, "<h2>Tracks</h2>\n<table>\n<thead><tr>")?;
+    writeln!(
+        f,
+        "<th class=\"text\">File</th><th>DR</th><th>Grade</th><th>Peak SP</th><th>Peak TP</th><th>RMS dB</th>\
+         <th>LUFS</th><th>PLR</th><th>Crest</th><th>Duration</th><th class=\"text\">Info</th>{}",
+        if waveforms { "<th class=\"text\">Waveform</th>" } else { "" }
+    )?;
+    writeln!(f, "</tr></thead>\n<tbody>")?;
+    for t in &tracks {
+        let (grade, _) = rating(t.dr);
+        write!(f, "<tr><td class=\"text\" data-v=\"{n}\">{n}</td>", n = xml_escape(&t.display_name()))?;
+        write!(f, "{}", cell(t.dr_exact, &dr(t.dr_exact)))?;
+        write!(f, "<td data-v=\"{}\"><span class=\"grade {g}\">{g}</span></td>", t.dr, g = grade)?;
+        write!(f, "{}", cell(t.peak_db, &format!("{:+.2}", t.peak_db)))?;
+        write!(f, "{}", cell(t.true_peak_db, &format!("{:+.2}", t.true_peak_db)))?;
+        write!(f, "{}", cell(t.rms_db, &format!("{:+.2}", t.rms_db)))?;
+        write!(f, "{}", cell(t.lufs.max(-100.0), &format!("{:+.1}", t.lufs)))?;
+        write!(f, "{}", cell(t.true_peak_db - t.lufs.max(-100.0), &format!("{:.1}", t.true_peak_db - t.lufs)))?;
+        write!(f, "{}", cell(t.crest_db, &format!("{:.2}", t.crest_db)))?;
+        write!(f, "{}", cell(t.duration_secs, &format_duration(t.duration_secs)))?;
+        let info = format!("{}/{}/{}", t.sample_rate / 1000, t.bit_depth, t.channels);
+        write!(f, "<td class=\"text\" data-v=\"{i}\">{i}</td>", i = info)?;
+        if waveforms {
+            match &t.waveform {
+                Some(w) => write!(
+                    f,
+                    "<td class=\"text\" data-v=\"\"><img class=\"wave\" alt=\"waveform\" src=\"data:image/png;base64,{}\"></td>",
+                    base64(&w.render().encode())
+                )?,
+                None => write!(f, "<td data-v=\"\"></td>")?,
+            }
+        }
+        writeln!(f, "</tr>")?;
+    }
+    writeln!(f, "</tbody>\n</table>")?;
+
+    if !errors.is_empty() {
+        writeln!(f, "<h2>Errors</h2>\n<ul class=\"errors\">")?;
+        for (name, err) in &errors {
+            writeln!(f, "<li>{} — {}</li>", xml_escape(name), xml_escape(err))?;
+        }
+        writeln!(f, "</ul>")?;
+    }
+
+    writeln!(
+        f,
+        "<footer>{} {} · DR Loudness Standard — <a href=\"https://www.dynamicrange.de\">dynamicrange.de</a></footer>",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(f, "<script>{}</script>\n</body>\n</html>", SCRIPT)?;
+    f.flush()
+}
@@ -0,0 +1,224 @@
+// ─── FLAC integrity check ─────────────────────────────────────────────────────
+//
+// `--verify` checks every file against the MD5 of its audio that the encoder
+// stored in STREAMINFO. The samples are decoded for the analysis anyway, so
+// the check only adds the hashing: each sample is fed to MD5 as FLAC defines
+// it, little-endian in the fewest whole bytes that hold its bit depth,
+// channels interleaved. Files that fail to decode part-way (a frame CRC
+// error, a truncated download) are reported as well.
+
+/// The outcome of `--verify` for one file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Integrity {
+    /// The decoded audio matches its STREAMINFO MD5.
+    Ok,
+    /// The encoder left the MD5 unset, so there is nothing to check against.
+    NoChecksum,
+    /// The decoded audio differs from what was encoded.
+    Mismatch,
+    /// Decoding stopped with an error after this many frames.
+    DecodeError { frames: u64, error: String },
+    /// The stream ended before the length STREAMINFO gives.
+    Truncated { frames: u64, expected: u64 },
+}
+
+impl Integrity {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Integrity::Ok | Integrity::NoChecksum)
+    }
+
+    /// Name used in the JSON document.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Integrity::Ok => "ok",
+            Integrity::NoChecksum => "no_checksum",
+            Integrity::Mismatch => "mismatch",
+            Integrity::DecodeError { .. } => "decode_error",
+            Integrity::Truncated { .. } => "truncated",
+        }
+    }
+
+    /// One-line explanation for the reports; `sample_rate` converts frame
+    /// counts to times.
+    pub fn describe(&self, sample_rate: u32) -> String {
+        let at = |frames: u64| crate::format_duration(frames as f64 / sample_rate.max(1) as f64);
+        match self {
+            Integrity::Ok => "audio matches its MD5".to_string(),
+            Integrity::NoChecksum => "no MD5 stored by the encoder, not checked".to_string(),
+            Integrity::Mismatch => "MD5 mismatch: the decoded audio differs from what was encoded".to_string(),
+            Integrity::DecodeError { frames, error } => format!("decoding failed at {} ({})", at(*frames), error),
+            Integrity::Truncated { frames, expected } => {
+                format!("truncated: ends at {} of {}", at(*frames), at(*expected))
+            }
+        }
+    }
+}
+
+/// Hashes the decoded samples of one file and judges them at the end.
+pub struct Verifier {
+    md5: Md5,
+    bytes_per_sample: usize,
+    buffer: Vec<u8>,
+}
+
+impl Verifier {
+    pub fn new(bits_per_sample: u32) -> Verifier {
+        Verifier { md5: Md5::new(), bytes_per_sample: bits_per_sample.div_ceil(8) as usize, buffer: Vec::new() }
+    }
+
+    pub fn push_sample(&mut self, sample: i32) {
+        self.buffer.extend_from_slice(&sample.to_le_bytes()[..self.bytes_per_sample]);
+        if self.buffer.len() >= 1 << 16 {
+            self.md5.update(&self.buffer);
+            self.buffer.clear();
+        }
+    }
+
+    /// The verdict once the stream has ended after `frames` frames, with
+    /// `error` if decoding failed; `expected` frames and `checksum` are
+    /// STREAMINFO's.
+    pub fn finish(mut self, frames: u64, error: Option<String>, expected: u64, checksum: &[u8; 16]) -> Integrity {
+        if let Some(error) = error {
+            return Integrity::DecodeError { frames, error };
+        }
+        if expected > 0 && frames < expected {
+            return Integrity::Truncated { frames, expected };
+        }
+        if *checksum == [0; 16] {
+            return Integrity::NoChecksum;
+        }
+        self.md5.update(&self.buffer);
+        if self.md5.finish() == *checksum { Integrity::Ok } else { Integrity::Mismatch }
+    }
+}
+
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Incremental MD5 (RFC 1321).
+struct Md5 {
+    state: [u32; 4],
+    pending: Vec<u8>,
+    length: u64,
+}
+
+impl Md5 {
+    fn new() -> Md5 {
+        Md5 { state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476], pending: Vec::with_capacity(64), length: 0 }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        if !self.pending.is_empty() {
+            let take = (64 - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < 64 {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.block(&block);
+        }
+        let mut chunks = data.chunks_exact(64);
+        for chunk in &mut chunks {
+            self.block(chunk);
+        }
+        self.pending.extend_from_slice(chunks.remainder());
+    }
+
+    fn block(&mut self, chunk: &[u8]) {
+        let mut m = [0u32; 16];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            m[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(K[i]).wrapping_add(m[g]).rotate_left(S[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    fn finish(mut self) -> [u8; 16] {
+        let bits = self.length.wrapping_mul(8);
+        let mut padding = vec![0x80u8];
+        padding.resize((119 - self.length as usize % 64) % 64 + 1, 0);
+        padding.extend_from_slice(&bits.to_le_bytes());
+        self.update(&padding);
+        let mut digest = [0u8; 16];
+        for (out, word) in digest.chunks_mut(4).zip(self.state) {
+            out.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md5(data: &[u8]) -> String {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finish().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn md5_matches_the_rfc_1321_test_suite() {
+        let vectors: [(&str, &str); 7] = [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("a", "0cc175b9c0f1b6a831c399e269772661"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            ("abcdefghijklmnopqrstuvwxyz", "c3fcd3d76192e4007dfb496cca67e13b"),
+            (
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f",
+            ),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ];
+        for (input, digest) in vectors {
+            assert_eq!(md5(input.as_bytes()), digest, "MD5 of {:?}", input);
+        }
+    }
+
+    #[test]
+    fn md5_is_the_same_fed_in_pieces() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for piece in [1, 3, 63, 64, 65, 200] {
+            let mut md5 = Md5::new();
+            for chunk in data.chunks(piece) {
+                md5.update(chunk);
+            }
+            let digest: String = md5.finish().iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(digest, self::md5(&data), "fed {} bytes at a time", piece);
+        }
+    }
+}
@@ -0,0 +1,526 @@
+// ─── Report languages ─────────────────────────────────────────────────────────
+//
+// `--lang` translates the text report's title, header and summary labels, the
+// notes and warnings under the track table and summary, section headings and
+// "none detected" lines, and the rating strings of the text, Markdown, HTML
+// and PDF reports. Figures, units, column abbreviations and the per-track
+// lines of the sections stay as they are, and so do the machine-readable
+// formats and foobar2000 logs, whose layout other tools rely on. `merge`
+// reads text reports by the labels of every language.
+//
+// Header labels are padded to 9 columns and summary labels to 15, so
+// translations stay within 9 and 15 characters.
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    /// English
+    #[default]
+    En,
+    /// Deutsch
+    De,
+    /// Français
+    Fr,
+    /// Italiano
+    It,
+    /// Español
+    Es,
+}
+
+impl Lang {
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Lang::En => &EN,
+            Lang::De => &DE,
+            Lang::Fr => &FR,
+            Lang::It => &IT,
+            Lang::Es => &ES,
+        }
+    }
+}
+
+pub struct Strings {
+    pub title: &'static str,
+    pub generated: &'static str,
+    pub folder: &'static str,
+    pub album: &'static str,
+    pub release: &'static str,
+    pub algorithm: &'static str,
+    pub lfe: &'static str,
+    pub lfe_excluded: &'static str,
+    pub lfe_weighted: &'static str,
+    pub params: &'static str,
+    /// With `{}` for the block length, top ratio (%) and peak number.
+    pub non_standard: &'static str,
+    pub analysis: &'static str,
+    pub mono_downmix: &'static str,
+    pub mode: &'static str,
+    /// With `{}` for the quick stride.
+    pub quick_estimate: &'static str,
+    pub range: &'static str,
+    pub range_end: &'static str,
+    pub gate: &'static str,
+    /// With `{}` for the gate level.
+    pub gated_blocks: &'static str,
+    /// With `{}` for the dB margin.
+    pub true_peak_excess: &'static str,
+    /// With `{}` for the target level.
+    pub gain_target: &'static str,
+    pub negative_correlation: &'static str,
+    pub summary: &'static str,
+    pub tracks_analysed: &'static str,
+    pub album_dr: &'static str,
+    pub k_weighted_dr: &'static str,
+    pub mean_album_dr: &'static str,
+    pub pooled_album_dr: &'static str,
+    pub mean_of_tracks: &'static str,
+    pub pooled_blocks: &'static str,
+    pub dr_range: &'static str,
+    pub total_duration: &'static str,
+    pub total_size: &'static str,
+    pub album_peak: &'static str,
+    pub album_rms: &'static str,
+    pub loudness_spread: &'static str,
+    pub rms_spread: &'static str,
+    /// With `{}` for the loudness spread limit.
+    pub level_mismatch: &'static str,
+    pub album_loudness: &'static str,
+    pub album_true_peak: &'static str,
+    pub album_plr: &'static str,
+    pub album_gain: &'static str,
+    pub dr_rating: &'static str,
+    /// With `{}` for the outlier distance, the album median and the track names.
+    pub outliers: &'static str,
+    /// With `{}` for the number of albums.
+    pub mixed_albums: &'static str,
+    pub albums: &'static str,
+    pub errors: &'static str,
+    pub clipping: &'static str,
+    pub no_clipping: &'static str,
+    pub integrity: &'static str,
+    pub intersample_overs: &'static str,
+    pub no_intersample_overs: &'static str,
+    pub clicks: &'static str,
+    pub no_clicks: &'static str,
+    pub channel_dr: &'static str,
+    pub dc_offset: &'static str,
+    pub rumble: &'static str,
+    pub balance: &'static str,
+    pub phase: &'static str,
+    pub mid_side: &'static str,
+    pub silence: &'static str,
+    pub silence_trimmed: &'static str,
+    pub spectral: &'static str,
+    pub bit_depth: &'static str,
+    pub files: &'static str,
+    pub loudness: &'static str,
+    /// With `{}` for the reference level.
+    pub replaygain: &'static str,
+    pub streaming: &'static str,
+    pub worst: &'static str,
+    /// Labels of grades A, B, C, D and F.
+    ratings: [&'static str; 5],
+}
+
+/// `template` with each `{}` replaced by the next of `values`.
+pub fn fill(template: &str, values: &[&dyn std::fmt::Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for (value, part) in values.iter().zip(parts) {
+        out.push_str(&value.to_string());
+        out.push_str(part);
+    }
+    out
+}
+
+impl Strings {
+    /// The rating label of a DR value, as `rating` grades it.
+    pub fn rating(&self, dr: i32) -> &'static str {
+        let index = match crate::rating(dr).0 {
+            "A" => 0,
+            "B" => 1,
+            "C" => 2,
+            "D" => 3,
+            _ => 4,
+        };
+        self.ratings[index]
+    }
+}
+
+const EN: Strings = Strings {
+    title: "Dynamic Range Report",
+    generated: "Generated",
+    folder: "Folder",
+    album: "Album",
+    release: "Release",
+    algorithm: "Algorithm",
+    lfe: "LFE",
+    lfe_excluded: "excluded from the DR average",
+    lfe_weighted: "DR averaged with BS.1770 channel weights (LFE excluded)",
+    params: "Params",
+    non_standard: "NON-STANDARD — {} s blocks, top {}% RMS, peak #{} (spec: 3 s, 20%, #2)",
+    analysis: "Analysis",
+    mono_downmix: "DR, peak and RMS measured on a mono downmix",
+    mode: "Mode",
+    quick_estimate: "quick estimate (1 block in {}), values marked ~ are approximate",
+    range: "Range",
+    range_end: "end",
+    gate: "Gate",
+    gated_blocks: "DR blocks below {} dBFS RMS excluded",
+    true_peak_excess: "True peak more than {} dB above the sample peak: strong intersample content",
+    gain_target: "Gain: change needed to reach {} (⚠ = true peak would exceed 0 dBTP)",
+    negative_correlation: "Negative correlation: likely mono-compatibility problems",
+    summary: "Summary",
+    tracks_analysed: "Tracks analysed",
+    album_dr: "Album DR",
+    k_weighted_dr: "K-weighted DR",
+    mean_album_dr: "Mean album DR",
+    pooled_album_dr: "Pooled album DR",
+    mean_of_tracks: "mean of tracks",
+    pooled_blocks: "pooled blocks",
+    dr_range: "DR range",
+    total_duration: "Total duration",
+    total_size: "Total size",
+    album_peak: "Album peak",
+    album_rms: "Album RMS",
+    loudness_spread: "Loudness spread",
+    rms_spread: "RMS spread",
+    level_mismatch: "Track levels differ by more than {} LU: mismatched mastering?",
+    album_loudness: "Album loudness",
+    album_true_peak: "Album true peak",
+    album_plr: "Album PLR",
+    album_gain: "Album gain",
+    dr_rating: "DR Rating",
+    outliers: "Outlier(s), {} DR or more from the album median ({}): {}",
+    mixed_albums: "Tracks from {} albums (by ALBUM tags): the album DR and rating are given per album below",
+    albums: "Albums",
+    errors: "Errors",
+    clipping: "Clipping",
+    no_clipping: "No clipped regions detected",
+    integrity: "Integrity",
+    intersample_overs: "Intersample overs",
+    no_intersample_overs: "No intersample overs detected",
+    clicks: "Clicks / pops",
+    no_clicks: "No clicks detected",
+    channel_dr: "Per-channel DR",
+    dc_offset: "DC offset (% of full scale, per channel)",
+    rumble: "Subsonic rumble (energy below 20 Hz, dB relative to RMS)",
+    balance: "Channel balance (L/R RMS, + = left louder)",
+    phase: "Phase",
+    mid_side: "Mid/Side (M = (L+R)/2, S = (L−R)/2)",
+    silence: "Silence",
+    silence_trimmed: "Silence (leading / trailing excluded from analysis)",
+    spectral: "Spectral analysis",
+    bit_depth: "Bit depth utilisation",
+    files: "Files (size, average bitrate, encoder)",
+    loudness: "Loudness (EBU R128)",
+    replaygain: "ReplayGain 2.0 (reference {} LUFS)",
+    streaming: "Streaming normalization (gain dB, resulting true peak dBTP)",
+    worst: "Most compressed tracks",
+    ratings: ["Excellent – wide dynamic range", "Good", "Acceptable", "Compressed", "Heavily brick-walled / clipped"],
+};
+
+const DE: Strings = Strings {
+    title: "Dynamikumfang-Bericht",
+    generated: "Erstellt",
+    folder: "Ordner",
+    album: "Album",
+    release: "Release",
+    algorithm: "Verfahren",
+    lfe: "LFE",
+    lfe_excluded: "aus dem DR-Mittel ausgenommen",
+    lfe_weighted: "DR mit BS.1770-Kanalgewichten gemittelt (ohne LFE)",
+    params: "Parameter",
+    non_standard: "NICHT STANDARD — {} s Blöcke, obere {}% RMS, Spitze #{} (Norm: 3 s, 20%, #2)",
+    analysis: "Analyse",
+    mono_downmix: "DR, Spitze und RMS an einem Mono-Downmix gemessen",
+    mode: "Modus",
+    quick_estimate: "Schnellschätzung (1 Block von {}), mit ~ markierte Werte sind Näherungen",
+    range: "Bereich",
+    range_end: "Ende",
+    gate: "Gate",
+    gated_blocks: "DR-Blöcke unter {} dBFS RMS ausgenommen",
+    true_peak_excess: "True Peak mehr als {} dB über der Sample-Spitze: starke Intersample-Anteile",
+    gain_target: "Gain: nötige Änderung für {} (⚠ = True Peak läge über 0 dBTP)",
+    negative_correlation: "Negative Korrelation: wahrscheinlich Probleme mit der Monokompatibilität",
+    summary: "Zusammenfassung",
+    tracks_analysed: "Titel gemessen",
+    album_dr: "Album-DR",
+    k_weighted_dr: "DR K-gewichtet",
+    mean_album_dr: "Album-DR Mittel",
+    pooled_album_dr: "Album-DR (Pool)",
+    mean_of_tracks: "Mittel der Titel",
+    pooled_blocks: "gepoolte Blöcke",
+    dr_range: "DR-Bereich",
+    total_duration: "Gesamtdauer",
+    total_size: "Gesamtgröße",
+    album_peak: "Album-Spitze",
+    album_rms: "Album-RMS",
+    loudness_spread: "Lautheitsspanne",
+    rms_spread: "RMS-Spanne",
+    level_mismatch: "Titelpegel weichen um mehr als {} LU ab: uneinheitliches Mastering?",
+    album_loudness: "Album-Lautheit",
+    album_true_peak: "Album-True-Peak",
+    album_plr: "Album-PLR",
+    album_gain: "Album-Gain",
+    dr_rating: "DR-Bewertung",
+    outliers: "Ausreißer, {} DR oder mehr vom Album-Median ({}) entfernt: {}",
+    mixed_albums: "Titel aus {} Alben (nach ALBUM-Tags): Album-DR und Bewertung stehen unten pro Album",
+    albums: "Alben",
+    errors: "Fehler",
+    clipping: "Clipping",
+    no_clipping: "Keine übersteuerten Bereiche gefunden",
+    integrity: "Integrität",
+    intersample_overs: "Intersample-Übersteuerungen",
+    no_intersample_overs: "Keine Intersample-Übersteuerungen gefunden",
+    clicks: "Klicks / Knackser",
+    no_clicks: "Keine Klicks gefunden",
+    channel_dr: "DR pro Kanal",
+    dc_offset: "DC-Offset (% des Vollausschlags, pro Kanal)",
+    rumble: "Subsonisches Rumpeln (Energie unter 20 Hz, dB relativ zum RMS)",
+    balance: "Kanalbalance (L/R RMS, + = links lauter)",
+    phase: "Phase",
+    mid_side: "Mitte/Seite (M = (L+R)/2, S = (L−R)/2)",
+    silence: "Stille",
+    silence_trimmed: "Stille (Anfang / Ende von der Analyse ausgenommen)",
+    spectral: "Spektralanalyse",
+    bit_depth: "Nutzung der Bittiefe",
+    files: "Dateien (Größe, mittlere Bitrate, Encoder)",
+    loudness: "Lautheit (EBU R128)",
+    replaygain: "ReplayGain 2.0 (Referenz {} LUFS)",
+    streaming: "Streaming-Normalisierung (Pegeländerung dB, resultierender True Peak dBTP)",
+    worst: "Am stärksten komprimierte Titel",
+    ratings: [
+        "Ausgezeichnet – großer Dynamikumfang",
+        "Gut",
+        "Akzeptabel",
+        "Komprimiert",
+        "Stark begrenzt / übersteuert",
+    ],
+};
+
+const FR: Strings = Strings {
+    title: "Rapport de plage dynamique",
+    generated: "Généré le",
+    folder: "Dossier",
+    album: "Album",
+    release: "Édition",
+    algorithm: "Méthode",
+    lfe: "LFE",
+    lfe_excluded: "exclu de la moyenne du DR",
+    lfe_weighted: "DR moyenné avec les poids de canal BS.1770 (LFE exclu)",
+    params: "Réglages",
+    non_standard: "NON STANDARD — blocs de {} s, {}% RMS les plus forts, crête n° {} (norme : 3 s, 20%, n° 2)",
+    analysis: "Analyse",
+    mono_downmix: "DR, crête et RMS mesurés sur un mixage mono",
+    mode: "Mode",
+    quick_estimate: "estimation rapide (1 bloc sur {}), les valeurs marquées ~ sont approximatives",
+    range: "Plage",
+    range_end: "fin",
+    gate: "Seuil",
+    gated_blocks: "blocs DR sous {} dBFS RMS exclus",
+    true_peak_excess: "True peak plus de {} dB au-dessus de la crête échantillon : fort contenu inter-échantillons",
+    gain_target: "Gain : correction nécessaire pour atteindre {} (⚠ = le true peak dépasserait 0 dBTP)",
+    negative_correlation: "Corrélation négative : problèmes probables de compatibilité mono",
+    summary: "Résumé",
+    tracks_analysed: "Pistes mesurées",
+    album_dr: "DR de l'album",
+    k_weighted_dr: "DR pondéré K",
+    mean_album_dr: "DR album moyen",
+    pooled_album_dr: "DR album groupé",
+    mean_of_tracks: "moyenne des pistes",
+    pooled_blocks: "blocs groupés",
+    dr_range: "Plage de DR",
+    total_duration: "Durée totale",
+    total_size: "Taille totale",
+    album_peak: "Crête album",
+    album_rms: "RMS album",
+    loudness_spread: "Écart de sonie",
+    rms_spread: "Écart RMS",
+    level_mismatch: "Les niveaux des pistes diffèrent de plus de {} LU : mastering disparate ?",
+    album_loudness: "Sonie album",
+    album_true_peak: "True peak album",
+    album_plr: "PLR album",
+    album_gain: "Gain album",
+    dr_rating: "Note DR",
+    outliers: "Valeur(s) atypique(s), à {} DR ou plus de la médiane de l'album ({}) : {}",
+    mixed_albums: "Pistes de {} albums (d'après les tags ALBUM) : le DR et la note sont donnés par album ci-dessous",
+    albums: "Albums",
+    errors: "Erreurs",
+    clipping: "Écrêtage",
+    no_clipping: "Aucune zone écrêtée détectée",
+    integrity: "Intégrité",
+    intersample_overs: "Dépassements inter-échantillons",
+    no_intersample_overs: "Aucun dépassement inter-échantillons détecté",
+    clicks: "Clics / craquements",
+    no_clicks: "Aucun clic détecté",
+    channel_dr: "DR par canal",
+    dc_offset: "Décalage DC (% de la pleine échelle, par canal)",
+    rumble: "Ronflement subsonique (énergie sous 20 Hz, dB par rapport au RMS)",
+    balance: "Balance des canaux (RMS G/D, + = gauche plus fort)",
+    phase: "Phase",
+    mid_side: "Mid/Side (M = (G+D)/2, S = (G−D)/2)",
+    silence: "Silence",
+    silence_trimmed: "Silence (début / fin exclus de l'analyse)",
+    spectral: "Analyse spectrale",
+    bit_depth: "Utilisation de la résolution",
+    files: "Fichiers (taille, débit moyen, encodeur)",
+    loudness: "Sonie (EBU R128)",
+    replaygain: "ReplayGain 2.0 (référence {} LUFS)",
+    streaming: "Normalisation des plateformes (gain dB, true peak résultant dBTP)",
+    worst: "Pistes les plus compressées",
+    ratings: ["Excellent – grande plage dynamique", "Bon", "Acceptable", "Compressé", "Fortement écrasé / écrêté"],
+};
+
+const IT: Strings = Strings {
+    title: "Rapporto sulla gamma dinamica",
+    generated: "Generato",
+    folder: "Cartella",
+    album: "Album",
+    release: "Edizione",
+    algorithm: "Algoritmo",
+    lfe: "LFE",
+    lfe_excluded: "escluso dalla media del DR",
+    lfe_weighted: "DR mediato con i pesi di canale BS.1770 (LFE escluso)",
+    params: "Parametri",
+    non_standard: "NON STANDARD — blocchi di {} s, {}% RMS più alti, picco n. {} (specifica: 3 s, 20%, n. 2)",
+    analysis: "Analisi",
+    mono_downmix: "DR, picco e RMS misurati su un downmix mono",
+    mode: "Modalità",
+    quick_estimate: "stima rapida (1 blocco su {}), i valori marcati ~ sono approssimativi",
+    range: "Sezione",
+    range_end: "fine",
+    gate: "Soglia",
+    gated_blocks: "blocchi DR sotto {} dBFS RMS esclusi",
+    true_peak_excess: "True peak oltre {} dB sopra il picco dei campioni: forte contenuto intersample",
+    gain_target: "Guadagno: variazione necessaria per raggiungere {} (⚠ = il true peak supererebbe 0 dBTP)",
+    negative_correlation: "Correlazione negativa: probabili problemi di compatibilità mono",
+    summary: "Riepilogo",
+    tracks_analysed: "Brani misurati",
+    album_dr: "DR dell'album",
+    k_weighted_dr: "DR pesato K",
+    mean_album_dr: "DR album medio",
+    pooled_album_dr: "DR album (pool)",
+    mean_of_tracks: "media dei brani",
+    pooled_blocks: "blocchi aggregati",
+    dr_range: "Intervallo DR",
+    total_duration: "Durata totale",
+    total_size: "Dimensione tot.",
+    album_peak: "Picco album",
+    album_rms: "RMS album",
+    loudness_spread: "Scarto loudness",
+    rms_spread: "Scarto RMS",
+    level_mismatch: "I livelli dei brani differiscono di oltre {} LU: mastering non omogeneo?",
+    album_loudness: "Loudness album",
+    album_true_peak: "True peak album",
+    album_plr: "PLR album",
+    album_gain: "Guadagno album",
+    dr_rating: "Valutazione DR",
+    outliers: "Anomalie, a {} DR o più dalla mediana dell'album ({}): {}",
+    mixed_albums: "Brani da {} album (secondo i tag ALBUM): DR e valutazione sono indicati per album qui sotto",
+    albums: "Album",
+    errors: "Errori",
+    clipping: "Clipping",
+    no_clipping: "Nessuna zona con clipping rilevata",
+    integrity: "Integrità",
+    intersample_overs: "Superamenti inter-campione",
+    no_intersample_overs: "Nessun superamento inter-campione rilevato",
+    clicks: "Click / scoppiettii",
+    no_clicks: "Nessun click rilevato",
+    channel_dr: "DR per canale",
+    dc_offset: "Offset DC (% del fondo scala, per canale)",
+    rumble: "Rombo subsonico (energia sotto 20 Hz, dB rispetto al RMS)",
+    balance: "Bilanciamento canali (RMS L/R, + = sinistro più forte)",
+    phase: "Fase",
+    mid_side: "Mid/Side (M = (L+R)/2, S = (L−R)/2)",
+    silence: "Silenzio",
+    silence_trimmed: "Silenzio (iniziale / finale escluso dall'analisi)",
+    spectral: "Analisi spettrale",
+    bit_depth: "Utilizzo della profondità di bit",
+    files: "File (dimensione, bitrate medio, encoder)",
+    loudness: "Loudness (EBU R128)",
+    replaygain: "ReplayGain 2.0 (riferimento {} LUFS)",
+    streaming: "Normalizzazione streaming (guadagno dB, true peak risultante dBTP)",
+    worst: "Brani più compressi",
+    ratings: [
+        "Eccellente – ampia gamma dinamica",
+        "Buono",
+        "Accettabile",
+        "Compresso",
+        "Pesantemente limitato / clippato",
+    ],
+};
+
+const ES: Strings = Strings {
+    title: "Informe de rango dinámico",
+    generated: "Generado",
+    folder: "Carpeta",
+    album: "Álbum",
+    release: "Edición",
+    algorithm: "Algoritmo",
+    lfe: "LFE",
+    lfe_excluded: "excluido de la media del DR",
+    lfe_weighted: "DR promediado con los pesos de canal BS.1770 (sin LFE)",
+    params: "Ajustes",
+    non_standard: "NO ESTÁNDAR — bloques de {} s, {}% RMS más altos, pico n.º {} (norma: 3 s, 20%, n.º 2)",
+    analysis: "Análisis",
+    mono_downmix: "DR, pico y RMS medidos en una mezcla mono",
+    mode: "Modo",
+    quick_estimate: "estimación rápida (1 bloque de cada {}), los valores marcados con ~ son aproximados",
+    range: "Tramo",
+    range_end: "final",
+    gate: "Umbral",
+    gated_blocks: "bloques DR por debajo de {} dBFS RMS excluidos",
+    true_peak_excess: "True peak más de {} dB por encima del pico de muestra: fuerte contenido entre muestras",
+    gain_target: "Ganancia: cambio necesario para llegar a {} (⚠ = el true peak superaría 0 dBTP)",
+    negative_correlation: "Correlación negativa: probables problemas de compatibilidad mono",
+    summary: "Resumen",
+    tracks_analysed: "Pistas medidas",
+    album_dr: "DR del álbum",
+    k_weighted_dr: "DR ponderado K",
+    mean_album_dr: "DR medio álbum",
+    pooled_album_dr: "DR álbum (pool)",
+    mean_of_tracks: "media de las pistas",
+    pooled_blocks: "bloques agrupados",
+    dr_range: "Rango de DR",
+    total_duration: "Duración total",
+    total_size: "Tamaño total",
+    album_peak: "Pico del álbum",
+    album_rms: "RMS del álbum",
+    loudness_spread: "Rango sonoridad",
+    rms_spread: "Rango RMS",
+    level_mismatch: "Los niveles de las pistas difieren en más de {} LU: ¿masterización dispar?",
+    album_loudness: "Sonoridad álbum",
+    album_true_peak: "True peak álbum",
+    album_plr: "PLR del álbum",
+    album_gain: "Ganancia álbum",
+    dr_rating: "Calificación DR",
+    outliers: "Valores atípicos, a {} DR o más de la mediana del álbum ({}): {}",
+    mixed_albums: "Pistas de {} álbumes (según las etiquetas ALBUM): el DR y la calificación se dan por álbum más abajo",
+    albums: "Álbumes",
+    errors: "Errores",
+    clipping: "Recorte (clipping)",
+    no_clipping: "No se detectaron zonas recortadas",
+    integrity: "Integridad",
+    intersample_overs: "Excesos entre muestras",
+    no_intersample_overs: "No se detectaron excesos entre muestras",
+    clicks: "Clics / chasquidos",
+    no_clicks: "No se detectaron clics",
+    channel_dr: "DR por canal",
+    dc_offset: "Offset DC (% de escala completa, por canal)",
+    rumble: "Retumbo subsónico (energía bajo 20 Hz, dB relativos al RMS)",
+    balance: "Balance de canales (RMS I/D, + = izquierdo más alto)",
+    phase: "Fase",
+    mid_side: "Mid/Side (M = (I+D)/2, S = (I−D)/2)",
+    silence: "Silencio",
+    silence_trimmed: "Silencio (inicial / final excluido del análisis)",
+    spectral: "Análisis espectral",
+    bit_depth: "Uso de la profundidad de bits",
+    files: "Archivos (tamaño, tasa de bits media, codificador)",
+    loudness: "Sonoridad (EBU R128)",
+    replaygain: "ReplayGain 2.0 (referencia {} LUFS)",
+    streaming: "Normalización en streaming (ganancia dB, true peak resultante dBTP)",
+    worst: "Pistas más comprimidas",
+    ratings: ["Excelente – amplio rango dinámico", "Bueno", "Aceptable", "Comprimido", "Muy limitado / recortado"],
+};
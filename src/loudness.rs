@@ -0,0 +1,383 @@
+// ─── Loudness (ITU-R BS.1770 / EBU R128) ─────────────────────────────────────
+//
+//  1. Each channel is passed through the K-weighting filter: a high-shelf
+//     "head" stage followed by the RLB high-pass stage.
+//  2. The weighted signal is accumulated in 100 ms sub-blocks; every sub-block
+//     closes a 400 ms gating block (75 % overlap) made of the last four.
+//  3. Block power = Σ G_ch · mean( y_ch² ), loudness = -0.691 + 10·log10(power).
+//  4. Integrated loudness gates out blocks below -70 LUFS (absolute) and then
+//     below (ungated mean - 10 LU) (relative), and averages the rest.
+//
+// ReplayGain 2.0 is the integrated loudness referenced to -18 LUFS; album gain
+// pools the gating blocks of every track before gating.
+//
+// True peak (BS.1770 Annex 2) oversamples each channel to at least 176.4 kHz
+// with a windowed-sinc polyphase interpolator and takes the maximum |y|. Runs
+// of frames whose interpolated signal exceeds 0 dBFS are intersample overs.
+
+pub const SUBBLOCK_SECONDS: f64 = 0.1;
+/// Gating block and momentary loudness window (400 ms) in sub-blocks.
+pub const SUBBLOCKS_PER_MOMENTARY: usize = 4;
+/// Short-term loudness window (3 s) in sub-blocks.
+pub const SUBBLOCKS_PER_SHORT_TERM: usize = 30;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Loudness reported for silent (fully gated) material.
+pub const LUFS_FLOOR: f64 = ABSOLUTE_GATE_LUFS;
+
+/// ReplayGain 2.0 reference loudness.
+pub const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+const TRUE_PEAK_TAPS_PER_PHASE: usize = 12;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Biquad { b, a, z1: 0.0, z2: 0.0 }
+    }
+
+    /// Second-order low-pass at `f0` Hz (bilinear transform, quality `q`).
+    pub fn lowpass(sample_rate: u32, f0: f64, q: f64) -> Self {
+        let k = (std::f64::consts::PI * f0 / sample_rate as f64).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let b0 = k * k / a0;
+        Biquad::new(
+            [b0, 2.0 * b0, b0],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    // Transposed direct form II
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z1;
+        self.z1 = self.b[1] * x - self.a[1] * y + self.z2;
+        self.z2 = self.b[2] * x - self.a[2] * y;
+        y
+    }
+}
+
+/// K-weighting filter for one channel, with coefficients derived for any sample
+/// rate (same analog prototypes as libebur128).
+#[derive(Debug, Clone, Copy)]
+pub struct KWeighting {
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    pub fn new(sample_rate: u32) -> Self {
+        let fs = sample_rate as f64;
+
+        let f0 = 1681.974450955533;
+        let g = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let vh = 10f64.powf(g / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        KWeighting { shelf, highpass }
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        self.highpass.process(self.shelf.process(x))
+    }
+}
+
+/// Whether `ch` is the LFE channel in FLAC's default channel order.
+pub fn is_lfe(channels: u32, ch: usize) -> bool {
+    matches!((channels, ch), (6, 3) | (7, 3) | (8, 3))
+}
+
+/// BS.1770 channel weight for FLAC's default channel order. LFE is excluded,
+/// surround channels get +1.5 dB.
+pub fn channel_weight(channels: u32, ch: usize) -> f64 {
+    match (channels, ch) {
+        _ if is_lfe(channels, ch) => 0.0,
+        (4, 2..=3) | (5, 3..=4) | (6, 4..=5) | (7, 4..=6) | (8, 4..=7) => 1.41,
+        _ => 1.0,
+    }
+}
+
+pub fn power_to_lufs(power: f64) -> f64 {
+    if power <= 0.0 {
+        f64::NEG_INFINITY
+    } else {
+        -0.691 + 10.0 * power.log10()
+    }
+}
+
+/// Gated integrated loudness over a set of 400 ms block powers.
+pub fn integrated_loudness(blocks: &[f64]) -> f64 {
+    let above = |gate: f64| blocks.iter().cloned().filter(move |&p| power_to_lufs(p) > gate);
+
+    let abs_count = above(ABSOLUTE_GATE_LUFS).count();
+    if abs_count == 0 {
+        return LUFS_FLOOR;
+    }
+    let abs_mean = above(ABSOLUTE_GATE_LUFS).sum::<f64>() / abs_count as f64;
+
+    let relative_gate = power_to_lufs(abs_mean) + RELATIVE_GATE_LU;
+    let gate = relative_gate.max(ABSOLUTE_GATE_LUFS);
+    let rel_count = above(gate).count();
+    if rel_count == 0 {
+        return LUFS_FLOOR;
+    }
+    power_to_lufs(above(gate).sum::<f64>() / rel_count as f64)
+}
+
+fn subblock_len(sample_rate: u32) -> usize {
+    ((SUBBLOCK_SECONDS * sample_rate as f64).round() as usize).max(1)
+}
+
+/// Momentary (400 ms) loudness in LUFS, one value per 100 ms step. Element
+/// `i` covers sub-blocks `i ..= i + 3`.
+pub fn momentary_loudness(subblocks: &[f64]) -> Vec<f64> {
+    subblocks
+        .windows(SUBBLOCKS_PER_MOMENTARY)
+        .map(|w| power_to_lufs(w.iter().sum::<f64>() / SUBBLOCKS_PER_MOMENTARY as f64))
+        .collect()
+}
+
+/// Short-term (3 s) loudness in LUFS, one value per 100 ms step once a full
+/// window is available. Element `i` covers sub-blocks `i ..= i + 29`.
+pub fn short_term_loudness(subblocks: &[f64]) -> Vec<f64> {
+    subblocks
+        .windows(SUBBLOCKS_PER_SHORT_TERM)
+        .map(|w| power_to_lufs(w.iter().sum::<f64>() / SUBBLOCKS_PER_SHORT_TERM as f64))
+        .collect()
+}
+
+/// ReplayGain 2.0 gain (dB) for a given integrated loudness.
+pub fn replaygain(lufs: f64) -> f64 {
+    REPLAYGAIN_REFERENCE_LUFS - lufs
+}
+
+#[derive(Debug, Clone)]
+pub struct Loudness {
+    /// Gated integrated loudness in LUFS.
+    pub integrated: f64,
+    /// Power of every 400 ms gating block, kept for album-level pooling.
+    pub blocks: Vec<f64>,
+    /// Weighted power of every 100 ms sub-block, for loudness timelines.
+    pub subblocks: Vec<f64>,
+}
+
+/// Streaming BS.1770 meter fed one interleaved frame at a time.
+pub struct LoudnessMeter {
+    filters: Vec<KWeighting>,
+    weights: Vec<f64>,
+    subblock_len: usize,
+    subblock_pos: usize,
+    subblock_acc: Vec<f64>,
+    subblocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u32, sample_rate: u32) -> Self {
+        let filter = KWeighting::new(sample_rate);
+        LoudnessMeter {
+            filters: vec![filter; channels as usize],
+            weights: (0..channels as usize).map(|ch| channel_weight(channels, ch)).collect(),
+            subblock_len: subblock_len(sample_rate),
+            subblock_pos: 0,
+            subblock_acc: vec![0.0; channels as usize],
+            subblocks: Vec::new(),
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        for (ch, &x) in frame.iter().enumerate() {
+            let y = self.filters[ch].process(x);
+            self.subblock_acc[ch] += y * y;
+        }
+        self.subblock_pos += 1;
+
+        if self.subblock_pos == self.subblock_len {
+            let n = self.subblock_len as f64;
+            let power = self
+                .subblock_acc
+                .iter()
+                .zip(&self.weights)
+                .map(|(acc, w)| w * acc / n)
+                .sum();
+            self.subblocks.push(power);
+            self.subblock_acc.iter_mut().for_each(|acc| *acc = 0.0);
+            self.subblock_pos = 0;
+        }
+    }
+
+    pub fn finish(self) -> Loudness {
+        let blocks: Vec<f64> = self
+            .subblocks
+            .windows(SUBBLOCKS_PER_MOMENTARY)
+            .map(|w| w.iter().sum::<f64>() / SUBBLOCKS_PER_MOMENTARY as f64)
+            .collect();
+        Loudness {
+            integrated: integrated_loudness(&blocks),
+            blocks,
+            subblocks: self.subblocks,
+        }
+    }
+}
+
+// ─── True peak ────────────────────────────────────────────────────────────────
+
+fn oversampling_factor(sample_rate: u32) -> usize {
+    match sample_rate {
+        0..=95_999 => 4,
+        96_000..=191_999 => 2,
+        _ => 1,
+    }
+}
+
+/// Polyphase interpolation filter: `phases[p][k]` is tap `p + factor * k` of a
+/// Blackman-windowed sinc low-pass at the original Nyquist frequency.
+fn interpolation_phases(factor: usize) -> Vec<Vec<f64>> {
+    if factor == 1 {
+        return Vec::new();
+    }
+    let taps = TRUE_PEAK_TAPS_PER_PHASE * factor;
+    let centre = (taps - 1) as f64 / 2.0;
+    let h: Vec<f64> = (0..taps)
+        .map(|n| {
+            let t = (n as f64 - centre) / factor as f64;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t)
+            };
+            let x = 2.0 * std::f64::consts::PI * n as f64 / (taps - 1) as f64;
+            let window = 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos();
+            sinc * window
+        })
+        .collect();
+
+    (0..factor)
+        .map(|p| {
+            let phase: Vec<f64> = (0..TRUE_PEAK_TAPS_PER_PHASE).map(|k| h[p + factor * k]).collect();
+            // Unity DC gain per phase so a full-scale DC signal reads 0 dBTP
+            let gain: f64 = phase.iter().sum();
+            phase.into_iter().map(|c| c / gain).collect()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct TruePeak {
+    /// True peak as a linear fraction of full scale.
+    pub peak: f64,
+    /// True peak of every 100 ms sub-block (aligned with `Loudness::subblocks`).
+    pub subblock_peaks: Vec<f64>,
+    /// First frame of every intersample over.
+    pub overs: Vec<u64>,
+}
+
+/// Streaming true-peak meter fed one interleaved frame at a time.
+pub struct TruePeakMeter {
+    phases: Vec<Vec<f64>>,
+    /// Each channel's last samples, written twice so that the filter window
+    /// is always one contiguous slice.
+    history: Vec<[f64; 2 * TRUE_PEAK_TAPS_PER_PHASE]>,
+    pos: usize,
+    subblock_len: usize,
+    subblock_pos: usize,
+    subblock_peak: f64,
+    subblock_peaks: Vec<f64>,
+    frame: u64,
+    last_over: Option<u64>,
+    overs: Vec<u64>,
+}
+
+impl TruePeakMeter {
+    pub fn new(channels: u32, sample_rate: u32) -> Self {
+        TruePeakMeter {
+            phases: interpolation_phases(oversampling_factor(sample_rate)),
+            history: vec![[0.0; 2 * TRUE_PEAK_TAPS_PER_PHASE]; channels as usize],
+            pos: 0,
+            subblock_len: subblock_len(sample_rate),
+            subblock_pos: 0,
+            subblock_peak: 0.0,
+            subblock_peaks: Vec::new(),
+            frame: 0,
+            last_over: None,
+            overs: Vec::new(),
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        self.pos = (self.pos + 1) % TRUE_PEAK_TAPS_PER_PHASE;
+        let mut over = false;
+        for (ch, &x) in frame.iter().enumerate() {
+            let history = &mut self.history[ch];
+            history[self.pos] = x;
+            history[self.pos + TRUE_PEAK_TAPS_PER_PHASE] = x;
+            self.subblock_peak = self.subblock_peak.max(x.abs());
+            // Newest sample last
+            let window = &history[self.pos + 1..=self.pos + TRUE_PEAK_TAPS_PER_PHASE];
+            for phase in &self.phases {
+                let y: f64 = phase.iter().zip(window.iter().rev()).map(|(c, h)| c * h).sum();
+                self.subblock_peak = self.subblock_peak.max(y.abs());
+                over |= y.abs() > 1.0;
+            }
+        }
+
+        // Overs closer than one filter length belong to the same event. The
+        // interpolator delays by half its length; shift the timestamp back.
+        if over {
+            let gap = TRUE_PEAK_TAPS_PER_PHASE as u64;
+            let new_event = match self.last_over {
+                Some(last) => self.frame - last > gap,
+                None => true,
+            };
+            if new_event {
+                self.overs.push(self.frame.saturating_sub(gap / 2));
+            }
+            self.last_over = Some(self.frame);
+        }
+        self.frame += 1;
+
+        self.subblock_pos += 1;
+        if self.subblock_pos == self.subblock_len {
+            self.subblock_peaks.push(self.subblock_peak);
+            self.subblock_peak = 0.0;
+            self.subblock_pos = 0;
+        }
+    }
+
+    pub fn finish(mut self) -> TruePeak {
+        if self.subblock_pos > 0 {
+            self.subblock_peaks.push(self.subblock_peak);
+        }
+        TruePeak {
+            peak: self.subblock_peaks.iter().cloned().fold(0.0, f64::max),
+            subblock_peaks: self.subblock_peaks,
+            overs: self.overs,
+        }
+    }
+}
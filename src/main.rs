@@ -1,27 +1,616 @@
-use clap::Parser;
+mod acoustid;
+mod cache;
+mod clicks;
+mod clipping;
+mod compare;
+mod database;
+mod diff;
+mod document;
+mod export;
+mod flacmeta;
+mod foobar;
+mod html;
+mod integrity;
+mod json;
+mod lang;
+mod loudness;
+mod markdown;
+mod merge;
+mod musicbrainz;
+mod noise;
+mod pdf;
+mod png;
+mod rumble;
+mod seal;
+mod spectrum;
+mod tagging;
+mod template;
+mod waveform;
+mod xlsx;
+
+use clap::{Parser, Subcommand, ValueEnum};
 use claxon::FlacReader;
 use chrono::Local;
+use clicks::ClickDetector;
+use lang::{Lang, Strings};
+use clipping::{ClipDetector, Clipping};
+use loudness::{KWeighting, LoudnessMeter, TruePeak, TruePeakMeter};
+use noise::NoiseFloorMeter;
+use rumble::RumbleMeter;
+use spectrum::{Spectrum, SpectrumAnalyzer};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
+use waveform::{Waveform, WaveformBuilder};
 
 /// Dynamic Range meter for FLAC files.
 /// Computes the DR value per the DR Loudness Standard (Pleasurize Music Foundation).
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Folder containing FLAC files (default: current directory)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Folder containing FLAC files, or a single FLAC file (default: current directory)
     #[arg(default_value = ".")]
     folder: PathBuf,
 
-    /// Output report file path (default: <folder>/dr_report.txt)
-    #[arg(short, long)]
+    /// Output report file path, or - for stdout (default: <folder>/dr_report.txt, or .json etc. for --format);
+    /// with --recursive, the path of the combined summary
+    #[arg(short, long, global = true)]
     output: Option<PathBuf>,
 
+    /// Write the report to stdout (same as --output -); progress goes to stderr
+    #[arg(long, global = true, conflicts_with = "output")]
+    stdout: bool,
+
+    /// Also scan subfolders, writing a report into each folder that contains FLAC files
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with_all = ["timeline", "dr_timeline", "dump_blocks", "export_blocks", "psr_timeline", "clip_log", "labels", "waveforms", "spectrograms"]
+    )]
+    recursive: bool,
+
+    /// With --recursive, also write a summary of every album to <folder>/dr_summary.txt
+    /// (.xlsx for --format xlsx); --output names it instead
+    #[arg(long, global = true, requires = "recursive")]
+    combined: bool,
+
+    /// Report format (default: from the --output extension, else text)
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    format: Option<Format>,
+
+    /// Render the report through this Handlebars-style template instead of a
+    /// built-in format (see README for the fields and syntax)
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "format")]
+    template: Option<PathBuf>,
+
+    /// Also upsert every track into this SQLite database (needs the sqlite3 shell)
+    #[arg(long, global = true, value_name = "FILE")]
+    db: Option<PathBuf>,
+
+    /// Add the files an existing report doesn't list yet instead of rewriting it
+    /// (text, CSV and NDJSON reports)
+    #[arg(long, global = true, conflicts_with_all = ["stdout", "template", "recursive"])]
+    append: bool,
+
+    /// Name tracks without a TITLE tag by their AcoustID fingerprint, with this
+    /// acoustid.org API key; needs fpcalc (Chromaprint) and curl
+    #[arg(long, global = true, value_name = "KEY")]
+    acoustid: Option<String>,
+
+    /// Look the release up on MusicBrainz (by MUSICBRAINZ_ALBUMID, else by
+    /// album and artist) and name it in the report header; needs curl
+    #[arg(long, global = true)]
+    musicbrainz: bool,
+
+    /// Skip files that already carry a DYNAMIC_RANGE tag, or that the --db
+    /// database holds unchanged, so library rescans only measure new music (not with --tag
+    /// or --write-replaygain, whose album values need every track)
+    #[arg(long, global = true, conflicts_with_all = ["tag", "write_replaygain"])]
+    missing_only: bool,
+
+    /// Only analyse tracks whose ARTIST or ALBUMARTIST contains this text
+    /// (case-insensitive)
+    #[arg(long, global = true, value_name = "TEXT")]
+    filter_artist: Option<String>,
+
+    /// Only analyse tracks whose GENRE contains this text (case-insensitive)
+    #[arg(long, global = true, value_name = "TEXT")]
+    filter_genre: Option<String>,
+
+    /// Only analyse tracks whose DATE falls in this year or range: 1979,
+    /// 1970-1979, 2010+ (or 2010-) and -1999
+    #[arg(long, global = true, value_name = "YEARS", allow_hyphen_values = true, value_parser = parse_year_range)]
+    filter_year: Option<YearRange>,
+
+    /// Write DYNAMIC_RANGE and ALBUM_DYNAMIC_RANGE Vorbis comments into the analysed files
+    /// (standard measurement only)
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = [
+            "algorithm", "downmix", "lfe", "block_seconds", "top_ratio", "nth_peak", "start", "end", "quick",
+            "trim_silence", "silence_gate",
+        ]
+    )]
+    tag: bool,
+
+    /// Write REPLAYGAIN_TRACK_GAIN/PEAK and REPLAYGAIN_ALBUM_GAIN/PEAK Vorbis comments
+    /// (ReplayGain 2.0) into the analysed files (standard measurement only)
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = [
+            "algorithm", "downmix", "lfe", "block_seconds", "top_ratio", "nth_peak", "start", "end", "quick",
+            "trim_silence", "silence_gate",
+        ]
+    )]
+    write_replaygain: bool,
+
+    /// Also store each track's results (its sidecar JSON) in the FLAC file itself, as a
+    /// DR_MEASURE Vorbis comment
+    #[arg(long, global = true)]
+    embed: bool,
+
+    /// Keep the files' modification times when --tag, --write-replaygain, --embed or `untag`
+    /// rewrite their tags
+    #[arg(long, global = true)]
+    preserve_mtime: bool,
+
+    /// Append the report's SHA-256 to its end, for `verify` (text reports and foobar2000 logs)
+    #[arg(long, global = true, conflicts_with_all = ["stdout", "template", "append"])]
+    hash: bool,
+
+    /// Also sign the report with this Ed25519 private key (PEM; needs the openssl tool)
+    #[arg(long, global = true, value_name = "FILE", conflicts_with_all = ["stdout", "template", "append"])]
+    sign_key: Option<PathBuf>,
+
+    /// Write a DR database submission log, foo_dr.txt: the foobar2000 log with
+    /// dr14tt values and standard parameters, only for a fully analysed, tagged album
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = [
+            "format", "template", "append", "hash", "sign_key", "algorithm", "downmix", "lfe",
+            "block_seconds", "top_ratio", "nth_peak", "start", "end", "quick", "trim_silence", "silence_gate",
+        ]
+    )]
+    submission: bool,
+
+    /// Check each file's decoded audio against the MD5 in its STREAMINFO and
+    /// list corrupt files in the report (exit status 1 if any)
+    #[arg(long, global = true)]
+    verify: bool,
+
+    /// Also write each track's metrics to a <track>.dr.json file next to it
+    #[arg(long, global = true)]
+    sidecar: bool,
+
+    /// Plain ASCII instead of box-drawing characters, dashes and symbols in the
+    /// text report and console output, for legacy terminals and code pages
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Language of the report headings and ratings
+    #[arg(long, global = true, value_enum, default_value_t)]
+    lang: Lang,
+
     /// Suppress console output
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     quiet: bool,
+
+    /// Show progress as an aligned table, one row of results per track as it finishes
+    #[arg(long, global = true)]
+    table: bool,
+
+    /// Print a bar chart of the track DR values to the console after the scan
+    #[arg(long, global = true)]
+    chart: bool,
+
+    /// Colour the console's DR values by rating (auto: on a terminal, unless NO_COLOR is set)
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorMode,
+
+    /// Add detailed sections (per-channel DR and DC offset, spectral cutoffs, …) to the report
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// List the N lowest-DR tracks of the scan, with their paths, at the end of
+    /// the report (with --recursive, of the combined summary)
+    #[arg(long, global = true, value_name = "N")]
+    worst: Option<usize>,
+
+    /// Print DR values with N decimals instead of rounding to an integer
+    #[arg(long, global = true, value_name = "N", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=4))]
+    precision: u8,
+
+    /// Order of the tracks in the report
+    #[arg(long, global = true, value_enum, value_name = "KEY", default_value_t)]
+    sort: SortKey,
+
+    /// Reverse the --sort order (e.g. highest DR first)
+    #[arg(long, global = true)]
+    reverse: bool,
+
+    /// How the album DR is derived: pool every block of every track (official), or average the track DRs
+    #[arg(long, global = true, value_enum, default_value_t = AlbumMethod::Mean)]
+    album_method: AlbumMethod,
+
+    /// Warn when the L/R RMS balance differs by more than this many dB
+    #[arg(long, global = true, value_name = "DB", default_value_t = 1.0)]
+    balance_warn: f64,
+
+    /// Interpretation of the DR standard to apply (`--compat` is an older alias)
+    #[arg(long, global = true, value_enum, value_name = "VARIANT", default_value_t, alias = "compat")]
+    algorithm: Algorithm,
+
+    /// Measure DR on a downmix instead of averaging the per-channel values
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    downmix: Option<Downmix>,
+
+    /// Also measure stereo files as mid (L+R) and side (L−R) and report both
+    #[arg(long, global = true)]
+    ms: bool,
+
+    /// How the LFE channel of 5.1/6.1/7.1 files enters the per-channel DR average
+    #[arg(long, global = true, value_enum, value_name = "MODE", default_value_t)]
+    lfe: Lfe,
+
+    /// DR block length in seconds (spec: 3)
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = BLOCKSIZE_SECONDS, value_parser = parse_positive)]
+    block_seconds: f64,
+
+    /// Fraction of loudest blocks whose RMS is averaged (spec: 0.2)
+    #[arg(long, global = true, value_name = "RATIO", default_value_t = UPMOST_BLOCKS_RATIO, value_parser = parse_ratio)]
+    top_ratio: f64,
+
+    /// Which highest block peak is used, 1 = the maximum (spec: 2)
+    #[arg(long, global = true, value_name = "N", default_value_t = NTH_HIGHEST_PEAK, value_parser = parse_nth_peak)]
+    nth_peak: usize,
+
+    /// Analyse each file from this position on (SS, MM:SS or HH:MM:SS, decimals allowed)
+    #[arg(long, global = true, value_name = "TIME", value_parser = parse_time)]
+    start: Option<f64>,
+
+    /// Stop analysing each file at this position (SS, MM:SS or HH:MM:SS)
+    #[arg(long, global = true, value_name = "TIME", value_parser = parse_time)]
+    end: Option<f64>,
+
+    /// Estimate quickly: analyse only every 4th 3 s block (values are marked with ~)
+    #[arg(long, global = true)]
+    quick: bool,
+
+    /// Keep each folder's results in a .dr-measure-cache file, and analyse only new or changed
+    /// files on later runs
+    #[arg(long, global = true)]
+    cache: bool,
+
+    /// Analyse up to N files at once (default: one per CPU core)
+    #[arg(short, long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    jobs: Option<u32>,
+
+    /// Exclude leading and trailing digital silence from the analysis
+    #[arg(long, global = true)]
+    trim_silence: bool,
+
+    /// Drop 3 s blocks whose RMS is below this level (dBFS) from the DR statistics
+    #[arg(long, global = true, value_name = "DB", allow_negative_numbers = true)]
+    silence_gate: Option<f64>,
+
+    /// Columns of the text and CSV track tables, in order (e.g. dr,peak,lufs,file)
+    #[arg(long, global = true, value_enum, value_name = "LIST", value_delimiter = ',')]
+    columns: Option<Vec<Column>>,
+
+    /// Add P50/P95/P99 columns: percentiles of the 3 s block RMS values
+    #[arg(long, global = true)]
+    percentiles: bool,
+
+    /// Also measure a "perceptual" DR on the weighted signal, shown next to the standard one
+    #[arg(long, global = true, value_enum, value_name = "FILTER")]
+    weighting: Option<Weighting>,
+
+    /// Add a column with the gain that brings each track to the --gain-target level
+    #[arg(long, global = true)]
+    gain: bool,
+
+    /// Level for the --gain column: LUFS (-14, -16lufs) or plain RMS (-18rms)
+    #[arg(long, global = true, value_name = "LEVEL", default_value = "-14", allow_hyphen_values = true, value_parser = parse_gain_target)]
+    gain_target: GainTarget,
+
+    /// Add a stereo correlation column (-1 = out of phase, +1 = mono)
+    #[arg(long, global = true)]
+    correlation: bool,
+
+    /// Export momentary (400 ms) and short-term (3 s) loudness of every track (CSV, or JSON if the path ends in .json)
+    #[arg(long, global = true, value_name = "FILE")]
+    timeline: Option<PathBuf>,
+
+    /// Export each track's DR over a sliding window (CSV, or JSON if the path ends in .json)
+    #[arg(long, global = true, value_name = "FILE")]
+    dr_timeline: Option<PathBuf>,
+
+    /// Sliding window length for --dr-timeline, in seconds (rounded to 3 s blocks)
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 30.0)]
+    dr_window: f64,
+
+    /// Dump the per-block RMS and peak values behind the DR figure (CSV, or JSON if the path ends in .json)
+    #[arg(long, global = true, value_name = "FILE")]
+    dump_blocks: Option<PathBuf>,
+
+    /// Write a CSV of every track's DR blocks (start time, RMS and peak dB) into this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    export_blocks: Option<PathBuf>,
+
+    /// Export the short-term PSR timeline of every track (CSV, or JSON if the path ends in .json)
+    #[arg(long, global = true, value_name = "FILE")]
+    psr_timeline: Option<PathBuf>,
+
+    /// Export the time positions of each track's 10 longest clipped regions (CSV, or JSON if the path ends in .json)
+    #[arg(long, global = true, value_name = "FILE")]
+    clip_log: Option<PathBuf>,
+
+    /// Write an Audacity label track of each track's clipped regions, intersample overs
+    /// and clicks into this directory (tracks without any are skipped)
+    #[arg(long, global = true, value_name = "DIR")]
+    labels: Option<PathBuf>,
+
+    /// Render a min/max waveform PNG of every track into this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    waveforms: Option<PathBuf>,
+
+    /// Render a spectrogram PNG of every track into this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    spectrograms: Option<PathBuf>,
+}
+
+/// Parses "90", "1:30" or "1:02:03.5" into seconds.
+fn parse_time(s: &str) -> Result<f64, String> {
+    let mut secs = 0.0;
+    for (i, part) in s.split(':').enumerate() {
+        if i > 2 {
+            return Err("expected SS, MM:SS or HH:MM:SS".to_string());
+        }
+        let value: f64 = part.parse().map_err(|_| format!("invalid time '{}'", s))?;
+        if value < 0.0 || !value.is_finite() {
+            return Err(format!("invalid time '{}'", s));
+        }
+        secs = secs * 60.0 + value;
+    }
+    Ok(secs)
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+        _ => Err(format!("expected a positive number, got '{}'", s)),
+    }
+}
+
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x > 0.0 && x <= 1.0 => Ok(x),
+        _ => Err(format!("expected a ratio in (0, 1], got '{}'", s)),
+    }
+}
+
+fn parse_nth_peak(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected a whole number of at least 1, got '{}'", s)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human-readable report
+    Text,
+    /// Structured document for scripts
+    Json,
+    /// One row per track, for spreadsheets and pandas
+    Csv,
+    /// The JSON document as XML elements
+    Xml,
+    /// Standalone page with a sortable table and charts
+    Html,
+    /// GitHub-flavored Markdown table and summary
+    Md,
+    /// Log in the layout of foobar2000's DR Meter, for the DR database
+    Foobar,
+    /// The JSON document as YAML
+    Yaml,
+    /// One JSON object per track, streamed to stdout (or --output) as each finishes
+    Ndjson,
+    /// Printable A4 report with the summary and track table
+    Pdf,
+    /// Excel workbook with a summary sheet and a sheet per album
+    Xlsx,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Xml => "xml",
+            Format::Html => "html",
+            Format::Md => "md",
+            Format::Foobar => "log",
+            Format::Yaml => "yaml",
+            Format::Ndjson => "ndjson",
+            Format::Pdf => "pdf",
+            Format::Xlsx => "xlsx",
+        }
+    }
+
+    /// The format an output path's extension asks for, if any.
+    fn from_path(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        Format::value_variants().iter().copied().find(|f| f.extension() == ext)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum AlbumMethod {
+    /// DR over the blocks of all tracks pooled together (DR Loudness Standard)
+    Pooled,
+    /// Mean of the per-track DR values
+    #[default]
+    Mean,
+}
+
+/// A column of the track table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Column {
+    /// DR value
+    Dr,
+    /// Letter grade
+    Grade,
+    /// DR on the --weighting signal
+    DrK,
+    /// Sample peak
+    Peak,
+    /// True peak
+    TruePeak,
+    /// RMS level
+    Rms,
+    /// Crest factor
+    Crest,
+    /// Noise floor
+    Floor,
+    /// Integrated loudness
+    Lufs,
+    /// Peak-to-loudness ratio
+    Plr,
+    /// Duration
+    Duration,
+    /// Sample rate / bit depth / channels
+    Info,
+    /// P50/P95/P99 block RMS (text only)
+    Percentiles,
+    /// Gain to the --gain-target (text only)
+    Gain,
+    /// Stereo correlation
+    Corr,
+    /// File name
+    File,
+}
+
+impl Column {
+    /// The fields of the JSON document the column shows, for CSV output.
+    fn document_keys(self) -> &'static [&'static str] {
+        match self {
+            Column::Dr => &["dr"],
+            Column::Grade => &["grade"],
+            Column::DrK => &["k_dr"],
+            Column::Peak => &["peak_db"],
+            Column::TruePeak => &["true_peak_db"],
+            Column::Rms => &["rms_db"],
+            Column::Crest => &["crest_db"],
+            Column::Floor => &["noise_floor_db"],
+            Column::Lufs => &["lufs"],
+            Column::Plr => &["plr_db"],
+            Column::Duration => &["duration_secs"],
+            Column::Info => &["sample_rate", "bit_depth", "channels"],
+            Column::Percentiles | Column::Gain => &[],
+            Column::Corr => &["correlation"],
+            Column::File => &["file"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    /// File name, numbers by value ("2 - …" before "10 - …")
+    Name,
+    /// DR, lowest first
+    Dr,
+    /// RMS level, quietest first
+    Rms,
+    /// Duration, shortest first
+    Duration,
+    /// DISCNUMBER and TRACKNUMBER tags when every file has them, else the file name
+    #[default]
+    Track,
+}
+
+/// Level a suggested normalization gain aims for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GainTarget {
+    /// Integrated loudness (LUFS)
+    Lufs(f64),
+    /// Plain RMS over the whole track (dBFS)
+    Rms(f64),
+}
+
+/// Parses "-14" or "-14lufs" (loudness) and "-18rms" (RMS level).
+fn parse_gain_target(s: &str) -> Result<GainTarget, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let (number, target): (&str, fn(f64) -> GainTarget) = if let Some(n) = lower.strip_suffix("rms") {
+        (n, GainTarget::Rms)
+    } else {
+        (lower.strip_suffix("lufs").unwrap_or(&lower), GainTarget::Lufs)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(level) if level.is_finite() && level <= 0.0 => Ok(target(level)),
+        _ => Err(format!("expected a level like -14, -14lufs or -18rms, got '{}'", s)),
+    }
+}
+
+/// Inclusive range of release years for --filter-year; open ends are None.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct YearRange {
+    from: Option<u32>,
+    to: Option<u32>,
+}
+
+impl YearRange {
+    fn contains(&self, year: u32) -> bool {
+        self.from.is_none_or(|from| year >= from) && self.to.is_none_or(|to| year <= to)
+    }
+}
+
+/// Parses "1979", "1970-1979", "2010+", "2010-" and "-1999".
+fn parse_year_range(s: &str) -> Result<YearRange, String> {
+    let invalid = || format!("expected a year or range like 1979, 1970-1979, 2010+ or -1999, got '{}'", s);
+    let year = |y: &str| if y.is_empty() { Ok(None) } else { y.parse().map(Some).map_err(|_| invalid()) };
+    let s = s.trim();
+    let range = match (s.strip_suffix('+'), s.split_once('-')) {
+        (Some(from), _) => YearRange { from: year(from)?, to: None },
+        (None, Some((from, to))) => YearRange { from: year(from)?, to: year(to)? },
+        (None, None) => YearRange { from: year(s)?, to: year(s)? },
+    };
+    match range {
+        YearRange { from: None, to: None } => Err(invalid()),
+        YearRange { from: Some(from), to: Some(to) } if from > to => Err(invalid()),
+        _ => Ok(range),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Weighting {
+    /// BS.1770 K-weighting (the loudness filter)
+    K,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Downmix {
+    /// Average of all channels
+    Mono,
 }
 
 // ─── DR Algorithm ────────────────────────────────────────────────────────────
@@ -38,35 +627,421 @@ struct Args {
 //     peak_loud  = peak[-2]   (2nd highest peak block, NTH_HIGHEST_PEAK = 2)
 //  5. DR_channel = 20 * log10( peak_loud / rms_loud )  (0.0 if rms_loud == 0)
 //  6. DR_track   = mean( DR_channel ), rounded to nearest integer.
+//
+// `--algorithm dr14tt` reproduces the DR14 T.T. Meter (dr14_t.meter, the
+// foobar2000 DR Meter lineage) instead:
+//   • the trailing partial block is always present — an empty one (RMS 0,
+//     peak 0) when the length is an exact multiple of the block size;
+//   • tracks shorter than 3 blocks get DR 0;
+//   • top_n = floor( total_blocks * 0.2 ), at least 1;
+//   • the 2nd highest peak is picked among all blocks, the empty one included;
+//   • rounding is Python's round-half-to-even.
+//
+// `--algorithm pmf-official` follows the PMF's published description of the
+// offline meter literally:
+//   • only complete 3 s blocks are measured; the partial tail is dropped
+//     (a track shorter than one block is measured as a single block);
+//   • top_n = ceil( total_blocks * 0.2 ), i.e. "at least 20 %";
+//   • the 2nd highest peak is therefore picked among complete blocks only;
+//   • rounding is half-up (x.5 → x+1, also for negative values).
 
 const BLOCKSIZE_SECONDS: f64 = 3.0;
 const UPMOST_BLOCKS_RATIO: f64 = 0.2;
 const NTH_HIGHEST_PEAK: usize = 2; // 1-based from top → [-2] in Python
 
-fn block_size_for_sample_rate(sample_rate: u32) -> usize {
-    (BLOCKSIZE_SECONDS * sample_rate as f64).round() as usize
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two folders of the same album (e.g. original CD vs remaster) track by track
+    Compare {
+        /// Folder with the first version (A)
+        a: PathBuf,
+        /// Folder with the second version (B); the comparison is written here
+        /// unless --output is given
+        b: PathBuf,
+    },
+    /// Merge existing per-album reports (text, foobar2000 log or JSON) into one
+    /// library report, without analysing again; --sort dr orders it by album DR
+    Merge {
+        /// Report files, or folders to search for dr_report.json / .txt / .log or foo_dr.txt
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+    },
+    /// Show what changed between two JSON or NDJSON reports of the same folder
+    /// (added and removed files, metric and tag changes); printed unless --output is given
+    Diff {
+        /// The earlier report, or a folder with a dr_report.json
+        old: PathBuf,
+        /// The later report, or a folder with a dr_report.json
+        new: PathBuf,
+    },
+    /// Remove the DR, ReplayGain and embedded-result tags written by --tag,
+    /// --write-replaygain and --embed (with --recursive, from every folder below)
+    Untag {
+        /// Folder (or single FLAC file) to clean
+        folder: PathBuf,
+    },
+    /// Print the JSON Schema of the JSON and NDJSON reports
+    Schema,
+    /// Check that a report written with --hash or --sign-key is unchanged
+    Verify {
+        /// The text report or foobar2000 log
+        report: PathBuf,
+        /// Ed25519 public key (PEM) to check the signature with
+        #[arg(long, value_name = "FILE")]
+        pubkey: Option<PathBuf>,
+    },
+}
+
+/// DR algorithm variant applied to the block statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// drmeter (Python), the reference this tool was ported from
+    #[default]
+    Drmeter,
+    /// DR14 T.T. Meter / foobar2000 DR Meter
+    Dr14tt,
+    /// Literal reading of the PMF's published measurement description
+    PmfOfficial,
+}
+
+impl Algorithm {
+    fn round(self, dr: f64) -> i32 {
+        match self {
+            Algorithm::Drmeter => dr.round() as i32,
+            Algorithm::Dr14tt => round_half_even(dr) as i32,
+            Algorithm::PmfOfficial => (dr + 0.5).floor() as i32,
+        }
+    }
+
+    /// Report header description; None for the default.
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Algorithm::Drmeter => None,
+            Algorithm::Dr14tt => Some("DR14 T.T. Meter compatible"),
+            Algorithm::PmfOfficial => Some("PMF official (complete blocks only, half-up rounding)"),
+        }
+    }
+}
+
+/// Part the LFE channel plays in the per-channel DR average.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum Lfe {
+    /// Leave the LFE channel out
+    Exclude,
+    /// Plain mean over all channels, LFE included
+    #[default]
+    Include,
+    /// BS.1770 channel weights: LFE excluded, surrounds +1.5 dB
+    Weighted,
+}
+
+/// Algorithm variant and block constants. Only the spec values are standard;
+/// the CLI can override them to study how sensitive DR is to each.
+#[derive(Debug, Clone, Copy)]
+struct DrParams {
+    algorithm: Algorithm,
+    block_seconds: f64,
+    top_ratio: f64,
+    nth_peak: usize,
+    lfe: Lfe,
+}
+
+impl Default for DrParams {
+    fn default() -> Self {
+        DrParams {
+            algorithm: Algorithm::default(),
+            block_seconds: BLOCKSIZE_SECONDS,
+            top_ratio: UPMOST_BLOCKS_RATIO,
+            nth_peak: NTH_HIGHEST_PEAK,
+            lfe: Lfe::default(),
+        }
+    }
+}
+
+impl DrParams {
+    fn is_standard(&self) -> bool {
+        self.block_seconds == BLOCKSIZE_SECONDS
+            && self.top_ratio == UPMOST_BLOCKS_RATIO
+            && self.nth_peak == NTH_HIGHEST_PEAK
+    }
+
+    /// Combines per-channel DR values (in FLAC channel order) into the track
+    /// value according to the LFE mode.
+    fn channel_mean(&self, values: &[f64]) -> f64 {
+        let channels = values.len() as u32;
+        let weight = |ch| match self.lfe {
+            Lfe::Include => 1.0,
+            Lfe::Exclude if loudness::is_lfe(channels, ch) => 0.0,
+            Lfe::Exclude => 1.0,
+            Lfe::Weighted => loudness::channel_weight(channels, ch),
+        };
+        let (sum, total) = values
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(sum, total), (ch, &v)| (sum + weight(ch) * v, total + weight(ch)));
+        if total > 0.0 {
+            sum / total
+        } else {
+            0.0
+        }
+    }
+}
+
+fn round_half_even(x: f64) -> f64 {
+    if (x - x.trunc()).abs() == 0.5 {
+        2.0 * (x / 2.0).round()
+    } else {
+        x.round()
+    }
+}
+
+fn block_size_for_sample_rate(sample_rate: u32, block_seconds: f64) -> usize {
+    ((block_seconds * sample_rate as f64).round() as usize).max(1)
 }
 
 #[derive(Debug, Clone)]
 struct BlockStats {
     rms: f64,
     peak: f64,
+    /// File position (frame) of the block's first sample.
+    start: u64,
+}
+
+/// Independent accumulators in the block statistics: each lane sums every
+/// LANES-th sample of a block, one AVX2 register of f64 or two NEON ones.
+const LANES: usize = 4;
+
+/// Adds `samples`, a whole number of lane sets, to the lane sums: with AVX2 on
+/// x86-64 CPUs that have it, NEON on AArch64, one lane at a time elsewhere.
+/// All three do the same additions in the same order, so they agree to the bit.
+fn lane_sums(sum_sq: &mut [f64; LANES], peak: &mut [f64; LANES], samples: &[f64]) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU has just been found to support AVX2
+        unsafe { lane_sums_avx2(sum_sq, peak, samples) };
+        return;
+    }
+    // SAFETY: NEON is part of every AArch64 CPU
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        lane_sums_neon(sum_sq, peak, samples)
+    }
+    #[cfg(not(target_arch = "aarch64"))]
+    lane_sums_scalar(sum_sq, peak, samples)
+}
+
+// Only the tests use it on AArch64
+#[cfg_attr(target_arch = "aarch64", allow(dead_code))]
+fn lane_sums_scalar(sum_sq: &mut [f64; LANES], peak: &mut [f64; LANES], samples: &[f64]) {
+    for chunk in samples.chunks_exact(LANES) {
+        for lane in 0..LANES {
+            sum_sq[lane] += chunk[lane] * chunk[lane];
+            peak[lane] = peak[lane].max(chunk[lane].abs());
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn lane_sums_avx2(sum_sq: &mut [f64; LANES], peak: &mut [f64; LANES], samples: &[f64]) {
+    use std::arch::x86_64::*;
+    // Clearing the sign bit gives the magnitude
+    let sign = _mm256_set1_pd(-0.0);
+    let mut sq = _mm256_loadu_pd(sum_sq.as_ptr());
+    let mut pk = _mm256_loadu_pd(peak.as_ptr());
+    for chunk in samples.chunks_exact(LANES) {
+        let x = _mm256_loadu_pd(chunk.as_ptr());
+        sq = _mm256_add_pd(sq, _mm256_mul_pd(x, x));
+        pk = _mm256_max_pd(pk, _mm256_andnot_pd(sign, x));
+    }
+    _mm256_storeu_pd(sum_sq.as_mut_ptr(), sq);
+    _mm256_storeu_pd(peak.as_mut_ptr(), pk);
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn lane_sums_neon(sum_sq: &mut [f64; LANES], peak: &mut [f64; LANES], samples: &[f64]) {
+    use std::arch::aarch64::*;
+    // Lanes 0-1 and 2-3
+    let mut sq = [vld1q_f64(sum_sq.as_ptr()), vld1q_f64(sum_sq.as_ptr().add(2))];
+    let mut pk = [vld1q_f64(peak.as_ptr()), vld1q_f64(peak.as_ptr().add(2))];
+    for chunk in samples.chunks_exact(LANES) {
+        for half in 0..2 {
+            let x = vld1q_f64(chunk.as_ptr().add(2 * half));
+            sq[half] = vaddq_f64(sq[half], vmulq_f64(x, x));
+            pk[half] = vmaxq_f64(pk[half], vabsq_f64(x));
+        }
+    }
+    vst1q_f64(sum_sq.as_mut_ptr(), sq[0]);
+    vst1q_f64(sum_sq.as_mut_ptr().add(2), sq[1]);
+    vst1q_f64(peak.as_mut_ptr(), pk[0]);
+    vst1q_f64(peak.as_mut_ptr().add(2), pk[1]);
+}
+
+/// Running sum of squares and largest magnitude of one channel's current
+/// block. Sample `k` of the block goes to lane `k % LANES`, however the block
+/// arrives; the last few of a block go to the first lane.
+#[derive(Clone, Default)]
+struct BlockSums {
+    sum_sq: [f64; LANES],
+    peak: [f64; LANES],
+    /// Samples waiting for a full set of lanes.
+    pending: [f64; LANES],
+    pending_len: usize,
+}
+
+impl BlockSums {
+    /// Adds the next `samples` of the block.
+    fn add(&mut self, mut samples: &[f64]) {
+        if self.pending_len > 0 {
+            let fill = (LANES - self.pending_len).min(samples.len());
+            self.pending[self.pending_len..self.pending_len + fill].copy_from_slice(&samples[..fill]);
+            self.pending_len += fill;
+            samples = &samples[fill..];
+            if self.pending_len < LANES {
+                return;
+            }
+            let pending = self.pending;
+            lane_sums(&mut self.sum_sq, &mut self.peak, &pending);
+            self.pending_len = 0;
+        }
+        let whole = samples.len() - samples.len() % LANES;
+        lane_sums(&mut self.sum_sq, &mut self.peak, &samples[..whole]);
+        let rest = &samples[whole..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    /// Statistics of the `len` samples added, and sums cleared for the next
+    /// block.
+    fn take(&mut self, len: usize, start: u64) -> BlockStats {
+        let mut sums = std::mem::take(self);
+        if len == 0 {
+            return BlockStats { rms: 0.0, peak: 0.0, start };
+        }
+        for &x in &sums.pending[..sums.pending_len] {
+            sums.sum_sq[0] += x * x;
+            sums.peak[0] = sums.peak[0].max(x.abs());
+        }
+        let sum_sq: f64 = sums.sum_sq.iter().sum();
+        let peak = sums.peak.iter().fold(0.0f64, |a, &x| a.max(x));
+        // RMS: sqrt( mean( 2 * |x|² ) )
+        BlockStats { rms: (2.0 * sum_sq / len as f64).sqrt(), peak, start }
+    }
+}
+
+/// Frames staged per channel before they are summed, about one FLAC block.
+const STAGED_FRAMES: usize = 4096;
+
+/// Cuts per-channel sample streams into DR blocks of `block_len` samples,
+/// keeping running sums rather than the samples, so that memory stays the
+/// same whatever the block length.
+struct BlockSplitter {
+    block_len: usize,
+    /// Samples in the current block.
+    len: usize,
+    sums: Vec<BlockSums>,
+    blocks: Vec<Vec<BlockStats>>,
+    /// Consecutive frames not summed yet, one slice per channel, the first
+    /// from file position `staged_at`.
+    staged: Vec<Vec<f64>>,
+    staged_at: u64,
+    /// File position of the current block's first frame, and of the frame
+    /// after the last one summed.
+    start: u64,
+    next: u64,
 }
 
-fn compute_block_stats(samples: &[f64]) -> BlockStats {
-    let n = samples.len() as f64;
-    // RMS: sqrt( mean( 2 * |x|² ) )
-    let rms = (samples.iter().map(|x| 2.0 * x * x).sum::<f64>() / n).sqrt();
-    let peak = samples.iter().cloned().fold(0.0f64, |a, x| a.max(x.abs()));
-    BlockStats { rms, peak }
+impl BlockSplitter {
+    fn new(channels: usize, block_len: usize) -> Self {
+        BlockSplitter {
+            block_len,
+            len: 0,
+            sums: vec![BlockSums::default(); channels],
+            blocks: vec![Vec::new(); channels],
+            staged: vec![Vec::with_capacity(STAGED_FRAMES); channels],
+            staged_at: 0,
+            start: 0,
+            next: 0,
+        }
+    }
+
+    /// Adds one sample per channel, from the frame at file position `at`.
+    fn push(&mut self, samples: &[f64], at: u64) {
+        let staged = self.staged[0].len();
+        if staged == STAGED_FRAMES || (staged > 0 && at != self.staged_at + staged as u64) {
+            self.flush();
+        }
+        if self.staged[0].is_empty() {
+            self.staged_at = at;
+        }
+        for (staged, &x) in self.staged.iter_mut().zip(samples) {
+            staged.push(x);
+        }
+    }
+
+    /// Sums the staged frames a channel slice at a time, closing every block
+    /// they complete.
+    fn flush(&mut self) {
+        let frames = self.staged[0].len();
+        if frames == 0 {
+            return;
+        }
+        let mut done = 0;
+        while done < frames {
+            if self.len == 0 {
+                self.start = self.staged_at + done as u64;
+            }
+            let n = (self.block_len - self.len).min(frames - done);
+            for (sums, staged) in self.sums.iter_mut().zip(&self.staged) {
+                sums.add(&staged[done..done + n]);
+            }
+            self.len += n;
+            done += n;
+            if self.len == self.block_len {
+                for (sums, blocks) in self.sums.iter_mut().zip(&mut self.blocks) {
+                    blocks.push(sums.take(self.len, self.start));
+                }
+                self.len = 0;
+            }
+        }
+        self.next = self.staged_at + frames as u64;
+        for staged in &mut self.staged {
+            staged.clear();
+        }
+    }
+
+    /// Closes the partial last block the way `algorithm` wants it: DR14 T.T.
+    /// keeps even an empty one (the flag returned tells whether that
+    /// happened), PMF official drops it unless it is the only block.
+    fn finish(mut self, algorithm: Algorithm) -> (Vec<Vec<BlockStats>>, bool) {
+        self.flush();
+        let partial = self.len > 0;
+        let empty_tail = !partial && algorithm == Algorithm::Dr14tt;
+        let keep = match algorithm {
+            Algorithm::Drmeter => partial,
+            Algorithm::Dr14tt => true,
+            Algorithm::PmfOfficial => partial && self.blocks[0].is_empty(),
+        };
+        if keep {
+            let start = if partial { self.start } else { self.next };
+            for (sums, blocks) in self.sums.iter_mut().zip(&mut self.blocks) {
+                blocks.push(sums.take(self.len, start));
+            }
+        }
+        (self.blocks, empty_tail)
+    }
 }
 
-fn dr_for_channel(blocks: &[BlockStats]) -> f64 {
+fn dr_for_channel(blocks: &[BlockStats], params: DrParams) -> f64 {
     if blocks.is_empty() {
         return 0.0;
     }
 
     let total = blocks.len();
+    if params.algorithm == Algorithm::Dr14tt && total < 3 {
+        return 0.0;
+    }
 
     // Sort RMS values ascending (mirrors block_rms.sort(axis=0))
     let mut rms_sorted: Vec<f64> = blocks.iter().map(|b| b.rms).collect();
@@ -77,11 +1052,16 @@ fn dr_for_channel(blocks: &[BlockStats]) -> f64 {
     peak_sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
     // peak_loud = block_peak[-NTH_HIGHEST_PEAK] = 2nd highest
-    let peak_idx = total.saturating_sub(NTH_HIGHEST_PEAK);
+    let peak_idx = total.saturating_sub(params.nth_peak);
     let peak_loud = peak_sorted[peak_idx];
 
     // top 20% blocks by RMS: last top_n elements of the sorted array
-    let top_n = ((total as f64 * UPMOST_BLOCKS_RATIO).round() as usize).max(1);
+    let top_n = match params.algorithm {
+        Algorithm::Drmeter => (total as f64 * params.top_ratio).round() as usize,
+        Algorithm::Dr14tt => (total as f64 * params.top_ratio).floor() as usize,
+        Algorithm::PmfOfficial => (total as f64 * params.top_ratio).ceil() as usize,
+    }
+    .clamp(1, total);
     let upmost_rms = &rms_sorted[(total - top_n)..];
 
     // rms_loud = sqrt( sum( rms² ) / top_n )
@@ -94,112 +1074,718 @@ fn dr_for_channel(blocks: &[BlockStats]) -> f64 {
     20.0 * (peak_loud / rms_loud).log10()
 }
 
-// ─── File processing ──────────────────────────────────────────────────────────
+/// DR over a window of `window` consecutive blocks sliding one block at a
+/// time; element `i` covers blocks `i .. i + window`.
+fn windowed_dr(channel_blocks: &[Vec<BlockStats>], window: usize, params: DrParams) -> Vec<f64> {
+    let total = channel_blocks.first().map(|b| b.len()).unwrap_or(0);
+    if total < window {
+        return Vec::new();
+    }
+    (0..=total - window)
+        .map(|i| {
+            let values: Vec<f64> = channel_blocks
+                .iter()
+                .map(|blocks| dr_for_channel(&blocks[i..i + window], params))
+                .collect();
+            params.channel_mean(&values)
+        })
+        .collect()
+}
 
-#[derive(Debug)]
-struct TrackResult {
-    filename: String,
-    dr: i32,
-    peak_db: f64,
-    rms_db: f64,
-    duration_secs: f64,
-    channels: u32,
-    sample_rate: u32,
-    bit_depth: u32,
+/// Official album DR: per channel, the blocks of every track are pooled and
+/// measured as one long track; the channel values are then averaged.
+fn album_dr_pooled(tracks: &[&TrackResult], params: DrParams) -> f64 {
+    let channels = tracks.iter().map(|t| t.channel_blocks.len()).max().unwrap_or(0);
+    if channels == 0 {
+        return 0.0;
+    }
+    let dr_values: Vec<f64> = (0..channels)
+        .map(|ch| {
+            let pooled: Vec<BlockStats> = tracks
+                .iter()
+                .filter_map(|t| t.channel_blocks.get(ch))
+                .flat_map(|blocks| blocks.iter().cloned())
+                .collect();
+            dr_for_channel(&pooled, params)
+        })
+        .collect();
+    params.channel_mean(&dr_values)
 }
 
-fn process_flac(path: &Path) -> Result<TrackResult, String> {
-    let mut reader = FlacReader::open(path)
-        .map_err(|e| format!("Cannot open: {}", e))?;
+// ─── File processing ──────────────────────────────────────────────────────────
 
-    let info = reader.streaminfo();
-    let channels = info.channels;
-    let sample_rate = info.sample_rate;
-    let bits_per_sample = info.bits_per_sample;
-    let total_samples = info.samples.unwrap_or(0);
-    let duration_secs = if sample_rate > 0 {
-        total_samples as f64 / sample_rate as f64
-    } else {
-        0.0
+/// Channel labels in FLAC's default channel order.
+fn channel_names(channels: u32) -> Vec<String> {
+    let names: &[&str] = match channels {
+        1 => &["M"],
+        2 => &["L", "R"],
+        3 => &["L", "R", "C"],
+        4 => &["FL", "FR", "BL", "BR"],
+        5 => &["FL", "FR", "FC", "BL", "BR"],
+        6 => &["FL", "FR", "FC", "LFE", "BL", "BR"],
+        7 => &["FL", "FR", "FC", "LFE", "BC", "SL", "SR"],
+        8 => &["FL", "FR", "FC", "LFE", "BL", "BR", "SL", "SR"],
+        _ => &[],
     };
+    (0..channels as usize)
+        .map(|ch| names.get(ch).map(|n| n.to_string()).unwrap_or_else(|| format!("Ch{}", ch + 1)))
+        .collect()
+}
 
-    let scale = (1i64 << (bits_per_sample - 1)) as f64;
-    let block_len = block_size_for_sample_rate(sample_rate);
+impl TrackResult {
+    /// "NN - Artist - Title" from the tags, or the file name when untitled.
+    fn display_name(&self) -> String {
+        self.tags.display_name(&self.filename)
+    }
+
+    /// Average bitrate of the whole file, metadata included, in kbit/s.
+    fn bitrate_kbps(&self) -> f64 {
+        if self.duration_secs > 0.0 { self.file_size as f64 * 8.0 / self.duration_secs / 1000.0 } else { 0.0 }
+    }
+}
 
-    // Per-channel sample buffers
-    let mut ch_buffers: Vec<Vec<f64>> = vec![Vec::new(); channels as usize];
-    // Per-channel block stats
-    let mut ch_blocks: Vec<Vec<BlockStats>> = vec![Vec::new(); channels as usize];
+/// Album artist and title shared by the tagged tracks; the artist is
+/// ALBUMARTIST, else a common ARTIST, else "Various Artists".
+fn album_tags<'a>(tracks: impl IntoIterator<Item = &'a TrackResult>) -> Option<(String, String)> {
+    let tags: Vec<&Tags> = tracks.into_iter().map(|t| &t.tags).collect();
+    let common = |field: fn(&Tags) -> &Option<String>| {
+        let first = field(tags.first()?).as_ref()?;
+        tags.iter().all(|t| field(t).as_ref() == Some(first)).then(|| first.clone())
+    };
+    let album = common(|t| &t.album)?;
+    let artist = common(|t| &t.album_artist)
+        .or_else(|| common(|t| &t.artist))
+        .unwrap_or_else(|| "Various Artists".to_string());
+    Some((artist, album))
+}
 
-    // Interleaved sample iteration
-    let mut samples_iter = reader.samples();
+/// Earliest release year among the tracks.
+fn album_year<'a>(tracks: impl IntoIterator<Item = &'a TrackResult>) -> Option<u32> {
+    tracks.into_iter().filter_map(|t| t.tags.year).min()
+}
 
-    loop {
-        // Read one inter-channel frame
-        let mut frame = Vec::with_capacity(channels as usize);
-        let mut eof = false;
-        for _ in 0..channels {
-            match samples_iter.next() {
-                Some(Ok(s)) => frame.push(s as f64 / scale),
-                Some(Err(_)) => { eof = true; break; }
-                None => { eof = true; break; }
-            }
-        }
-        if frame.len() == channels as usize {
-            for (ch, &s) in frame.iter().enumerate() {
-                ch_buffers[ch].push(s);
-            }
-        }
+/// The tracks of one disc: its DISCNUMBER (None when untagged) and tracks.
+type DiscGroup<'a> = (Option<u32>, Vec<&'a TrackResult>);
 
-        // Flush full blocks
-        let buf_len = ch_buffers[0].len();
-        if buf_len >= block_len || (eof && buf_len > 0) {
-            let take = if buf_len >= block_len { block_len } else { buf_len };
-            for ch in 0..channels as usize {
-                let block: Vec<f64> = ch_buffers[ch].drain(..take).collect();
-                ch_blocks[ch].push(compute_block_stats(&block));
-            }
+/// Tracks grouped by DISCNUMBER in disc order, untagged ones last; None when
+/// they don't span more than one disc.
+fn disc_groups<'a>(tracks: &[&'a TrackResult]) -> Option<Vec<DiscGroup<'a>>> {
+    let mut groups: Vec<DiscGroup> = Vec::new();
+    for &t in tracks {
+        match groups.iter_mut().find(|(disc, _)| *disc == t.tags.disc_number) {
+            Some((_, group)) => group.push(t),
+            None => groups.push((t.tags.disc_number, vec![t])),
         }
-
-        if eof { break; }
     }
+    if groups.len() < 2 {
+        return None;
+    }
+    groups.sort_by_key(|(disc, _)| disc.unwrap_or(u32::MAX));
+    Some(groups)
+}
 
-    // Compute per-channel DR and aggregate
-    let dr_values: Vec<f64> = (0..channels as usize)
-        .map(|ch| dr_for_channel(&ch_blocks[ch]))
-        .collect();
+/// The tracks of one album in a mixed folder: its "Artist — Album (year)"
+/// label and tracks.
+type AlbumGroup<'a> = (String, Vec<&'a TrackResult>);
 
-    let dr_mean = dr_values.iter().sum::<f64>() / dr_values.len() as f64;
-    let dr = dr_mean.round() as i32;
+/// Tracks grouped by their ALBUMARTIST and ALBUM tags, in order of first
+/// appearance, tracks without an ALBUM tag last; None when they all belong to
+/// one album.
+fn album_groups<'a>(tracks: &[&'a TrackResult]) -> Option<Vec<AlbumGroup<'a>>> {
+    type Key<'k> = (Option<&'k String>, Option<&'k String>);
+    let mut groups: Vec<(Key, Vec<&TrackResult>)> = Vec::new();
+    for &t in tracks {
+        let key = (t.tags.album.as_ref(), t.tags.album_artist.as_ref());
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(t),
+            None => groups.push((key, vec![t])),
+        }
+    }
+    if groups.len() < 2 {
+        return None;
+    }
+    groups.sort_by_key(|((album, _), _)| album.is_none());
+    let labelled = groups.into_iter().map(|(_, group)| {
+        let label = match (album_tags(group.iter().copied()), album_year(group.iter().copied())) {
+            (Some((artist, album)), Some(year)) => format!("{} — {} ({})", artist, album, year),
+            (Some((artist, album)), None) => format!("{} — {}", artist, album),
+            (None, _) => "(no ALBUM tag)".to_string(),
+        };
+        (label, group)
+    });
+    Some(labelled.collect())
+}
+
+/// `tracks` album by album as album_groups splits them, each with its label;
+/// one unlabelled group when they belong to a single album.
+fn album_sections<'a>(tracks: &[&'a TrackResult]) -> Vec<(Option<String>, Vec<&'a TrackResult>)> {
+    match album_groups(tracks) {
+        Some(groups) => groups.into_iter().map(|(label, group)| (Some(label), group)).collect(),
+        None => vec![(None, tracks.to_vec())],
+    }
+}
+
+fn disc_label(disc: Option<u32>) -> String {
+    disc.map_or_else(|| "Disc ?".to_string(), |n| format!("Disc {}", n))
+}
+
+fn to_db(linear: f64) -> f64 {
+    if linear < 1e-10 { -100.0 } else { 20.0 * linear.log10() }
+}
+
+/// The Vorbis comments shown in the report.
+#[derive(Debug, Clone, Default)]
+struct Tags {
+    artist: Option<String>,
+    album_artist: Option<String>,
+    title: Option<String>,
+    album: Option<String>,
+    /// TRACKNUMBER, without a "/total" suffix.
+    track_number: Option<u32>,
+    /// DISCNUMBER, likewise.
+    disc_number: Option<u32>,
+    /// Release year from DATE (or YEAR), e.g. 1979 from "1979-11-30".
+    year: Option<u32>,
+    /// MUSICBRAINZ_ALBUMID, the MusicBrainz release ID.
+    release_id: Option<String>,
+}
+
+impl Tags {
+    fn read<R: std::io::Read>(reader: &FlacReader<R>) -> Tags {
+        let tag = |name: &str| {
+            reader.get_tag(name).map(str::trim).find(|v| !v.is_empty()).map(str::to_string)
+        };
+        let number = |name: &str| tag(name).and_then(|n| n.split('/').next()?.trim().parse().ok());
+        Tags {
+            artist: tag("ARTIST"),
+            album_artist: tag("ALBUMARTIST"),
+            title: tag("TITLE"),
+            album: tag("ALBUM"),
+            track_number: number("TRACKNUMBER"),
+            disc_number: number("DISCNUMBER"),
+            year: tag("DATE").or_else(|| tag("YEAR")).and_then(|d| d.get(..4)?.parse().ok()),
+            release_id: tag("MUSICBRAINZ_ALBUMID"),
+        }
+    }
+
+    /// "NN - Artist - Title", or `filename` without a TITLE tag.
+    fn display_name(&self, filename: &str) -> String {
+        let Some(title) = &self.title else {
+            return filename.to_string();
+        };
+        let mut name = String::new();
+        if let Some(n) = self.track_number {
+            name.push_str(&format!("{:02} - ", n));
+        }
+        if let Some(artist) = &self.artist {
+            name.push_str(&format!("{} - ", artist));
+        }
+        name.push_str(title);
+        name
+    }
+}
+
+#[derive(Debug)]
+struct TrackResult {
+    filename: String,
+    tags: Tags,
+    dr: i32,
+    /// Unrounded DR (mean of the channel values).
+    dr_exact: f64,
+    /// Unrounded DR of each channel (a single value when downmixed).
+    channel_dr: Vec<f64>,
+    /// Unrounded DR measured after K-weighting (`--weighting k`).
+    k_dr: Option<f64>,
+    /// Mid and side levels of a stereo file (`--ms`).
+    mid_side: Option<[Levels; 2]>,
+    /// DR block statistics per channel (or of the downmix), pooled for the
+    /// official album DR.
+    channel_blocks: Vec<Vec<BlockStats>>,
+    peak_db: f64,
+    rms_db: f64,
+    /// Peak-to-RMS ratio over the whole file (plain RMS, no factor of 2).
+    crest_db: f64,
+    /// Plain RMS of every sample (dBFS, no factor of 2).
+    plain_rms_db: f64,
+    /// RMS of the quietest non-silent passages (dBFS).
+    noise_floor_db: f64,
+    duration_secs: f64,
+    channels: u32,
+    sample_rate: u32,
+    bit_depth: u32,
+    /// Bits carrying data: `bit_depth` minus the LSBs that are zero in every sample.
+    effective_bits: u32,
+    /// Sample peak as a linear fraction of full scale (ReplayGain peak).
+    sample_peak: f64,
+    /// Integrated loudness (LUFS).
+    lufs: f64,
+    /// Oversampled true peak (dBTP).
+    true_peak_db: f64,
+    /// 400 ms gating block powers, pooled for the album loudness.
+    gating_blocks: Vec<f64>,
+    /// K-weighted power of every 100 ms sub-block.
+    subblock_powers: Vec<f64>,
+    /// True peak (linear) of every 100 ms sub-block.
+    subblock_peaks: Vec<f64>,
+    /// Runs of full-scale samples.
+    clipping: Clipping,
+    /// First frame of every intersample over (> 0 dBTP).
+    intersample_overs: Vec<u64>,
+    /// Position of every click / pop, in seconds from the start of the file.
+    clicks: Vec<f64>,
+    /// Mean sample value per channel, as a fraction of full scale.
+    dc_offset: Vec<f64>,
+    /// Energy below 20 Hz relative to the whole signal, in dB (None for silence).
+    subsonic_db: Option<f64>,
+    /// Correlation coefficient between the first two channels (None for mono).
+    correlation: Option<f64>,
+    /// L/R RMS difference in dB, positive when the left channel is louder (None for mono).
+    balance_db: Option<f64>,
+    /// Average spectrum and lossy-transcode verdict.
+    spectrum: Spectrum,
+    /// Min/max thumbnail for `--waveforms`.
+    waveform: Option<Waveform>,
+    /// MD5 of the decoded audio from STREAMINFO (all zeros if the encoder left it unset).
+    audio_md5: [u8; 16],
+    /// Size of the file on disk, in bytes.
+    file_size: u64,
+    /// Encoder vendor string of the Vorbis comment block ("reference libFLAC 1.3.2 20170101").
+    vendor: Option<String>,
+    /// Decoded audio checked against `audio_md5` (`--verify`).
+    integrity: Option<integrity::Integrity>,
+    /// Digital silence (all-zero frames) at the start and end, in seconds.
+    leading_silence_secs: f64,
+    trailing_silence_secs: f64,
+    /// DR blocks dropped by `--silence-gate`.
+    gated_blocks: usize,
+}
+
+/// DR, peak and RMS of one derived signal, measured like the main figures.
+#[derive(Debug, Clone, Copy)]
+struct Levels {
+    dr: f64,
+    peak_db: f64,
+    rms_db: f64,
+}
+
+/// Peak and RMS (DR convention, factor of 2) of the measured blocks.
+fn block_levels<'a>(blocks: impl IntoIterator<Item = &'a BlockStats>) -> (f64, f64) {
+    let (peak, sq, n) = blocks
+        .into_iter()
+        .fold((0.0f64, 0.0, 0usize), |(peak, sq, n), b| (peak.max(b.peak), sq + b.rms * b.rms, n + 1));
+    (peak, (sq / n.max(1) as f64).sqrt())
+}
+
+/// Analysis settings selected on the command line.
+#[derive(Debug, Clone, Default)]
+struct AnalysisOptions {
+    downmix: Option<Downmix>,
+    dr: DrParams,
+    /// Also measure stereo files as mid/side.
+    mid_side: bool,
+    /// Leave leading and trailing digital silence out of every measurement.
+    trim_silence: bool,
+    /// Drop DR blocks whose RMS is below this level (dBFS) on every channel.
+    silence_gate: Option<f64>,
+    /// Section of each file to analyse, in seconds.
+    start: Option<f64>,
+    end: Option<f64>,
+    /// Analyse only one DR block in QUICK_STRIDE.
+    quick: bool,
+    /// Collect a waveform thumbnail.
+    waveform: bool,
+    /// Collect a spectrogram.
+    spectrogram: bool,
+    /// Also measure DR on a weighted signal.
+    weighting: Option<Weighting>,
+    /// Check the decoded audio against the STREAMINFO MD5.
+    verify: bool,
+    /// Measure the true peak and intersample overs.
+    true_peak: bool,
+    /// Look for clicks and pops.
+    clicks: bool,
+    /// Analyse the spectrum (also needed for a spectrogram).
+    spectrum: bool,
+    /// Measure the subsonic rumble.
+    rumble: bool,
+}
+
+/// `--quick` analyses the first of every QUICK_STRIDE blocks and skips the rest.
+const QUICK_STRIDE: u64 = 4;
+
+/// Analyses one file; `decoded` counts its frames as they are decoded, for
+/// the progress bars.
+fn process_flac(path: &Path, opts: &AnalysisOptions, decoded: &AtomicU64) -> Result<TrackResult, String> {
+    let mut reader = FlacReader::open(path)
+        .map_err(|e| format!("Cannot open: {}", e))?;
+
+    let info = reader.streaminfo();
+    let channels = info.channels;
+    let sample_rate = info.sample_rate;
+    let bits_per_sample = info.bits_per_sample;
+    let total_samples = info.samples.unwrap_or(0);
+    let audio_md5 = info.md5sum;
+    let tags = Tags::read(&reader);
+    let vendor = reader.vendor().map(str::to_string);
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let duration_secs = if sample_rate > 0 {
+        total_samples as f64 / sample_rate as f64
+    } else {
+        0.0
+    };
+
+    let scale = (1i64 << (bits_per_sample - 1)) as f64;
+    let block_len = block_size_for_sample_rate(sample_rate, opts.dr.block_seconds);
+
+    // Channels the DR blocks are measured on: the file's own, or one downmix
+    let dr_channels = match opts.downmix {
+        Some(Downmix::Mono) => 1,
+        None => channels as usize,
+    };
+    let mut splitter = BlockSplitter::new(dr_channels, block_len);
+    // Same blocks measured after K-weighting (--weighting k)
+    let mut k_weighting = opts.weighting.map(|Weighting::K| {
+        (vec![KWeighting::new(sample_rate); dr_channels], BlockSplitter::new(dr_channels, block_len))
+    });
+    // Mid/side blocks of a stereo file (--ms)
+    let mut ms_splitter = (opts.mid_side && channels == 2).then(|| BlockSplitter::new(2, block_len));
+    let mut loudness_meter = LoudnessMeter::new(channels, sample_rate);
+    let mut clip_detector = ClipDetector::new(channels, bits_per_sample);
+    let mut noise_floor_meter = NoiseFloorMeter::new(sample_rate);
+    // Only run when the report or exports show their results
+    let mut true_peak_meter = opts.true_peak.then(|| TruePeakMeter::new(channels, sample_rate));
+    let mut click_detector = opts.clicks.then(|| ClickDetector::new(channels, sample_rate));
+    let mut rumble_meter = opts.rumble.then(|| RumbleMeter::new(channels, sample_rate));
+    // Whole-file sample peak and sum of squares (independent of --downmix)
+    let mut sample_peak = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    // OR of every sample: its trailing zeros are the wasted LSBs
+    let mut used_bits = 0i32;
+    // Per-channel sums for the DC offset
+    let mut ch_sums = vec![0.0f64; channels as usize];
+    // L·R, L², R² sums for the stereo correlation
+    let mut corr_sums = [0.0f64; 3];
+    let mut sample_count = 0u64;
+
+    // Frame range selected with --start / --end
+    let start_frame = opts.start.map(|t| (t * sample_rate as f64).round() as u64).unwrap_or(0);
+    let end_frame = opts.end.map(|t| (t * sample_rate as f64).round() as u64);
+    if total_samples > 0 && start_frame >= total_samples {
+        return Err(format!("Start position is past the end ({})", format_duration(duration_secs)));
+    }
+
+    // Frames the analyzers will see (roughly, with --quick)
+    let mut range_frames = end_frame.unwrap_or(total_samples).min(total_samples).saturating_sub(start_frame);
+    if opts.quick {
+        range_frames /= QUICK_STRIDE;
+    }
+    let mut waveform_builder = opts.waveform.then(|| WaveformBuilder::new(range_frames, sample_rate));
+    let mut spectrum_analyzer = opts.spectrum.then(|| {
+        let analyzer = SpectrumAnalyzer::new(sample_rate);
+        if opts.spectrogram {
+            analyzer.with_spectrogram(range_frames)
+        } else {
+            analyzer
+        }
+    });
+
+    let mut weighted = vec![0.0; dr_channels];
+    // Each stretch of consecutive analysed frames, as the number of frames
+    // analysed before it and its file position: the detectors count analysed
+    // frames, which --start, --quick and --trim-silence set apart from the file's
+    let mut runs: Vec<(u64, u64)> = Vec::new();
+    let mut analysed = 0u64;
+    // `at` is the frame's position in the file
+    let mut analyze = |frame: &[f64], at: u64| {
+        if runs.last().is_none_or(|&(n, start)| start + (analysed - n) != at) {
+            runs.push((analysed, at));
+        }
+        analysed += 1;
+        let mono;
+        let dr_frame = match opts.downmix {
+            Some(Downmix::Mono) => {
+                mono = [frame.iter().sum::<f64>() / channels as f64];
+                &mono[..]
+            }
+            None => frame,
+        };
+        splitter.push(dr_frame, at);
+        if let Some((filters, k_splitter)) = k_weighting.as_mut() {
+            for ((k, &x), y) in filters.iter_mut().zip(dr_frame).zip(&mut weighted) {
+                *y = k.process(x);
+            }
+            k_splitter.push(&weighted, at);
+        }
+        if let Some(ms_splitter) = ms_splitter.as_mut() {
+            ms_splitter.push(&[(frame[0] + frame[1]) / 2.0, (frame[0] - frame[1]) / 2.0], at);
+        }
+        for (ch, &s) in frame.iter().enumerate() {
+            sample_peak = sample_peak.max(s.abs());
+            sum_sq += s * s;
+            ch_sums[ch] += s;
+        }
+        sample_count += channels as u64;
+        if channels >= 2 {
+            corr_sums[0] += frame[0] * frame[1];
+            corr_sums[1] += frame[0] * frame[0];
+            corr_sums[2] += frame[1] * frame[1];
+        }
+        loudness_meter.push_frame(frame);
+        clip_detector.push_frame(frame);
+        noise_floor_meter.push_frame(frame);
+        if let Some(meter) = true_peak_meter.as_mut() {
+            meter.push_frame(frame);
+        }
+        if let Some(detector) = click_detector.as_mut() {
+            detector.push_frame(frame);
+        }
+        if let Some(analyzer) = spectrum_analyzer.as_mut() {
+            analyzer.push_frame(frame);
+        }
+        if let Some(meter) = rumble_meter.as_mut() {
+            meter.push_frame(frame);
+        }
+        if let Some(builder) = waveform_builder.as_mut() {
+            builder.push_frame(frame);
+        }
+    };
+
+    // Digital silence before the first sound, and zero frames seen since the
+    // last sound (trailing silence if the file ends here)
+    let mut leading_silence = 0u64;
+    let mut pending_silence = 0u64;
+    let mut heard_sound = false;
+
+    // Decoded a FLAC block at a time into a reused buffer
+    let mut blocks = reader.blocks();
+    let mut buffer = Vec::new();
+    let mut frame = vec![0.0f64; channels as usize];
+    let silence = vec![0.0f64; channels as usize];
+    let mut position = 0u64;
+    let mut verifier = opts.verify.then(|| integrity::Verifier::new(bits_per_sample));
+    let mut decode_error = None;
+
+    'decode: loop {
+        let block = match blocks.read_next_or_eof(std::mem::take(&mut buffer)) {
+            Ok(Some(block)) => block,
+            Ok(None) => break,
+            // A file cut short ends mid-frame; --verify reports it as truncated
+            Err(claxon::Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                decode_error = Some(e.to_string());
+                break;
+            }
+        };
+        decoded.fetch_add(block.duration() as u64, Ordering::Relaxed);
+        for i in 0..block.duration() {
+            for (ch, value) in (0..channels).zip(frame.iter_mut()) {
+                let s = block.sample(ch, i);
+                used_bits |= s;
+                *value = s as f64 / scale;
+                if let Some(verifier) = verifier.as_mut() {
+                    verifier.push_sample(s);
+                }
+            }
+            position += 1;
+            if position <= start_frame {
+                continue;
+            }
+            if end_frame.is_some_and(|end| position > end) {
+                // --verify hashes the rest of the file too
+                if verifier.is_some() {
+                    continue;
+                }
+                break 'decode;
+            }
+            if opts.quick && !((position - start_frame - 1) / block_len as u64).is_multiple_of(QUICK_STRIDE) {
+                continue;
+            }
+
+            if frame.iter().all(|&s| s == 0.0) {
+                if heard_sound {
+                    pending_silence += 1;
+                } else {
+                    leading_silence += 1;
+                }
+                if opts.trim_silence {
+                    continue;
+                }
+            } else {
+                // The held-back silence was a gap, not the end of the track
+                if opts.trim_silence {
+                    for k in 0..pending_silence {
+                        analyze(&silence, position - 1 - pending_silence + k);
+                    }
+                }
+                pending_silence = 0;
+                heard_sound = true;
+            }
+            analyze(&frame, position - 1);
+        }
+        buffer = block.into_buffer();
+    }
+    let trailing_silence = pending_silence;
+    let integrity = verifier.map(|v| v.finish(position, decode_error, total_samples, &audio_md5));
+
+    // Flush the partial last block (DR14 T.T. always closes with a possibly empty block);
+    // empty_tail is set when that final block is empty
+    let (mut ch_blocks, empty_tail) = splitter.finish(opts.dr.algorithm);
+    let mut k_blocks = k_weighting.map(|(_, k_splitter)| k_splitter.finish(opts.dr.algorithm).0);
+    let mut ms_blocks = ms_splitter.map(|ms_splitter| ms_splitter.finish(opts.dr.algorithm).0);
+
+    // Drop blocks that are silent on every channel (the DR14 T.T. empty tail stays)
+    let mut gated_blocks = 0;
+    if let Some(gate) = opts.silence_gate {
+        let measured = ch_blocks[0].len() - empty_tail as usize;
+        let keep: Vec<bool> = (0..ch_blocks[0].len())
+            .map(|i| i >= measured || ch_blocks.iter().any(|b| to_db(b[i].rms / std::f64::consts::SQRT_2) >= gate))
+            .collect();
+        gated_blocks = keep.iter().filter(|&&k| !k).count();
+        let derived = k_blocks.iter_mut().chain(ms_blocks.iter_mut()).flatten();
+        for blocks in ch_blocks.iter_mut().chain(derived) {
+            let mut keep = keep.iter();
+            blocks.retain(|_| *keep.next().unwrap());
+        }
+    }
+
+    // Compute per-channel DR and aggregate
+    let dr_values: Vec<f64> = (0..dr_channels)
+        .map(|ch| dr_for_channel(&ch_blocks[ch], opts.dr))
+        .collect();
+
+    let dr_mean = opts.dr.channel_mean(&dr_values);
+    let dr = opts.dr.algorithm.round(dr_mean);
+    let k_dr = k_blocks.map(|blocks| {
+        let values: Vec<f64> = blocks.iter().map(|b| dr_for_channel(b, opts.dr)).collect();
+        opts.dr.channel_mean(&values)
+    });
 
     // Overall peak & RMS across all channels
-    let all_blocks: Vec<&BlockStats> = ch_blocks.iter().flat_map(|v| v.iter()).collect();
-    let overall_peak = all_blocks.iter().map(|b| b.peak).fold(0.0f64, f64::max);
-    let overall_rms = {
-        let sq: f64 = all_blocks.iter().map(|b| b.rms * b.rms).sum();
-        (sq / all_blocks.len().max(1) as f64).sqrt()
+    let measured = ch_blocks[0].len() - empty_tail as usize;
+    let (overall_peak, overall_rms) = block_levels(ch_blocks.iter().flat_map(|v| &v[..measured]));
+    let mid_side = ms_blocks.map(|blocks| {
+        [0, 1].map(|ch| {
+            let (peak, rms) = block_levels(&blocks[ch][..measured]);
+            Levels { dr: dr_for_channel(&blocks[ch], opts.dr), peak_db: to_db(peak), rms_db: to_db(rms) }
+        })
+    });
+
+    let true_rms = (sum_sq / sample_count.max(1) as f64).sqrt();
+    let crest_db = if true_rms > 0.0 { to_db(sample_peak) - to_db(true_rms) } else { 0.0 };
+
+    // corr_sums[1] and [2] are the L and R energies
+    let balance_db = if channels < 2 || corr_sums[1] <= 0.0 || corr_sums[2] <= 0.0 {
+        None
+    } else {
+        Some(10.0 * (corr_sums[1] / corr_sums[2]).log10())
+    };
+
+    let correlation = if channels < 2 {
+        None
+    } else if corr_sums[1] > 0.0 && corr_sums[2] > 0.0 {
+        Some(corr_sums[0] / (corr_sums[1] * corr_sums[2]).sqrt())
+    } else {
+        // One silent channel: no phase relationship to speak of
+        Some(0.0)
+    };
+
+    // File position of the analysed frame `index`
+    let file_position = |index: u64| {
+        let run = runs.partition_point(|&(n, _)| n <= index).saturating_sub(1);
+        runs.get(run).map_or(index, |&(n, start)| start + (index - n))
     };
 
-    fn to_db(linear: f64) -> f64 {
-        if linear < 1e-10 { -100.0 } else { 20.0 * linear.log10() }
+    let mut clipping = clip_detector.finish();
+    for region in &mut clipping.regions {
+        region.start = file_position(region.start);
     }
 
+    let loudness = loudness_meter.finish();
+    // Unmeasured, the true peak is taken as the sample peak
+    let true_peak = true_peak_meter.map_or_else(
+        || TruePeak { peak: sample_peak, subblock_peaks: Vec::new(), overs: Vec::new() },
+        TruePeakMeter::finish,
+    );
+
     let filename = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
 
     Ok(TrackResult {
         filename,
+        tags,
+        audio_md5,
+        file_size,
+        vendor,
+        integrity,
         dr,
+        dr_exact: dr_mean,
+        channel_dr: dr_values,
+        k_dr,
+        mid_side,
+        channel_blocks: ch_blocks,
         peak_db: to_db(overall_peak),
         rms_db: to_db(overall_rms),
-        duration_secs,
+        crest_db,
+        plain_rms_db: to_db(true_rms),
+        noise_floor_db: to_db(noise_floor_meter.finish()),
+        // The section length when --start / --end narrowed the analysis
+        duration_secs: if opts.start.is_some() || opts.end.is_some() {
+            position.min(end_frame.unwrap_or(u64::MAX)).saturating_sub(start_frame) as f64 / sample_rate.max(1) as f64
+        } else {
+            duration_secs
+        },
         channels,
         sample_rate,
         bit_depth: bits_per_sample,
+        effective_bits: if used_bits == 0 {
+            0
+        } else {
+            bits_per_sample.saturating_sub(used_bits.trailing_zeros())
+        },
+        sample_peak,
+        lufs: loudness.integrated,
+        true_peak_db: to_db(true_peak.peak),
+        gating_blocks: loudness.blocks,
+        subblock_powers: loudness.subblocks,
+        subblock_peaks: true_peak.subblock_peaks,
+        clipping,
+        intersample_overs: true_peak.overs.iter().map(|&frame| file_position(frame)).collect(),
+        clicks: click_detector
+            .map(ClickDetector::finish)
+            .unwrap_or_default()
+            .iter()
+            .map(|&frame| file_position(frame) as f64 / sample_rate.max(1) as f64)
+            .collect(),
+        correlation,
+        balance_db,
+        spectrum: spectrum_analyzer.map_or_else(
+            || Spectrum {
+                cutoff_hz: sample_rate as f64 / 2.0,
+                lossy_suspect: false,
+                upsampled_from: None,
+                spectrogram: None,
+            },
+            SpectrumAnalyzer::finish,
+        ),
+        waveform: waveform_builder.map(WaveformBuilder::finish),
+        subsonic_db: rumble_meter.and_then(RumbleMeter::finish),
+        dc_offset: ch_sums.iter().map(|sum| sum / (sample_count / channels as u64).max(1) as f64).collect(),
+        leading_silence_secs: leading_silence as f64 / sample_rate.max(1) as f64,
+        trailing_silence_secs: trailing_silence as f64 / sample_rate.max(1) as f64,
+        gated_blocks,
     })
 }
 
 // ─── Report formatting ────────────────────────────────────────────────────────
 
+/// "DR12", or "DR11.63" when decimals were requested with `--precision`.
+fn format_dr(dr: f64, precision: usize, algorithm: Algorithm) -> String {
+    if precision == 0 {
+        format!("DR{}", algorithm.round(dr))
+    } else {
+        format!("DR{:.*}", precision, dr)
+    }
+}
+
 fn format_duration(secs: f64) -> String {
     let total = secs as u64;
     let h = total / 3600;
@@ -212,12 +1798,105 @@ fn format_duration(secs: f64) -> String {
     }
 }
 
+/// Letter grade and rating of a DR value (the README's rating scale).
+fn rating(dr: i32) -> (&'static str, &'static str) {
+    match dr {
+        dr if dr >= 14 => ("A", "Excellent – wide dynamic range"),
+        dr if dr >= 10 => ("B", "Good"),
+        dr if dr >= 8  => ("C", "Acceptable"),
+        dr if dr >= 6  => ("D", "Compressed"),
+        _               => ("F", "Heavily brick-walled / clipped"),
+    }
+}
+
+/// Tracks whose DR differs from the album median by this much are outliers.
+const OUTLIER_DR: f64 = 4.0;
+
+/// Median of the unrounded track DR values (None without tracks).
+fn median_dr(results: &[Result<TrackResult, (String, String)>]) -> Option<f64> {
+    let mut values: Vec<f64> = results.iter().flatten().map(|t| t.dr_exact).collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] })
+}
+
+/// Block RMS percentiles shown by `--percentiles`.
+const RMS_PERCENTILES: [usize; 3] = [50, 95, 99];
+
+/// RMS (dB, same scale as the RMS column) of the DR blocks at each of
+/// RMS_PERCENTILES, nearest-rank over the blocks of every channel.
+fn block_rms_percentiles(t: &TrackResult) -> Vec<f64> {
+    let mut rms: Vec<f64> = t.channel_blocks.iter().flatten().map(|b| b.rms).collect();
+    rms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    RMS_PERCENTILES
+        .iter()
+        .map(|&p| {
+            let rank = ((p as f64 / 100.0 * rms.len() as f64).ceil() as usize).max(1);
+            rms.get(rank - 1).map(|&r| to_db(r)).unwrap_or(f64::NEG_INFINITY)
+        })
+        .collect()
+}
+
+/// True peak this far above the sample peak (dB) is flagged in the table.
+const TRUE_PEAK_EXCESS_WARN: f64 = 1.0;
+
+/// Correlation below this (i.e. negative once rounded to two decimals) is flagged.
+const NEGATIVE_CORRELATION: f64 = -0.005;
+
+/// Optional report content selected on the command line.
+#[derive(Debug, Clone, Default)]
+struct ReportOptions {
+    verbose: bool,
+    dr: DrParams,
+    downmix: Option<Downmix>,
+    precision: usize,
+    album_method: AlbumMethod,
+    /// Track table columns (--columns, or the default set).
+    columns: Vec<Column>,
+    gain: Option<GainTarget>,
+    balance_warn: f64,
+    trim_silence: bool,
+    silence_gate: Option<f64>,
+    start: Option<f64>,
+    end: Option<f64>,
+    quick: bool,
+    /// Plain-ASCII text report.
+    ascii: bool,
+    /// Length of the lowest-DR track list (--worst).
+    worst: Option<usize>,
+    lang: Lang,
+    /// The folder's release, looked up with --musicbrainz.
+    release: Option<musicbrainz::Release>,
+}
+
+/// Album DR as the mean of the track values as printed (rounded unless
+/// --precision), and from the pooled blocks.
+fn album_dr_values(tracks: &[&TrackResult], opts: &ReportOptions) -> (f64, f64) {
+    let value = |t: &TrackResult| if opts.precision == 0 { t.dr as f64 } else { t.dr_exact };
+    let mean = tracks.iter().map(|t| value(t)).sum::<f64>() / tracks.len().max(1) as f64;
+    (mean, album_dr_pooled(tracks, opts.dr))
+}
+
+/// Unrounded album DR by the chosen --album-method.
+fn album_dr(tracks: &[&TrackResult], opts: &ReportOptions) -> f64 {
+    let (mean, pooled) = album_dr_values(tracks, opts);
+    match opts.album_method {
+        AlbumMethod::Pooled => pooled,
+        AlbumMethod::Mean => mean,
+    }
+}
+
 fn write_report(
     results: &[Result<TrackResult, (String, String)>],
     folder: &Path,
     output_path: &Path,
+    opts: &ReportOptions,
 ) -> std::io::Result<()> {
-    let mut f = File::create(output_path)?;
+    let mut f = BufWriter::new(create_text_output(output_path, opts.ascii)?);
+    let s = opts.lang.strings();
 
     let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let folder_str = folder.canonicalize()
@@ -227,43 +1906,205 @@ fn write_report(
 
     // Header
     writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
-    writeln!(f, "  Dynamic Range Report")?;
-    writeln!(f, "  Generated : {}", timestamp)?;
-    writeln!(f, "  Folder    : {}", folder_str)?;
+    writeln!(f, "  {}", s.title)?;
+    writeln!(f, "  {:<9} : {}", s.generated, timestamp)?;
+    writeln!(f, "  {:<9} : {}", s.folder, folder_str)?;
+    if let Some((artist, album)) = album_tags(results.iter().flatten()) {
+        match album_year(results.iter().flatten()) {
+            Some(year) => writeln!(f, "  {:<9} : {} — {} ({})", s.album, artist, album, year)?,
+            None => writeln!(f, "  {:<9} : {} — {}", s.album, artist, album)?,
+        }
+    }
+    if let Some(release) = &opts.release {
+        writeln!(f, "  {:<9} : {}", s.release, release.describe())?;
+    }
+    if let Some(label) = opts.dr.algorithm.label() {
+        writeln!(f, "  {:<9} : {}", s.algorithm, label)?;
+    }
+    match opts.dr.lfe {
+        Lfe::Include => {}
+        Lfe::Exclude => writeln!(f, "  {:<9} : {}", s.lfe, s.lfe_excluded)?,
+        Lfe::Weighted => writeln!(f, "  {:<9} : {}", s.lfe, s.lfe_weighted)?,
+    }
+    if !opts.dr.is_standard() {
+        let params = lang::fill(
+            s.non_standard,
+            &[&opts.dr.block_seconds, &(opts.dr.top_ratio * 100.0), &opts.dr.nth_peak],
+        );
+        writeln!(f, "  {:<9} : {}", s.params, params)?;
+    }
+    if opts.downmix == Some(Downmix::Mono) {
+        writeln!(f, "  {:<9} : {}", s.analysis, s.mono_downmix)?;
+    }
+    if opts.quick {
+        writeln!(f, "  {:<9} : {}", s.mode, lang::fill(s.quick_estimate, &[&QUICK_STRIDE]))?;
+    }
+    if opts.start.is_some() || opts.end.is_some() {
+        let end = opts.end.map(format_duration).unwrap_or_else(|| s.range_end.to_string());
+        writeln!(f, "  {:<9} : {} – {}", s.range, format_duration(opts.start.unwrap_or(0.0)), end)?;
+    }
+    if let Some(gate) = opts.silence_gate {
+        writeln!(f, "  {:<9} : {}", s.gate, lang::fill(s.gated_blocks, &[&format!("{:.1}", gate)]))?;
+    }
     writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
     writeln!(f)?;
 
+    // Tracks far from the album median, marked with an asterisk
+    let median = median_dr(results);
+    let is_outlier = |t: &TrackResult| median.is_some_and(|m| (t.dr_exact - m).abs() >= OUTLIER_DR);
+    let any_outlier = results.iter().flatten().any(is_outlier);
+
+    let tp_exceeds = |t: &TrackResult| t.true_peak_db - to_db(t.sample_peak) > TRUE_PEAK_EXCESS_WARN;
+
     // Column headers
-    writeln!(
-        f,
-        "  {:<4}  {:<8}  {:<8}  {:<8}  {:<8}  {}",
-        "DR", "Peak dB", "RMS dB", "Duration", "Info", "File"
-    )?;
+    let dr_width = if opts.precision == 0 { 4 } else { 5 + opts.precision }
+        + opts.quick as usize
+        + any_outlier as usize;
+    let estimate = if opts.quick { "~" } else { "" };
+    let k_width = dr_width.max(5);
+    // The file name is padded only when another column follows it
+    let file_width = results.iter().flatten().map(|t| t.display_name().chars().count()).max().unwrap_or(0).max(4);
+    let columns = &opts.columns;
+    let width = |column: Column| match column {
+        Column::Dr => dr_width,
+        Column::Grade => 5,
+        Column::DrK => k_width,
+        Column::Peak | Column::Rms | Column::Duration | Column::Info => 8,
+        Column::TruePeak => 9,
+        Column::Crest | Column::Plr => 6,
+        Column::Floor | Column::Lufs | Column::Gain | Column::Corr => 7,
+        Column::Percentiles => 6 * RMS_PERCENTILES.len() + 2 * (RMS_PERCENTILES.len() - 1),
+        Column::File => file_width,
+    };
+    let pad = |text: String, column: Column, last: bool| {
+        if last { text } else { format!("{:<w$}", text, w = width(column)) }
+    };
+    let mut header = String::from("  ");
+    for (i, &column) in columns.iter().enumerate() {
+        let text = match column {
+            Column::Dr => "DR".to_string(),
+            Column::Grade => "Grade".to_string(),
+            Column::DrK => "DR(K)".to_string(),
+            Column::Peak => "Peak SP".to_string(),
+            Column::TruePeak => "Peak TP".to_string(),
+            Column::Rms => "RMS dB".to_string(),
+            Column::Crest => "Crest".to_string(),
+            Column::Floor => "Floor".to_string(),
+            Column::Lufs => "LUFS".to_string(),
+            Column::Plr => "PLR".to_string(),
+            Column::Duration => "Duration".to_string(),
+            Column::Info => "Info".to_string(),
+            Column::Percentiles => {
+                RMS_PERCENTILES.iter().map(|p| format!("{:<6}", format!("P{}", p))).collect::<Vec<_>>().join("  ")
+            }
+            Column::Gain => "Gain".to_string(),
+            Column::Corr => "Corr".to_string(),
+            Column::File => "File".to_string(),
+        };
+        if i > 0 {
+            header.push_str("  ");
+        }
+        header.push_str(&pad(text, column, i + 1 == columns.len()));
+    }
+    writeln!(f, "{}", header.trim_end())?;
     writeln!(f, "  {}", "─".repeat(73))?;
 
     let mut dr_values: Vec<i32> = Vec::new();
+    let mut tracks: Vec<&TrackResult> = Vec::new();
     let mut errors: Vec<(&str, &str)> = Vec::new();
 
-    for result in results {
+    // A mixed folder is listed album by album and a multi-disc album disc by
+    // disc, each under its own label
+    let all: Vec<&TrackResult> = results.iter().flatten().collect();
+    let albums = album_groups(&all);
+    let album_of = |t: &TrackResult| match &albums {
+        Some(albums) => albums.iter().position(|(_, group)| group.iter().any(|g| std::ptr::eq(*g, t))).unwrap_or(0),
+        None => 0,
+    };
+    let multi_disc: Vec<bool> = match &albums {
+        Some(albums) => albums.iter().map(|(_, group)| disc_groups(group).is_some()).collect(),
+        None => vec![disc_groups(&all).is_some()],
+    };
+    let disc_of = |t: &TrackResult| multi_disc[album_of(t)].then_some(t.tags.disc_number);
+    let mut ordered: Vec<&Outcome> = results.iter().collect();
+    ordered.sort_by_key(|r| match r {
+        Ok(t) => (false, album_of(t), disc_of(t).map_or(0, |disc| disc.unwrap_or(u32::MAX))),
+        Err(_) => (true, 0, 0),
+    });
+    let disc_indent = if albums.is_some() { "    " } else { "  " };
+    let mut current = None;
+
+    for result in ordered {
         match result {
             Ok(t) => {
-                let info = format!(
-                    "{}/{}/{}",
-                    t.sample_rate / 1000,
-                    t.bit_depth,
-                    t.channels
-                );
-                writeln!(
-                    f,
-                    "  {:<4}  {:>+8.2}  {:>+8.2}  {:<8}  {:<8}  {}",
-                    format!("DR{}", t.dr),
-                    t.peak_db,
-                    t.rms_db,
-                    format_duration(t.duration_secs),
-                    info,
-                    t.filename
-                )?;
+                let (album, disc) = (album_of(t), disc_of(t));
+                if current != Some((album, disc)) {
+                    if current.is_some() {
+                        writeln!(f)?;
+                    }
+                    if let Some(albums) = albums.as_ref().filter(|_| current.is_none_or(|(a, _)| a != album)) {
+                        writeln!(f, "  {}", albums[album].0)?;
+                    }
+                    if let Some(disc) = disc {
+                        writeln!(f, "{}{}", disc_indent, disc_label(disc))?;
+                    }
+                    current = Some((album, disc));
+                }
+                let mut row = String::from("  ");
+                for (i, &column) in columns.iter().enumerate() {
+                    let text = match column {
+                        Column::Dr => format!(
+                            "{}{}{}",
+                            estimate,
+                            format_dr(t.dr_exact, opts.precision, opts.dr.algorithm),
+                            if is_outlier(t) { "*" } else { "" }
+                        ),
+                        Column::Grade => format!("{:^5}", rating(t.dr).0),
+                        Column::DrK => match t.k_dr {
+                            Some(k) => format!("{}{}", estimate, format_dr(k, opts.precision, opts.dr.algorithm)),
+                            None => "–".to_string(),
+                        },
+                        Column::Peak => format!("{:>+8.2}", t.peak_db),
+                        Column::TruePeak => {
+                            format!("{:>+8.2}{}", t.true_peak_db, if tp_exceeds(t) { "⚠" } else { " " })
+                        }
+                        Column::Rms => format!("{:>+8.2}", t.rms_db),
+                        Column::Crest => format!("{:>6.2}", t.crest_db),
+                        Column::Floor => format!("{:>+7.1}", t.noise_floor_db),
+                        Column::Lufs => format!("{:>+7.1}", t.lufs),
+                        Column::Plr => format!("{:>6.1}", t.true_peak_db - t.lufs),
+                        Column::Duration => format_duration(t.duration_secs),
+                        Column::Info => format!("{}/{}/{}", t.sample_rate / 1000, t.bit_depth, t.channels),
+                        Column::Percentiles => block_rms_percentiles(t)
+                            .iter()
+                            .map(|db| format!("{:>+6.1}", db))
+                            .collect::<Vec<_>>()
+                            .join("  "),
+                        Column::Gain => match opts.gain {
+                            Some(target) => {
+                                let gain = match target {
+                                    GainTarget::Lufs(level) => level - t.lufs,
+                                    GainTarget::Rms(level) => level - t.plain_rms_db,
+                                };
+                                let clips = t.true_peak_db + gain > 0.0;
+                                format!("{:>+6.1}{}", gain, if clips { "⚠" } else { " " })
+                            }
+                            None => format!("{:>6} ", "–"),
+                        },
+                        Column::Corr => match t.correlation {
+                            Some(c) => format!("{:>+6.2}{}", c, if c < NEGATIVE_CORRELATION { "⚠" } else { " " }),
+                            None => format!("{:>6} ", "–"),
+                        },
+                        Column::File => t.display_name(),
+                    };
+                    if i > 0 {
+                        row.push_str("  ");
+                    }
+                    row.push_str(&pad(text, column, i + 1 == columns.len()));
+                }
+                writeln!(f, "{}", row.trim_end())?;
                 dr_values.push(t.dr);
+                tracks.push(t);
             }
             Err((name, err)) => {
                 errors.push((name, err));
@@ -272,37 +2113,141 @@ fn write_report(
     }
 
     writeln!(f, "  {}", "─".repeat(73))?;
+    if columns.contains(&Column::TruePeak) && tracks.iter().any(|t| tp_exceeds(t)) {
+        writeln!(f, "  ⚠ {}", lang::fill(s.true_peak_excess, &[&format!("{:.0}", TRUE_PEAK_EXCESS_WARN)]))?;
+    }
+    if let Some(target) = opts.gain.filter(|_| columns.contains(&Column::Gain)) {
+        let target = match target {
+            GainTarget::Lufs(level) => format!("{:.1} LUFS", level),
+            GainTarget::Rms(level) => format!("{:.1} dBFS RMS", level),
+        };
+        writeln!(f, "  {}", lang::fill(s.gain_target, &[&target]))?;
+    }
+    if columns.contains(&Column::Corr) && tracks.iter().any(|t| t.correlation.is_some_and(|c| c < NEGATIVE_CORRELATION)) {
+        writeln!(f, "  ⚠ {}", s.negative_correlation)?;
+    }
     writeln!(f)?;
 
     // Summary
     if !dr_values.is_empty() {
         let dr_min = dr_values.iter().cloned().min().unwrap();
         let dr_max = dr_values.iter().cloned().max().unwrap();
-        let dr_avg = dr_values.iter().sum::<i32>() as f64 / dr_values.len() as f64;
-        let dr_album = dr_avg.round() as i32;
+        let exact: Vec<f64> = tracks.iter().map(|t| t.dr_exact).collect();
+
+        let (dr_mean, dr_pooled) = album_dr_values(&tracks, opts);
+        let (album_value, other_value, album_label, other_label) = match opts.album_method {
+            AlbumMethod::Pooled => (dr_pooled, dr_mean, s.pooled_blocks, s.mean_album_dr),
+            AlbumMethod::Mean => (dr_mean, dr_pooled, s.mean_of_tracks, s.pooled_album_dr),
+        };
+        let dr_album = opts.dr.algorithm.round(album_value);
 
-        writeln!(f, "  Summary")?;
+        writeln!(f, "  {}", s.summary)?;
         writeln!(f, "  ───────────────────────────────")?;
-        writeln!(f, "  Tracks analysed : {}", dr_values.len())?;
-        writeln!(f, "  Album DR        : DR{}", dr_album)?;
-        writeln!(f, "  DR range        : DR{} – DR{}", dr_min, dr_max)?;
+        writeln!(f, "  {:<15} : {}", s.tracks_analysed, dr_values.len())?;
+        // A mixed folder's album DR and rating are given per album instead
+        if albums.is_none() {
+            writeln!(
+                f,
+                "  {:<15} : {}{}  ({})",
+                s.album_dr,
+                estimate,
+                format_dr(album_value, opts.precision, opts.dr.algorithm),
+                album_label
+            )?;
+            writeln!(f, "  {:<15} : {}{}", other_label, estimate, format_dr(other_value, opts.precision, opts.dr.algorithm))?;
+        }
+        let k_values: Vec<f64> = tracks.iter().filter_map(|t| t.k_dr).collect();
+        if !k_values.is_empty() && albums.is_none() {
+            let k_mean = k_values.iter().sum::<f64>() / k_values.len() as f64;
+            writeln!(
+                f,
+                "  {:<15} : {}{}  ({})",
+                s.k_weighted_dr,
+                estimate,
+                format_dr(k_mean, opts.precision, opts.dr.algorithm),
+                s.mean_of_tracks
+            )?;
+        }
+        if opts.precision == 0 {
+            writeln!(f, "  {:<15} : {e}DR{} – {e}DR{}", s.dr_range, dr_min, dr_max, e = estimate)?;
+        } else {
+            let exact_min = exact.iter().cloned().fold(f64::INFINITY, f64::min);
+            let exact_max = exact.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            writeln!(
+                f,
+                "  {:<15} : {e}{} – {e}{}",
+                s.dr_range,
+                format_dr(exact_min, opts.precision, opts.dr.algorithm),
+                format_dr(exact_max, opts.precision, opts.dr.algorithm),
+                e = estimate
+            )?;
+        }
+        for (disc, group) in disc_groups(&tracks).iter().flatten().filter(|_| albums.is_none()) {
+            writeln!(
+                f,
+                "  {:<16}: {}{}  ({} track{})",
+                disc_label(*disc),
+                estimate,
+                format_dr(album_dr(group, opts), opts.precision, opts.dr.algorithm),
+                group.len(),
+                if group.len() == 1 { "" } else { "s" }
+            )?;
+        }
+        let (peak_db, rms_db) = album_levels(&tracks);
+        writeln!(f, "  {:<15} : {}", s.total_duration, format_duration(tracks.iter().map(|t| t.duration_secs).sum()))?;
+        writeln!(f, "  {:<15} : {:.1} MB", s.total_size, tracks.iter().map(|t| t.file_size).sum::<u64>() as f64 / 1_048_576.0)?;
+        writeln!(f, "  {:<15} : {:.2} dB", s.album_peak, peak_db)?;
+        writeln!(f, "  {:<15} : {:.2} dB", s.album_rms, rms_db)?;
+        write_loudness_spread(&mut f, &tracks, s)?;
         writeln!(f)?;
 
         // Rating
-        let rating = match dr_album {
-            dr if dr >= 14 => "Excellent – wide dynamic range",
-            dr if dr >= 10 => "Good",
-            dr if dr >= 8  => "Acceptable",
-            dr if dr >= 6  => "Compressed",
-            _               => "Heavily brick-walled / clipped",
-        };
-        writeln!(f, "  DR Rating : {}", rating)?;
+        match &albums {
+            Some(albums) => writeln!(f, "  ⚠ {}", s.mixed_albums.replace("{}", &albums.len().to_string()))?,
+            None => writeln!(f, "  {} : {} ({})", s.dr_rating, s.rating(dr_album), rating(dr_album).0)?,
+        }
+        if any_outlier {
+            let names: Vec<String> = tracks.iter().filter(|t| is_outlier(t)).map(|t| t.display_name()).collect();
+            let median = format_dr(median.unwrap_or(0.0), opts.precision.max(1), opts.dr.algorithm);
+            writeln!(f, "  * {}", lang::fill(s.outliers, &[&OUTLIER_DR, &median, &names.join(", ")]))?;
+        }
         writeln!(f)?;
+
+        if let Some(albums) = &albums {
+            write_albums(&mut f, albums, opts, estimate)?;
+        }
+        if tracks.iter().any(|t| t.integrity.is_some()) {
+            write_integrity(&mut f, &tracks, s)?;
+        }
+        write_clipping(&mut f, &tracks, opts.verbose, s)?;
+        write_intersample_overs(&mut f, &tracks, s)?;
+        write_clicks(&mut f, &tracks, opts.verbose, s)?;
+        if opts.verbose {
+            write_channel_dr(&mut f, &tracks, s)?;
+        }
+        write_dc_offset(&mut f, &tracks, opts.verbose, s)?;
+        write_rumble(&mut f, &tracks, opts.verbose, s)?;
+        write_balance(&mut f, &tracks, opts)?;
+        write_phase(&mut f, &tracks, s)?;
+        write_mid_side(&mut f, &tracks, opts)?;
+        write_silence(&mut f, &tracks, opts)?;
+        write_spectral(&mut f, &tracks, opts.verbose, s)?;
+        write_bit_depth(&mut f, &tracks, opts.verbose, s)?;
+        if opts.verbose {
+            write_files(&mut f, &tracks, s)?;
+        }
+        write_loudness(&mut f, &tracks, s)?;
+        write_replaygain(&mut f, &tracks, s)?;
+        write_streaming(&mut f, &tracks, s)?;
+        if let Some(n) = opts.worst {
+            let paths: Vec<(PathBuf, &TrackResult)> = tracks.iter().map(|t| (folder.join(&t.filename), *t)).collect();
+            write_worst(&mut f, &paths, n, opts)?;
+        }
     }
 
     // Errors
     if !errors.is_empty() {
-        writeln!(f, "  Errors")?;
+        writeln!(f, "  {}", s.errors)?;
         writeln!(f, "  ───────────────────────────────")?;
         for (name, err) in &errors {
             writeln!(f, "  ✗ {} — {}", name, err)?;
@@ -314,83 +2259,2577 @@ fn write_report(
     writeln!(f, "  DR Loudness Standard — https://www.dynamicrange.de")?;
     writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
 
+    f.flush()
+}
+
+/// Album DR, rating and track count of each album in a mixed folder, and of
+/// each disc of its multi-disc albums.
+fn write_albums(f: &mut dyn Write, albums: &[AlbumGroup], opts: &ReportOptions, estimate: &str) -> std::io::Result<()> {
+    let s = opts.lang.strings();
+    writeln!(f, "  {}", s.albums)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for (label, group) in albums {
+        let dr = album_dr(group, opts);
+        writeln!(
+            f,
+            "  {:<7} {}  {:>3} track{}  {}",
+            format!("{}{}", estimate, format_dr(dr, opts.precision, opts.dr.algorithm)),
+            rating(opts.dr.algorithm.round(dr)).0,
+            group.len(),
+            if group.len() == 1 { " " } else { "s" },
+            label
+        )?;
+        for (disc, group) in disc_groups(group).iter().flatten() {
+            writeln!(
+                f,
+                "          {:<7}  {:>3} track{}  {}",
+                format!("{}{}", estimate, format_dr(album_dr(group, opts), opts.precision, opts.dr.algorithm)),
+                group.len(),
+                if group.len() == 1 { " " } else { "s" },
+                disc_label(*disc)
+            )?;
+        }
+    }
+    writeln!(f)?;
+
     Ok(())
 }
 
-// ─── Main ─────────────────────────────────────────────────────────────────────
+/// Clipped regions listed per track with --verbose and in --clip-log.
+const WORST_CLIPS: usize = 10;
 
-fn main() {
-    let args = Args::parse();
+/// The `--combined` summary of a recursive scan: each album folder's DR,
+/// loudness and peak on one line. `release_years` are those of the folders'
+/// MusicBrainz releases (--musicbrainz), for albums without a year tag.
+fn write_summary(
+    folders: &[&Path],
+    results: &[Vec<Outcome>],
+    release_years: &[Option<u32>],
+    root: &Path,
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let mut f = BufWriter::new(create_text_output(output_path, opts.ascii)?);
 
-    let folder = &args.folder;
-    if !folder.exists() || !folder.is_dir() {
-        eprintln!("Error: '{}' is not a valid directory.", folder.display());
-        std::process::exit(1);
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let root_str = root.canonicalize().unwrap_or_else(|_| root.to_path_buf()).display().to_string();
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f, "  Dynamic Range Summary")?;
+    writeln!(f, "  Generated : {}", timestamp)?;
+    writeln!(f, "  Folder    : {}", root_str)?;
+    if let Some(label) = opts.dr.algorithm.label() {
+        writeln!(f, "  Algorithm : {}", label)?;
     }
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f)?;
 
-    // Collect FLAC files, sorted by name
-    let mut flac_files: Vec<PathBuf> = fs::read_dir(folder)
-        .expect("Cannot read directory")
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.is_file()
-                && p.extension()
-                    .map(|ext| ext.eq_ignore_ascii_case("flac"))
-                    .unwrap_or(false)
+    // Albums are named by their tags, else by their path below the root
+    let albums: Vec<(String, Vec<&TrackResult>)> = folders
+        .iter()
+        .zip(results)
+        .map(|(folder, results)| {
+            let tracks: Vec<&TrackResult> = results.iter().flatten().collect();
+            let name = match album_tags(tracks.iter().copied()) {
+                Some((artist, album)) => format!("{} — {}", artist, album),
+                None => match folder.strip_prefix(root) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+                    _ => root_str.clone(),
+                },
+            };
+            (name, tracks)
         })
         .collect();
-    flac_files.sort();
+    let dr_text = |tracks: &[&TrackResult]| {
+        if tracks.is_empty() { "–".to_string() } else { format_dr(album_dr(tracks, opts), opts.precision, opts.dr.algorithm) }
+    };
+    let dr_width = albums.iter().map(|(_, tracks)| dr_text(tracks).chars().count()).max().unwrap_or(0).max(4);
 
-    if flac_files.is_empty() {
-        eprintln!("No FLAC files found in '{}'.", folder.display());
-        std::process::exit(0);
+    writeln!(f, "  {:<w$}  {:>6}  {:>7}  {:>8}  Album", "DR", "Tracks", "LUFS", "Peak TP", w = dr_width)?;
+    writeln!(f, "  {}", "─".repeat(73))?;
+    for (name, tracks) in &albums {
+        if tracks.is_empty() {
+            writeln!(f, "  {:<w$}  {:>6}  {:>7}  {:>8}  {}", dr_text(tracks), 0, "–", "–", name, w = dr_width)?;
+            continue;
+        }
+        let true_peak = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+        writeln!(
+            f,
+            "  {:<w$}  {:>6}  {:>+7.1}  {:>+8.2}  {}",
+            dr_text(tracks),
+            tracks.len(),
+            album_lufs(tracks),
+            true_peak,
+            name,
+            w = dr_width
+        )?;
     }
+    writeln!(f, "  {}", "─".repeat(73))?;
+    writeln!(f)?;
 
-    if !args.quiet {
-        println!("DR Measure — found {} FLAC file(s) in {}\n", flac_files.len(), folder.display());
+    let album_drs: Vec<i32> = albums
+        .iter()
+        .filter(|(_, tracks)| !tracks.is_empty())
+        .map(|(_, tracks)| opts.dr.algorithm.round(album_dr(tracks, opts)))
+        .collect();
+    let failed: usize = results.iter().flatten().filter(|r| r.is_err()).count();
+    writeln!(f, "  Summary")?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(f, "  Albums          : {}", albums.len())?;
+    writeln!(f, "  Tracks analysed : {}", albums.iter().map(|(_, tracks)| tracks.len()).sum::<usize>())?;
+    if failed > 0 {
+        writeln!(f, "  Failed files    : {} (see the album reports)", failed)?;
     }
+    if let (Some(min), Some(max)) = (album_drs.iter().min(), album_drs.iter().max()) {
+        writeln!(f, "  Album DR range  : DR{} – DR{}", min, max)?;
+    }
+    writeln!(f)?;
 
-    let total = flac_files.len();
-    let mut results: Vec<Result<TrackResult, (String, String)>> = Vec::with_capacity(total);
+    let stats: Vec<merge::AlbumStats> = albums
+        .iter()
+        .zip(release_years)
+        .filter(|((_, tracks), _)| !tracks.is_empty())
+        .map(|((_, tracks), release_year)| merge::AlbumStats {
+            dr: album_dr(tracks, opts),
+            lufs: Some(album_lufs(tracks)).filter(|l| l.is_finite()),
+            year: album_year(tracks.iter().copied()).or(*release_year),
+        })
+        .collect();
+    merge::write_statistics(&mut f, &stats)?;
+    if let Some(n) = opts.worst {
+        let paths: Vec<(PathBuf, &TrackResult)> = folders
+            .iter()
+            .zip(&albums)
+            .flat_map(|(folder, (_, tracks))| tracks.iter().map(|t| (folder.join(&t.filename), *t)))
+            .collect();
+        write_worst(&mut f, &paths, n, opts)?;
+    }
+    f.flush()
+}
 
-    for (i, path) in flac_files.iter().enumerate() {
-        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
-        if !args.quiet {
-            print!("  [{}/{}] Analysing {} … ", i + 1, total, name);
-            let _ = std::io::stdout().flush();
-        }
-        let t0 = Instant::now();
-        match process_flac(path) {
-            Ok(track) => {
-                if !args.quiet {
-                    println!("DR{} ({:.1}s)", track.dr, t0.elapsed().as_secs_f32());
-                }
-                results.push(Ok(track));
-            }
-            Err(e) => {
-                if !args.quiet {
-                    println!("ERROR: {}", e);
-                }
-                results.push(Err((name, e)));
-            }
-        }
+/// The files that failed `--verify`, or a line saying they all passed.
+fn write_integrity(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    writeln!(f, "  {}", s.integrity)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    let failed: Vec<(&TrackResult, &integrity::Integrity)> = tracks
+        .iter()
+        .filter_map(|t| Some((*t, t.integrity.as_ref().filter(|i| !i.is_ok())?)))
+        .collect();
+    let intact = tracks.iter().filter(|t| t.integrity == Some(integrity::Integrity::Ok)).count();
+    if failed.is_empty() && intact > 0 {
+        writeln!(f, "  ✓ {} file(s) decode to exactly the audio they were encoded from", intact)?;
+    }
+    for (t, integrity) in &failed {
+        writeln!(f, "  ✗ {} — {}", t.display_name(), integrity.describe(t.sample_rate))?;
+    }
+    let unchecked: Vec<&str> = tracks
+        .iter()
+        .filter(|t| t.integrity == Some(integrity::Integrity::NoChecksum))
+        .map(|t| t.filename.as_str())
+        .collect();
+    if !unchecked.is_empty() {
+        writeln!(f, "  No MD5 stored by the encoder, not checked: {}", unchecked.join(", "))?;
     }
+    writeln!(f)?;
 
-    // Determine output path
-    let output_path = args.output.unwrap_or_else(|| folder.join("dr_report.txt"));
+    Ok(())
+}
 
-    match write_report(&results, folder, &output_path) {
-        Ok(()) => {
-            if !args.quiet {
-                println!("\n  Report written → {}", output_path.display());
-            }
-        }
-        Err(e) => {
-            eprintln!("Failed to write report: {}", e);
-            std::process::exit(1);
+fn write_clipping(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let clipped: Vec<&&TrackResult> = tracks.iter().filter(|t| !t.clipping.regions.is_empty()).collect();
+
+    writeln!(f, "  {}", s.clipping)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    if clipped.is_empty() {
+        writeln!(f, "  {}", s.no_clipping)?;
+    }
+    for t in &clipped {
+        writeln!(
+            f,
+            "  ⚠ {} — {} clipped region(s), {:.3}s total",
+            t.display_name(),
+            t.clipping.regions.len(),
+            t.clipping.clipped_frames as f64 / t.sample_rate as f64
+        )?;
+        if verbose {
+            let worst: Vec<String> = t
+                .clipping
+                .worst(WORST_CLIPS)
+                .iter()
+                .map(|r| format!("{} ({})", format_timestamp(r.start as f64 / t.sample_rate as f64), r.len))
+                .collect();
+            writeln!(f, "      longest at {}", worst.join(", "))?;
         }
     }
+    writeln!(f)?;
+
+    Ok(())
 }
 
+/// Click timestamps listed per track (all of them with --verbose).
+const CLICK_TIMES_SHOWN: usize = 10;
 
+/// "mm:ss.s"
+fn format_timestamp(secs: f64) -> String {
+    let tenths = (secs * 10.0).round() as u64;
+    format!("{:02}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+fn write_clicks(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let clicky: Vec<&&TrackResult> = tracks.iter().filter(|t| !t.clicks.is_empty()).collect();
+
+    writeln!(f, "  {}", s.clicks)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    if clicky.is_empty() {
+        writeln!(f, "  {}", s.no_clicks)?;
+    }
+    for t in &clicky {
+        writeln!(f, "  ⚠ {} — {} click(s)", t.display_name(), t.clicks.len())?;
+        let shown = if verbose { t.clicks.len() } else { CLICK_TIMES_SHOWN };
+        let times: Vec<String> = t.clicks.iter().take(shown).map(|&s| format_timestamp(s)).collect();
+        let more = if t.clicks.len() > shown { ", …" } else { "" };
+        writeln!(f, "      at {}{}", times.join(", "), more)?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+const WORST_OFFENDERS: usize = 10;
+
+fn write_intersample_overs(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    let mut offenders: Vec<&&TrackResult> =
+        tracks.iter().filter(|t| !t.intersample_overs.is_empty()).collect();
+    offenders.sort_by_key(|t| std::cmp::Reverse(t.intersample_overs.len()));
+
+    writeln!(f, "  {}", s.intersample_overs)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    if offenders.is_empty() {
+        writeln!(f, "  {}", s.no_intersample_overs)?;
+    } else {
+        writeln!(
+            f,
+            "  {} track(s) exceed 0 dBTP between samples; worst offenders:",
+            offenders.len()
+        )?;
+    }
+    for t in offenders.iter().take(WORST_OFFENDERS) {
+        writeln!(
+            f,
+            "  ⚠ {} — {} over(s), true peak {:+.2} dBTP",
+            t.display_name(),
+            t.intersample_overs.len(),
+            t.true_peak_db
+        )?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_channel_dr(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    writeln!(f, "  {}", s.channel_dr)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        let values: Vec<String> = channel_names(t.channel_dr.len() as u32)
+            .iter()
+            .zip(&t.channel_dr)
+            .map(|(name, dr)| format!("{}: {:>5.2}", name, dr))
+            .collect();
+        writeln!(f, "  {}  {}", values.join("  "), t.display_name())?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_balance(f: &mut dyn Write, tracks: &[&TrackResult], opts: &ReportOptions) -> std::io::Result<()> {
+    let s = opts.lang.strings();
+    let exceeds = |t: &TrackResult| t.balance_db.is_some_and(|b| b.abs() > opts.balance_warn);
+    let flagged = tracks.iter().filter(|t| exceeds(t)).count();
+    if !opts.verbose && flagged == 0 {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.balance)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        let Some(balance) = t.balance_db else { continue };
+        if exceeds(t) {
+            writeln!(f, "  ⚠ {:>+6.2} dB  {}", balance, t.display_name())?;
+        } else if opts.verbose {
+            writeln!(f, "    {:>+6.2} dB  {}", balance, t.display_name())?;
+        }
+    }
+    if flagged > 0 {
+        writeln!(f, "  {} track(s) unbalanced by more than {:.1} dB", flagged, opts.balance_warn)?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_mid_side(f: &mut dyn Write, tracks: &[&TrackResult], opts: &ReportOptions) -> std::io::Result<()> {
+    let s = opts.lang.strings();
+    if tracks.iter().all(|t| t.mid_side.is_none()) {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.mid_side)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(
+        f,
+        "  {:<7} {:>8}  {:>8}    {:<7} {:>8}  {:>8}    {:>6}  File",
+        "M DR", "Peak dB", "RMS dB", "S DR", "Peak dB", "RMS dB", "S−M"
+    )?;
+    for t in tracks {
+        let Some([mid, side]) = t.mid_side else { continue };
+        writeln!(
+            f,
+            "  {:<7} {:>+8.2}  {:>+8.2}    {:<7} {:>+8.2}  {:>+8.2}    {:>+6.1}  {}",
+            format_dr(mid.dr, opts.precision, opts.dr.algorithm),
+            mid.peak_db,
+            mid.rms_db,
+            format_dr(side.dr, opts.precision, opts.dr.algorithm),
+            side.peak_db,
+            side.rms_db,
+            side.rms_db - mid.rms_db,
+            t.display_name()
+        )?;
+    }
+    writeln!(f)?;
+    writeln!(f, "  S−M is how far the sides sit below the centre: 0 dB for unrelated")?;
+    writeln!(f, "  channels (very wide), below -20 dB nearly mono.")?;
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// Shorter silence (a waveform that merely starts on a zero sample) is not listed.
+const SILENCE_REPORT_SECS: f64 = 0.01;
+
+/// Correlation at or below this means one channel is mostly a polarity-inverted
+/// copy of the other.
+const OUT_OF_PHASE_CORRELATION: f64 = -0.5;
+
+fn write_phase(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    let inverted: Vec<(&&TrackResult, f64)> = tracks
+        .iter()
+        .filter_map(|t| t.correlation.filter(|&c| c <= OUT_OF_PHASE_CORRELATION).map(|c| (t, c)))
+        .collect();
+    if inverted.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.phase)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for (t, c) in &inverted {
+        writeln!(
+            f,
+            "  ⚠ {} — channels out of phase (correlation {:+.2}), check the transfer",
+            t.display_name(), c
+        )?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_silence(f: &mut dyn Write, tracks: &[&TrackResult], opts: &ReportOptions) -> std::io::Result<()> {
+    let s = opts.lang.strings();
+    let has_silence = |t: &TrackResult| {
+        t.leading_silence_secs >= SILENCE_REPORT_SECS
+            || t.trailing_silence_secs >= SILENCE_REPORT_SECS
+            || t.gated_blocks > 0
+    };
+    let padded = tracks.iter().filter(|t| has_silence(t)).count();
+    if !opts.verbose && padded == 0 {
+        return Ok(());
+    }
+
+    if opts.trim_silence {
+        writeln!(f, "  {}", s.silence_trimmed)?;
+    } else {
+        writeln!(f, "  {}", s.silence)?;
+    }
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        if !opts.verbose && !has_silence(t) {
+            continue;
+        }
+        let gated = if opts.silence_gate.is_some() {
+            format!("gated {:>3} block(s)  ", t.gated_blocks)
+        } else {
+            String::new()
+        };
+        writeln!(
+            f,
+            "    lead {:>6.2}s  trail {:>6.2}s  {}{}",
+            t.leading_silence_secs, t.trailing_silence_secs, gated, t.display_name()
+        )?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// Subsonic energy above this, relative to the whole signal, is flagged as rumble.
+const RUMBLE_WARN_DB: f64 = -25.0;
+
+fn write_rumble(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let exceeds = |t: &TrackResult| t.subsonic_db.is_some_and(|db| db > RUMBLE_WARN_DB);
+    let flagged = tracks.iter().filter(|t| exceeds(t)).count();
+    if !verbose && flagged == 0 {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.rumble)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        let Some(db) = t.subsonic_db else { continue };
+        if exceeds(t) {
+            writeln!(f, "  ⚠ {:>7.1} dB  {}", db, t.display_name())?;
+        } else if verbose {
+            writeln!(f, "    {:>7.1} dB  {}", db, t.display_name())?;
+        }
+    }
+    if flagged > 0 {
+        writeln!(f, "  {} track(s) with subsonic content above {:.0} dB", flagged, RUMBLE_WARN_DB)?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// DC offset above -60 dBFS usually means a faulty ADC or transfer chain.
+const DC_OFFSET_WARN: f64 = 0.001;
+
+fn write_dc_offset(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let exceeds = |t: &TrackResult| t.dc_offset.iter().any(|dc| dc.abs() > DC_OFFSET_WARN);
+    let flagged = tracks.iter().filter(|t| exceeds(t)).count();
+    if !verbose && flagged == 0 {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.dc_offset)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        if !verbose && !exceeds(t) {
+            continue;
+        }
+        let values: Vec<String> = t.dc_offset.iter().map(|dc| format!("{:+.4}", dc * 100.0)).collect();
+        let marker = if exceeds(t) { "⚠" } else { " " };
+        writeln!(f, "  {} {}  {}", marker, values.join("  "), t.display_name())?;
+    }
+    if flagged > 0 {
+        writeln!(
+            f,
+            "  {} track(s) exceed ±{:.1}% DC offset",
+            flagged,
+            DC_OFFSET_WARN * 100.0
+        )?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_spectral(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let suspects = tracks.iter().filter(|t| t.spectrum.lossy_suspect).count();
+    let upsampled = tracks.iter().filter(|t| t.spectrum.upsampled_from.is_some()).count();
+    if !verbose && suspects == 0 && upsampled == 0 {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.spectral)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        if t.spectrum.lossy_suspect {
+            writeln!(
+                f,
+                "  ⚠ {} — cutoff {:.1} kHz, likely transcoded from lossy ({})",
+                t.display_name(),
+                t.spectrum.cutoff_hz / 1000.0,
+                t.spectrum.lossy_guess()
+            )?;
+        } else if let Some(rate) = t.spectrum.upsampled_from {
+            writeln!(
+                f,
+                "  ⚠ {} — no content above {:.1} kHz, likely upsampled from {:.1} kHz",
+                t.display_name(),
+                t.spectrum.cutoff_hz / 1000.0,
+                rate as f64 / 1000.0
+            )?;
+        } else if verbose {
+            writeln!(f, "    {} — cutoff {:.1} kHz", t.display_name(), t.spectrum.cutoff_hz / 1000.0)?;
+        }
+    }
+    if suspects > 0 {
+        writeln!(f, "  {} track(s) show an encoder-style low-pass", suspects)?;
+    }
+    if upsampled > 0 {
+        writeln!(f, "  {} hi-res track(s) look upsampled", upsampled)?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+fn write_bit_depth(f: &mut dyn Write, tracks: &[&TrackResult], verbose: bool, s: &Strings) -> std::io::Result<()> {
+    let padded = |t: &TrackResult| t.effective_bits > 0 && t.effective_bits < t.bit_depth;
+    let flagged = tracks.iter().filter(|t| padded(t)).count();
+    if !verbose && flagged == 0 {
+        return Ok(());
+    }
+
+    writeln!(f, "  {}", s.bit_depth)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    for t in tracks {
+        if padded(t) {
+            writeln!(
+                f,
+                "  ⚠ {} — {}-bit file carries only {} bits of data ({} wasted)",
+                t.display_name(),
+                t.bit_depth,
+                t.effective_bits,
+                t.bit_depth - t.effective_bits
+            )?;
+        } else if verbose {
+            writeln!(f, "    {} — {} of {} bits used", t.display_name(), t.effective_bits, t.bit_depth)?;
+        }
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// Size, average bitrate and encoder of every file (--verbose), to spot rips
+/// made with old encoder versions.
+fn write_files(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    writeln!(f, "  {}", s.files)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(f, "  {:>8}  {:>6}  {:<34}  File", "Size MB", "kbps", "Encoder")?;
+    for t in tracks {
+        writeln!(
+            f,
+            "  {:>8.1}  {:>6.0}  {:<34}  {}",
+            t.file_size as f64 / 1_048_576.0,
+            t.bitrate_kbps(),
+            t.vendor.as_deref().unwrap_or("–"),
+            t.display_name()
+        )?;
+    }
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// Album sample peak and RMS (dB): the loudest track peak, and the track RMS
+/// levels averaged as power, weighted by duration.
+fn album_levels(tracks: &[&TrackResult]) -> (f64, f64) {
+    let peak = tracks.iter().map(|t| t.peak_db).fold(f64::NEG_INFINITY, f64::max);
+    let duration: f64 = tracks.iter().map(|t| t.duration_secs).sum();
+    let power: f64 = tracks.iter().map(|t| t.duration_secs * 10f64.powf(t.rms_db / 10.0)).sum();
+    (peak, if duration > 0.0 { to_db((power / duration).sqrt()) } else { to_db(0.0) })
+}
+
+fn album_lufs(tracks: &[&TrackResult]) -> f64 {
+    let blocks: Vec<f64> = tracks.iter().flat_map(|t| t.gating_blocks.iter().cloned()).collect();
+    loudness::integrated_loudness(&blocks)
+}
+
+/// Range of track loudness above which the album is flagged as inconsistent.
+const LOUDNESS_SPREAD_WARN: f64 = 6.0;
+
+/// Range and standard deviation of the integrated loudness and the RMS level
+/// across the non-silent tracks.
+fn write_loudness_spread(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    let audible: Vec<&&TrackResult> = tracks.iter().filter(|t| t.lufs > loudness::LUFS_FLOOR).collect();
+    if audible.len() < 2 {
+        return Ok(());
+    }
+    let spread = |values: Vec<f64>| {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        (max - min, variance.sqrt())
+    };
+    let (lufs_range, lufs_sd) = spread(audible.iter().map(|t| t.lufs).collect());
+    let (rms_range, rms_sd) = spread(audible.iter().map(|t| t.rms_db).collect());
+
+    writeln!(f, "  {:<15} : {:.1} LU (σ {:.1} LU)", s.loudness_spread, lufs_range, lufs_sd)?;
+    writeln!(f, "  {:<15} : {:.1} dB (σ {:.1} dB)", s.rms_spread, rms_range, rms_sd)?;
+    if lufs_range > LOUDNESS_SPREAD_WARN {
+        writeln!(f, "  ⚠ {}", lang::fill(s.level_mismatch, &[&format!("{:.0}", LOUDNESS_SPREAD_WARN)]))?;
+    }
+
+    Ok(())
+}
+
+fn write_loudness(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+
+    // PLR = true peak - integrated loudness
+    writeln!(f, "  {}", s.loudness)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(f, "  {:<8}  {:<8}  {:<6}  File", "LUFS", "TP dBTP", "PLR")?;
+    // Album values per album in a mixed folder
+    for (label, tracks) in album_sections(tracks) {
+        if let Some(label) = label {
+            writeln!(f, "  {}", label)?;
+        }
+        for t in &tracks {
+            writeln!(
+                f,
+                "  {:>+8.2}  {:>+8.2}  {:>6.2}  {}",
+                t.lufs,
+                t.true_peak_db,
+                t.true_peak_db - t.lufs,
+                t.display_name()
+            )?;
+        }
+        let album_lufs = album_lufs(&tracks);
+        let album_tp = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+        writeln!(f)?;
+        writeln!(f, "  {:<15} : {:.2} LUFS", s.album_loudness, album_lufs)?;
+        writeln!(f, "  {:<15} : {:+.2} dBTP", s.album_true_peak, album_tp)?;
+        writeln!(f, "  {:<15} : {:.2} dB", s.album_plr, album_tp - album_lufs)?;
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+fn write_replaygain(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+
+    writeln!(f, "  {}", s.replaygain.replace("{}", &format!("{:.0}", loudness::REPLAYGAIN_REFERENCE_LUFS)))?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(f, "  {:<9}  {:<8}  File", "Gain dB", "Peak")?;
+    // Album values per album in a mixed folder, as --write-replaygain tags them
+    for (label, tracks) in album_sections(tracks) {
+        if let Some(label) = label {
+            writeln!(f, "  {}", label)?;
+        }
+        for t in &tracks {
+            writeln!(
+                f,
+                "  {:>+9.2}  {:<8.6}  {}",
+                loudness::replaygain(t.lufs),
+                t.sample_peak,
+                t.display_name()
+            )?;
+        }
+        let album_peak = tracks.iter().map(|t| t.sample_peak).fold(0.0f64, f64::max);
+        writeln!(f)?;
+        writeln!(f, "  {:<15} : {:+.2} dB", s.album_gain, loudness::replaygain(album_lufs(&tracks)))?;
+        writeln!(f, "  {:<15} : {:.6}", s.album_peak, album_peak)?;
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
+/// A streaming service's loudness normalization: target (LUFS) and whether
+/// quiet tracks are turned up (only as far as the true peak allows).
+struct StreamingTarget {
+    name: &'static str,
+    lufs: f64,
+    turns_up: bool,
+}
+
+const STREAMING_TARGETS: [StreamingTarget; 5] = [
+    StreamingTarget { name: "Spotify", lufs: -14.0, turns_up: true },
+    StreamingTarget { name: "Apple Music", lufs: -16.0, turns_up: true },
+    StreamingTarget { name: "YouTube", lufs: -14.0, turns_up: false },
+    StreamingTarget { name: "Tidal", lufs: -14.0, turns_up: false },
+    StreamingTarget { name: "Amazon", lufs: -14.0, turns_up: false },
+];
+
+/// Highest true peak a service will raise a quiet track to.
+const STREAMING_PEAK_CEILING: f64 = -1.0;
+
+impl StreamingTarget {
+    fn gain(&self, t: &TrackResult) -> f64 {
+        let gain = self.lufs - t.lufs;
+        if gain <= 0.0 {
+            gain
+        } else if self.turns_up {
+            gain.min((STREAMING_PEAK_CEILING - t.true_peak_db).max(0.0))
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The `n` lowest-DR tracks (--worst), lowest first, with their paths.
+fn write_worst(f: &mut dyn Write, tracks: &[(PathBuf, &TrackResult)], n: usize, opts: &ReportOptions) -> std::io::Result<()> {
+    let s = opts.lang.strings();
+    let mut worst: Vec<&(PathBuf, &TrackResult)> = tracks.iter().collect();
+    worst.sort_by(|a, b| a.1.dr_exact.total_cmp(&b.1.dr_exact));
+    worst.truncate(n);
+    writeln!(f, "  {}", s.worst)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    let dr_width = if opts.precision == 0 { 4 } else { 5 + opts.precision };
+    for (path, t) in worst {
+        writeln!(
+            f,
+            "  {:<w$}  {:>+7.1} LUFS  {:>+6.2} dBTP  {}",
+            format_dr(t.dr_exact, opts.precision, opts.dr.algorithm),
+            t.lufs,
+            t.true_peak_db,
+            path.display(),
+            w = dr_width
+        )?;
+    }
+    writeln!(f)
+}
+
+fn write_streaming(f: &mut dyn Write, tracks: &[&TrackResult], s: &Strings) -> std::io::Result<()> {
+    writeln!(f, "  {}", s.streaming)?;
+    writeln!(f, "  ───────────────────────────────")?;
+    let header: Vec<String> = STREAMING_TARGETS
+        .iter()
+        .map(|s| format!("{:<15}", format!("{} {:.0}", s.name, s.lufs)))
+        .collect();
+    writeln!(f, "  {}  File", header.join("  "))?;
+    for t in tracks {
+        let cells: Vec<String> = STREAMING_TARGETS
+            .iter()
+            .map(|s| {
+                let gain = s.gain(t);
+                format!("{:<15}", format!("{:+5.1} ({:+5.1})", gain, t.true_peak_db + gain))
+            })
+            .collect();
+        writeln!(f, "  {}  {}", cells.join("  "), t.display_name())?;
+    }
+    writeln!(f)?;
+    writeln!(
+        f,
+        "  Quiet tracks are raised only up to {:.0} dBTP by Spotify and Apple Music;",
+        STREAMING_PEAK_CEILING
+    )?;
+    writeln!(f, "  YouTube, Tidal and Amazon only turn loud tracks down.")?;
+    writeln!(f)?;
+
+    Ok(())
+}
+
+/// Set when the report itself goes to stdout, so progress moves to stderr.
+static PROGRESS_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Set by --ascii, for the console output.
+static ASCII_CONSOLE: AtomicBool = AtomicBool::new(false);
+
+/// `print!` for console progress, on stderr while the report is on stdout.
+macro_rules! progress {
+    ($($arg:tt)*) => {{
+        let text = format!($($arg)*);
+        let text = if ASCII_CONSOLE.load(Ordering::Relaxed) { to_ascii(&text) } else { text };
+        if PROGRESS_ON_STDERR.load(Ordering::Relaxed) {
+            eprint!("{}", text);
+        } else {
+            print!("{}", text);
+        }
+    }};
+}
+
+/// Plain-ASCII spelling of the report's box-drawing characters and symbols;
+/// anything else outside ASCII (accents in file names) becomes '?'.
+fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ' '..='~' | '\n' | '\t' | '\r' | '\u{1b}' => out.push(c),
+            '═' => out.push('='),
+            '─' | '—' | '–' | '−' => out.push('-'),
+            '⚠' => out.push('!'),
+            '✗' => out.push('x'),
+            '→' => out.push_str("->"),
+            '…' => out.push_str("..."),
+            '±' => out.push_str("+/-"),
+            'Δ' => out.push('d'),
+            'σ' => out.push_str("sd"),
+            '·' => out.push('-'),
+            '█' => out.push('#'),
+            '░' => out.push('-'),
+            '×' => out.push('x'),
+            'ß' => out.push_str("ss"),
+            'À'..='ÿ' => out.push(fold_accent(c)),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+/// The unaccented letter of a Latin-1 one ("é" → "e"), or "?".
+fn fold_accent(c: char) -> char {
+    const FROM: &str = "ÀÁÂÃÄÅÇÈÉÊËÌÍÎÏÑÒÓÔÕÖÙÚÛÜÝàáâãäåçèéêëìíîïñòóôõöùúûüýÿ";
+    const TO: &str = "AAAAAACEEEEIIIINOOOOOUUUUYaaaaaaceeeeiiiinooooouuuuyy";
+    FROM.chars().position(|f| f == c).and_then(|i| TO.chars().nth(i)).unwrap_or('?')
+}
+
+/// Passes text through `to_ascii` on its way to `inner`.
+struct AsciiWriter<W: Write> {
+    inner: W,
+    /// Bytes of a character split across two writes.
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Write for AsciiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).expect("valid prefix");
+        self.inner.write_all(to_ascii(text).as_bytes())?;
+        self.pending.drain(..valid);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The destination of a text report, transliterated with --ascii.
+pub fn create_text_output(path: &Path, ascii: bool) -> std::io::Result<Box<dyn Write>> {
+    let out = create_output(path)?;
+    Ok(if ascii { Box::new(AsciiWriter { inner: out, pending: Vec::new() }) } else { out })
+}
+
+/// The report's destination: the file at `path`, or stdout for "-".
+pub fn create_output(path: &Path) -> std::io::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        Ok(Box::new(File::create(path)?))
+    }
+}
+
+/// An existing report opened for `--append`.
+pub fn append_output(path: &Path) -> std::io::Result<Box<dyn Write>> {
+    Ok(Box::new(fs::OpenOptions::new().append(true).open(path)?))
+}
+
+/// Reports the outcome of an auxiliary export; failures are fatal like the report's.
+fn finish_export(what: &str, path: &Path, result: std::io::Result<()>, quiet: bool) {
+    match result {
+        Ok(()) => {
+            if !quiet {
+                progress!("  {} written → {}\n", what, path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", what.to_lowercase(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// ─── Main ─────────────────────────────────────────────────────────────────────
+
+/// The FLAC files to analyse: every one in a folder, or a single file (the
+/// returned folder is then its parent). Exits when there is nothing to do.
+fn collect_flac_files(path: &Path) -> (PathBuf, Vec<PathBuf>) {
+    // A single file is analysed on its own, with the report next to it
+    let (folder, mut flac_files) = if path.is_file() {
+        let parent = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        (parent, vec![path.to_path_buf()])
+    } else if path.is_dir() {
+        // Collect FLAC files
+        let files: Vec<PathBuf> = fs::read_dir(path)
+            .expect("Cannot read directory")
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.is_file()
+                    && p.extension()
+                        .map(|ext| ext.eq_ignore_ascii_case("flac"))
+                        .unwrap_or(false)
+            })
+            .collect();
+        (path.to_path_buf(), files)
+    } else {
+        eprintln!("Error: '{}' is not a valid directory.", path.display());
+        std::process::exit(1);
+    };
+    // Analysed in the order the files are usually numbered
+    flac_files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    if flac_files.is_empty() {
+        eprintln!("No FLAC files found in '{}'.", folder.display());
+        std::process::exit(0);
+    }
+    (folder, flac_files)
+}
+
+/// Every folder under `root` (itself included) that holds FLAC files, with
+/// its files, in path order. Symlinked folders are not followed.
+fn collect_album_folders(root: &Path) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let mut albums = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Warning: cannot read '{}': {}", dir.display(), e);
+                continue;
+            }
+        };
+        let mut files = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => pending.push(path),
+                Ok(t) if t.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("flac")) => {
+                    files.push(path)
+                }
+                _ => {}
+            }
+        }
+        if !files.is_empty() {
+            files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+            albums.push((dir, files));
+        }
+    }
+    albums.sort();
+    albums
+}
+
+fn analysis_options(args: &Args) -> AnalysisOptions {
+    AnalysisOptions {
+        downmix: args.downmix,
+        dr: DrParams {
+            algorithm: args.algorithm,
+            block_seconds: args.block_seconds,
+            top_ratio: args.top_ratio,
+            nth_peak: args.nth_peak,
+            lfe: args.lfe,
+        },
+        mid_side: args.ms,
+        trim_silence: args.trim_silence,
+        silence_gate: args.silence_gate,
+        start: args.start,
+        end: args.end,
+        quick: args.quick,
+        waveform: args.waveforms.is_some(),
+        spectrogram: args.spectrograms.is_some(),
+        weighting: args.weighting,
+        verify: args.verify,
+        true_peak: shows(args, &["true_peak_db", "plr_db", "intersample_overs"])
+            || args.psr_timeline.is_some()
+            || args.clip_log.is_some()
+            || args.labels.is_some(),
+        clicks: shows(args, &["clicks"]) || args.labels.is_some(),
+        spectrum: shows(args, &["spectral_cutoff_hz", "lossy_suspect", "upsampled_from"]) || args.spectrograms.is_some(),
+        rumble: shows(args, &["subsonic_db"]),
+    }
+}
+
+/// Whether the reports of `args` show any of the measurements `keys` (named
+/// as in the JSON document): foobar2000 logs leave them out and new CSV
+/// reports keep to --columns, while every other report and export shows them.
+fn shows(args: &Args, keys: &[&str]) -> bool {
+    let exports = args.db.is_some() || args.sidecar || args.embed || args.combined || args.worst.is_some();
+    exports
+        || match report_format(args) {
+            Format::Foobar => false,
+            Format::Csv => match (&args.columns, args.append) {
+                (Some(columns), false) => columns.iter().flat_map(|c| c.document_keys()).any(|key| keys.contains(key)),
+                _ => true,
+            },
+            _ => true,
+        }
+}
+
+fn report_options(args: &Args, dr: DrParams) -> ReportOptions {
+    let columns = args.columns.clone().unwrap_or_else(|| {
+        let mut columns = vec![Column::Dr, Column::Grade];
+        if args.weighting.is_some() {
+            columns.push(Column::DrK);
+        }
+        columns.extend([
+            Column::Peak,
+            Column::TruePeak,
+            Column::Rms,
+            Column::Crest,
+            Column::Floor,
+            Column::Duration,
+            Column::Info,
+        ]);
+        for (on, column) in [
+            (args.percentiles, Column::Percentiles),
+            (args.gain, Column::Gain),
+            (args.correlation, Column::Corr),
+        ] {
+            if on {
+                columns.push(column);
+            }
+        }
+        columns.push(Column::File);
+        columns
+    });
+    ReportOptions {
+        verbose: args.verbose,
+        dr,
+        downmix: args.downmix,
+        precision: args.precision as usize,
+        album_method: args.album_method,
+        columns: columns.clone(),
+        gain: (args.gain || columns.contains(&Column::Gain)).then_some(args.gain_target),
+        balance_warn: args.balance_warn,
+        trim_silence: args.trim_silence,
+        silence_gate: args.silence_gate,
+        start: args.start,
+        end: args.end,
+        quick: args.quick,
+        ascii: args.ascii,
+        worst: args.worst,
+        lang: args.lang,
+        // Per folder, in write_folder_report
+        release: None,
+    }
+}
+
+/// A track's analysis, or its file name and the error.
+type Outcome = Result<TrackResult, (String, String)>;
+
+/// Whether the console progress goes to a terminal.
+fn console_is_terminal() -> bool {
+    use std::io::IsTerminal;
+    if PROGRESS_ON_STDERR.load(Ordering::Relaxed) {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    }
+}
+
+fn console_color(mode: ColorMode) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => console_is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+    }
+}
+
+/// `text` in the ANSI colour of a DR grade (green for A through red for F).
+fn paint(text: &str, dr: i32, color: bool) -> String {
+    if !color {
+        return text.to_string();
+    }
+    let code = match rating(dr).0 {
+        "A" => "1;32",
+        "B" => "32",
+        "C" => "33",
+        "D" => "38;5;208",
+        _ => "1;31",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Header of the `--table` console output.
+const CONSOLE_HEADER: &str = "DR       Grade  Peak SP   RMS dB    LUFS      Duration  Time    File";
+
+/// A finished track as a `--table` console row.
+fn console_row(track: &TrackResult, args: &Args, algorithm: Algorithm, secs: f32, color: bool) -> String {
+    let dr = format!("{}{}", if args.quick { "~" } else { "" }, format_dr(track.dr_exact, args.precision as usize, algorithm));
+    format!(
+        "{}  {}  {:>8.2}  {:>8.2}  {:>8.2}  {:<8}  {:>5.1}s  {}",
+        paint(&format!("{:<7}", dr), track.dr, color),
+        paint(&format!("{:<5}", rating(track.dr).0), track.dr, color),
+        track.peak_db,
+        track.rms_db,
+        track.lufs,
+        format_duration(track.duration_secs),
+        secs,
+        track.display_name()
+    )
+}
+
+/// DR at which a `--chart` bar fills CHART_WIDTH cells; higher values are capped.
+const CHART_MAX_DR: f64 = 20.0;
+const CHART_WIDTH: usize = 40;
+
+/// The `--chart` bars: one per track, in eighth-cell steps, coloured by grade,
+/// with the album DR last.
+fn print_dr_chart(results: &[Outcome], args: &Args, opts: &ReportOptions) {
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    if tracks.is_empty() {
+        return;
+    }
+    let color = console_color(args.color);
+    let ascii = ASCII_CONSOLE.load(Ordering::Relaxed);
+    let bar = |dr: f64| {
+        let eighths = (dr.clamp(0.0, CHART_MAX_DR) / CHART_MAX_DR * (CHART_WIDTH * 8) as f64).round() as usize;
+        // ASCII consoles get whole cells only
+        let partial = if ascii { "" } else { EIGHTHS[eighths % 8] };
+        format!("{}{}", "█".repeat(eighths / 8), partial)
+    };
+    let name = |text: String| match text.chars().count() {
+        n if n > 32 => format!("{}…", text.chars().take(31).collect::<String>()),
+        _ => text,
+    };
+
+    progress!("\n  DR chart (full width = DR{:.0})\n", CHART_MAX_DR);
+    progress!("  {}\n", "─".repeat(31));
+    let line = |label: String, dr: f64| {
+        let rounded = opts.dr.algorithm.round(dr);
+        let value = format_dr(dr, opts.precision, opts.dr.algorithm);
+        let row = format!("  {:<33} {:<6} {}", label, value, paint(&bar(dr), rounded, color));
+        progress!("{}\n", row.trim_end());
+    };
+    for t in &tracks {
+        line(name(t.display_name()), t.dr_exact);
+    }
+    line("Album".to_string(), album_dr(&tracks, opts));
+}
+
+/// Analyses every file, on --jobs worker threads, printing progress unless
+/// `quiet`, and hands the results to `on_result` in file order, each as soon
+/// as it and those before it are ready.
+fn analyse_files(
+    flac_files: &[PathBuf],
+    analysis_opts: &AnalysisOptions,
+    args: &Args,
+    quiet: bool,
+    on_result: &mut dyn FnMut(&Outcome),
+) -> Vec<Outcome> {
+    let cache = match flac_files.first().and_then(|path| path.parent()) {
+        Some(folder) if args.cache && cache::applies(analysis_opts) => cache::Cache::load(folder, analysis_opts),
+        _ => cache::Cache::default(),
+    };
+    let cache = &cache;
+    let decoded: Vec<AtomicU64> = flac_files.iter().map(|_| AtomicU64::new(0)).collect();
+    let decoded = &decoded;
+    let bars = (!quiet && console_is_terminal()).then(|| ProgressBars::new(flac_files, decoded));
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let jobs = args.jobs.map_or(cores, |n| n as usize).min(flac_files.len());
+
+    let next_file = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        if let Some(bars) = &bars {
+            scope.spawn(|| bars.run());
+        }
+        let results = if jobs < 2 {
+            let mut analyse = |i: usize| timed_analysis(&flac_files[i], analysis_opts, cache, &decoded[i]);
+            report_files(flac_files, args, analysis_opts, quiet, bars.as_ref(), &mut analyse, on_result)
+        } else {
+            // Workers take the files in turn; the results are reported in file
+            // order, as each one's predecessors are done, so that the console
+            // output and streamed records come out as they do with one job
+            let (sender, receiver) = std::sync::mpsc::channel();
+            for _ in 0..jobs {
+                let (sender, next_file) = (sender.clone(), &next_file);
+                scope.spawn(move || loop {
+                    let i = next_file.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = flac_files.get(i) else { break };
+                    // The receiver only goes away when the scan is aborted
+                    if sender.send((i, timed_analysis(path, analysis_opts, cache, &decoded[i]))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            let mut finished = HashMap::new();
+            let mut analyse = |i: usize| loop {
+                if let Some(outcome) = finished.remove(&i) {
+                    break outcome;
+                }
+                let (j, outcome) = receiver.recv().expect("an analysis thread panicked");
+                finished.insert(j, outcome);
+            };
+            report_files(flac_files, args, analysis_opts, quiet, bars.as_ref(), &mut analyse, on_result)
+        };
+        if let Some(bars) = &bars {
+            bars.finish();
+        }
+        results
+    })
+}
+
+/// The analysis of one file and the seconds it took.
+type TimedAnalysis = (Result<TrackResult, String>, f32);
+
+fn timed_analysis(path: &Path, opts: &AnalysisOptions, cache: &cache::Cache, decoded: &AtomicU64) -> TimedAnalysis {
+    let t0 = Instant::now();
+    let result = cache.get(path).map_or_else(|| process_flac(path, opts, decoded), Ok);
+    // Done as far as the progress bars go, also when cached or cut short by --end
+    decoded.store(u64::MAX, Ordering::Relaxed);
+    (result, t0.elapsed().as_secs_f32())
+}
+
+/// Progress bars kept at the bottom of the terminal while a folder is
+/// analysed: one for the file whose result is printed next, one for the whole
+/// folder with the decoding speed and the time left. Everything printed
+/// meanwhile goes through `suspend`, above them.
+struct ProgressBars<'a> {
+    names: Vec<String>,
+    /// Length in frames and sample rate of every file, from STREAMINFO.
+    lengths: Vec<(u64, u32)>,
+    /// Frames decoded so far per file (u64::MAX once it is done).
+    decoded: &'a [AtomicU64],
+    current: AtomicUsize,
+    started: Instant,
+    /// Whether the bars are on screen; locked while the console is written.
+    shown: std::sync::Mutex<bool>,
+    finished: AtomicBool,
+}
+
+const BAR_WIDTH: usize = 24;
+const BAR_NAME_WIDTH: usize = 28;
+
+/// Moves up over the two bar lines, clearing them.
+const CLEAR_BARS: &str = "\r\x1b[2K\x1b[1A\x1b[2K";
+
+impl<'a> ProgressBars<'a> {
+    fn new(flac_files: &[PathBuf], decoded: &'a [AtomicU64]) -> Self {
+        let length = |path: &PathBuf| {
+            FlacReader::open(path).map_or((0, 0), |r| (r.streaminfo().samples.unwrap_or(0), r.streaminfo().sample_rate))
+        };
+        ProgressBars {
+            names: flac_files.iter().map(|p| p.file_name().unwrap_or_default().to_string_lossy().into_owned()).collect(),
+            lengths: flac_files.iter().map(length).collect(),
+            decoded,
+            current: AtomicUsize::new(0),
+            started: Instant::now(),
+            shown: std::sync::Mutex::new(false),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    /// Redraws the bars ten times a second until `finish`.
+    fn run(&self) {
+        while !self.finished.load(Ordering::Relaxed) {
+            self.redraw();
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+
+    /// Shows file `i` on the first bar.
+    fn set_current(&self, i: usize) {
+        self.current.store(i, Ordering::Relaxed);
+    }
+
+    /// Runs `print` with the bars taken off the screen; `run` puts them back.
+    fn suspend(&self, print: impl FnOnce()) {
+        let mut shown = self.shown.lock().unwrap_or_else(|e| e.into_inner());
+        if *shown {
+            progress!("{}", CLEAR_BARS);
+            *shown = false;
+        }
+        print();
+        flush_console();
+    }
+
+    /// Takes the bars off the screen for good.
+    fn finish(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+        self.suspend(|| {});
+    }
+
+    fn redraw(&self) {
+        let mut shown = self.shown.lock().unwrap_or_else(|e| e.into_inner());
+        // finish() may have cleared the screen since run() last looked
+        if self.finished.load(Ordering::Relaxed) {
+            return;
+        }
+        progress!("{}{}", if *shown { CLEAR_BARS } else { "" }, self.render());
+        flush_console();
+        *shown = true;
+    }
+
+    /// Seconds of file `i`'s audio decoded, and its duration.
+    fn seconds(&self, i: usize) -> (f64, f64) {
+        let (frames, rate) = self.lengths[i];
+        let decoded = self.decoded[i].load(Ordering::Relaxed).min(frames);
+        let rate = rate.max(1) as f64;
+        (decoded as f64 / rate, frames as f64 / rate)
+    }
+
+    fn render(&self) -> String {
+        let bar = |fraction: f64| {
+            let filled = ((fraction * BAR_WIDTH as f64) as usize).min(BAR_WIDTH);
+            format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled))
+        };
+        let fraction = |done: f64, total: f64| if total > 0.0 { done / total } else { 0.0 };
+
+        let i = self.current.load(Ordering::Relaxed).min(self.names.len() - 1);
+        let mut name: String = self.names[i].chars().take(BAR_NAME_WIDTH).collect();
+        if self.names[i].chars().count() > BAR_NAME_WIDTH {
+            name.pop();
+            name.push('…');
+        }
+        let (position, duration) = self.seconds(i);
+        let file = fraction(position, duration);
+        let file_line = format!(
+            "  {:<w$}  {} {:>3.0}%  {} / {}",
+            name,
+            bar(file),
+            (file * 100.0).floor(),
+            format_duration(position),
+            format_duration(duration),
+            w = BAR_NAME_WIDTH
+        );
+
+        let (decoded, total) = (0..self.names.len())
+            .map(|i| self.seconds(i))
+            .fold((0.0, 0.0), |(decoded, total), (d, t)| (decoded + d, total + t));
+        let done = self.decoded.iter().filter(|d| d.load(Ordering::Relaxed) == u64::MAX).count();
+        let overall = fraction(decoded, total);
+        // Seconds of audio decoded per second
+        let speed = decoded / self.started.elapsed().as_secs_f64().max(0.001);
+        let eta = if speed > 0.0 { format_duration((total - decoded) / speed) } else { "--:--".to_string() };
+        let total_line = format!(
+            "  {:<w$}  {} {:>3.0}%  {}/{} files  {:.0}× realtime  ETA {}",
+            "All files",
+            bar(overall),
+            (overall * 100.0).floor(),
+            done,
+            self.names.len(),
+            speed,
+            eta,
+            w = BAR_NAME_WIDTH
+        );
+        format!("{}\n{}", file_line, total_line)
+    }
+}
+
+fn flush_console() {
+    if PROGRESS_ON_STDERR.load(Ordering::Relaxed) {
+        let _ = std::io::stderr().flush();
+    } else {
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Prints each file's progress line and collects the results, in file order;
+/// `analyse` gives the analysis of the file at an index.
+fn report_files(
+    flac_files: &[PathBuf],
+    args: &Args,
+    analysis_opts: &AnalysisOptions,
+    quiet: bool,
+    bars: Option<&ProgressBars>,
+    analyse: &mut dyn FnMut(usize) -> TimedAnalysis,
+    on_result: &mut dyn FnMut(&Outcome),
+) -> Vec<Outcome> {
+    let total = flac_files.len();
+    let mut results: Vec<Result<TrackResult, (String, String)>> = Vec::with_capacity(total);
+    let color = console_color(args.color);
+    if args.table && !quiet {
+        progress!("  {}\n  {}\n", CONSOLE_HEADER, "─".repeat(CONSOLE_HEADER.len()));
+    }
+    // Above the progress bars when they are shown
+    let console = |print: &dyn Fn()| match bars {
+        Some(bars) => bars.suspend(print),
+        None => print(),
+    };
+
+    let mut identifier = args.acoustid.as_deref().map(acoustid::Identifier::new);
+
+    for (i, path) in flac_files.iter().enumerate() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        // With the bars, each file's line is printed whole once it is done
+        let started = format!("  [{}/{}] Analysing {} … ", i + 1, total, name);
+        match bars {
+            Some(bars) => bars.set_current(i),
+            None if !quiet && !args.table => {
+                progress!("{}", started);
+                flush_console();
+            }
+            None => {}
+        }
+        let started = if bars.is_some() { started.as_str() } else { "" };
+        let (result, secs) = analyse(i);
+        match result {
+            Ok(mut track) => {
+                let identified = identifier.as_mut().map(|i| i.identify(&mut track, path));
+                console(&|| {
+                    if !quiet && args.table {
+                        let row = console_row(&track, args, analysis_opts.dr.algorithm, secs, color);
+                        progress!("  {}\n", row);
+                    } else if !quiet {
+                        let dr = format!(
+                            "{}{}",
+                            if args.quick { "~" } else { "" },
+                            format_dr(track.dr_exact, args.precision as usize, analysis_opts.dr.algorithm)
+                        );
+                        progress!("{}{} ({:.1}s)\n", started, paint(&dr, track.dr, color), secs);
+                    }
+                    if let Some(integrity) = track.integrity.as_ref().filter(|i| !i.is_ok()) {
+                        eprintln!("  ✗ {}: {}", name, integrity.describe(track.sample_rate));
+                    }
+                    if let Some(Err(e)) = &identified {
+                        eprintln!("  AcoustID lookup failed for {}: {}", name, e);
+                    }
+                });
+                let result = Ok(track);
+                console_result(bars, on_result, &result);
+                results.push(result);
+            }
+            Err(e) => {
+                console(&|| {
+                    if !quiet && args.table {
+                        progress!("  {}  {}\n", paint(&format!("ERROR: {}", e), 0, color), name);
+                    } else if !quiet {
+                        progress!("{}{}\n", started, paint(&format!("ERROR: {}", e), 0, color));
+                    }
+                });
+                let result = Err((name, e));
+                console_result(bars, on_result, &result);
+                results.push(result);
+            }
+        }
+    }
+    results
+}
+
+/// Hands `result` to `on_result`, which may stream it to the terminal.
+fn console_result(bars: Option<&ProgressBars>, on_result: &mut dyn FnMut(&Outcome), result: &Outcome) {
+    match bars {
+        Some(bars) => bars.suspend(|| on_result(result)),
+        None => on_result(result),
+    }
+}
+
+/// Compares file names the way people read them: runs of digits by their
+/// value ("2 - …" before "10 - …"), everything else case-insensitively.
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (x, y) = match (a.peek(), b.peek()) {
+            (Some(&x), Some(&y)) => (x, y),
+            (x, y) => return x.is_some().cmp(&y.is_some()),
+        };
+        let order = if x.is_ascii_digit() && y.is_ascii_digit() {
+            let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                let digits: String = std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+                let value = digits.trim_start_matches('0').to_string();
+                (value.len(), value)
+            };
+            number(&mut a).cmp(&number(&mut b))
+        } else {
+            a.next();
+            b.next();
+            x.to_lowercase().cmp(y.to_lowercase())
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+}
+
+/// Ends the run with exit status 1 when `--verify` found corrupt files.
+fn exit_if_corrupt<'a>(results: impl IntoIterator<Item = &'a Outcome>, quiet: bool) {
+    let corrupt = results.into_iter().flatten().filter(|t| t.integrity.as_ref().is_some_and(|i| !i.is_ok())).count();
+    if corrupt > 0 {
+        if !quiet {
+            progress!("\n  ✗ {} file(s) failed verification\n", corrupt);
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Orders the analysed tracks by `key`; files that failed stay at the end.
+fn sort_results(results: &mut [Outcome], key: SortKey, reverse: bool) {
+    use std::cmp::Ordering;
+
+    // Track order needs every file numbered; one untagged file and the whole
+    // folder goes by name, as an ordering half by tag and half by name isn't
+    // one order at all
+    let numbered = results.iter().flatten().all(|t| t.tags.track_number.is_some());
+    let compare = |a: &TrackResult, b: &TrackResult| -> Ordering {
+        match key {
+            SortKey::Name => natural_cmp(&a.filename, &b.filename),
+            SortKey::Dr => a.dr_exact.total_cmp(&b.dr_exact),
+            SortKey::Rms => a.rms_db.total_cmp(&b.rms_db),
+            SortKey::Duration => a.duration_secs.total_cmp(&b.duration_secs),
+            SortKey::Track if numbered => (a.tags.disc_number, a.tags.track_number)
+                .cmp(&(b.tags.disc_number, b.tags.track_number))
+                .then_with(|| natural_cmp(&a.filename, &b.filename)),
+            SortKey::Track => natural_cmp(&a.filename, &b.filename),
+        }
+    };
+    results.sort_by(|a, b| match (a, b) {
+        (Ok(a), Ok(b)) if reverse => compare(b, a),
+        (Ok(a), Ok(b)) => compare(a, b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err((a, _)), Err((b, _))) => a.cmp(b),
+    });
+}
+
+/// The `compare` subcommand: analyses both folders and writes the comparison.
+fn compare_folders(folders: [&PathBuf; 2], args: &Args, analysis_opts: &AnalysisOptions, report_opts: &ReportOptions) {
+    let output_path = args.output.clone().unwrap_or_else(|| folders[1].join("dr_compare.txt"));
+    let to_stdout = output_path == Path::new("-");
+    PROGRESS_ON_STDERR.store(to_stdout, Ordering::Relaxed);
+    let mut results = Vec::with_capacity(2);
+    for (label, path) in ["A", "B"].iter().zip(folders) {
+        let (folder, flac_files) = collect_flac_files(path);
+        if !args.quiet {
+            progress!("DR Measure — {}: {} FLAC file(s) in {}\n\n", label, flac_files.len(), folder.display());
+        }
+        results.push(analyse_files(&flac_files, analysis_opts, args, args.quiet, &mut |_| {}));
+        if !args.quiet {
+            progress!("\n");
+        }
+    }
+
+    let tracks: Vec<Vec<&TrackResult>> =
+        results.iter().map(|r| r.iter().filter_map(|r| r.as_ref().ok()).collect()).collect();
+    let errors: Vec<(String, String)> = ["A", "B"]
+        .iter()
+        .zip(&results)
+        .flat_map(|(label, r)| {
+            r.iter().filter_map(move |r| r.as_ref().err().map(|(name, e)| (format!("{}: {}", label, name), e.clone())))
+        })
+        .collect();
+
+    match compare::write_comparison(
+        [&tracks[0], &tracks[1]],
+        &errors,
+        [folders[0], folders[1]],
+        &output_path,
+        report_opts,
+    ) {
+        Ok(()) => {
+            if !args.quiet && !to_stdout {
+                progress!("  Comparison written → {}\n", output_path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write comparison: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The `merge` subcommand: reads the reports and writes the library report,
+/// by album name or, with --sort dr, by album DR.
+fn merge_reports(paths: &[PathBuf], args: &Args, report_opts: &ReportOptions) {
+    let output_path = args.output.clone().unwrap_or_else(|| PathBuf::from("dr_library.txt"));
+    let to_stdout = output_path == Path::new("-");
+    PROGRESS_ON_STDERR.store(to_stdout, Ordering::Relaxed);
+
+    let reports = merge::find_reports(paths);
+    if reports.is_empty() {
+        eprintln!("No reports found.");
+        std::process::exit(1);
+    }
+    let mut albums = Vec::new();
+    let mut skipped = Vec::new();
+    for path in reports {
+        match merge::read_report(&path) {
+            Ok(album) => albums.push(album),
+            Err(e) => {
+                if !args.quiet {
+                    progress!("  ✗ {} — {}\n", path.display(), e);
+                }
+                skipped.push((path, e));
+            }
+        }
+    }
+
+    albums.sort_by(|a, b| match args.sort {
+        SortKey::Dr => a.dr.total_cmp(&b.dr),
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    });
+    if args.reverse {
+        albums.reverse();
+    }
+
+    match merge::write_library(&albums, &skipped, &output_path, report_opts) {
+        Ok(()) => {
+            if !args.quiet && !to_stdout {
+                progress!("  Merged {} report(s) → {}\n", albums.len(), output_path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write library report: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The `diff` subcommand: compares two reports, printing the differences
+/// unless --output is given.
+fn diff_reports(paths: [&PathBuf; 2], args: &Args) {
+    let [old, new] = paths.map(|path| {
+        diff::read_report(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let output_path = args.output.clone().unwrap_or_else(|| PathBuf::from("-"));
+    match diff::write_diff(&old, &new, &output_path, args.ascii) {
+        Ok(()) => {
+            if !args.quiet && output_path != Path::new("-") {
+                progress!("  Diff written → {}\n", output_path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write diff: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn verify_report(report: &Path, pubkey: Option<&Path>) {
+    match seal::verify(report, pubkey) {
+        Ok(seal::Verdict::Intact { signed: true, .. }) => {
+            println!("OK: {} matches its SHA-256 and its signature", report.display());
+        }
+        Ok(seal::Verdict::Intact { has_signature, .. }) => {
+            println!("OK: {} matches its SHA-256", report.display());
+            if has_signature {
+                println!("  The signature was not checked; pass --pubkey to check it.");
+            }
+        }
+        Ok(seal::Verdict::Tampered(reason)) => {
+            println!("MODIFIED: {}: {}", report.display(), reason);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn untag_files(path: &Path, args: &Args) {
+    let flac_files: Vec<PathBuf> = if args.recursive && path.is_dir() {
+        collect_album_folders(path).into_iter().flat_map(|(_, files)| files).collect()
+    } else {
+        collect_flac_files(path).1
+    };
+    let (mut stripped, mut failed) = (0, 0);
+    for file in &flac_files {
+        match tagging::strip_tags(file) {
+            Ok(true) => {
+                stripped += 1;
+                if !args.quiet {
+                    progress!("  Untagged {}\n", file.display());
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed to untag {}: {}", file.display(), e);
+            }
+        }
+    }
+    if !args.quiet {
+        progress!("Removed DR tags from {} of {} FLAC file(s)\n", stripped, flac_files.len());
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Report format from --format or the --output extension; text by default
+/// and under --template.
+fn report_format(args: &Args) -> Format {
+    match &args.template {
+        Some(_) => Format::Text,
+        None => args
+            .format
+            .or_else(|| args.output.as_deref().and_then(Format::from_path))
+            .unwrap_or(Format::Text),
+    }
+}
+
+/// Default name of a folder's report: dr_report.<ext>, or foo_dr.txt (the
+/// name foo_dr_meter gives its logs) with --submission.
+fn report_file_name(args: &Args, format: Format) -> String {
+    if args.submission {
+        "foo_dr.txt".to_string()
+    } else {
+        format!("dr_report.{}", report_extension(args, format))
+    }
+}
+
+/// Extension of the default report name.
+fn report_extension(args: &Args, format: Format) -> &str {
+    args.template.as_deref().map_or(format.extension(), template::output_extension)
+}
+
+/// Whether an existing report (`--append`) already lists a FLAC file: by the
+/// "file" field of a CSV or NDJSON report, or in a text report's track tables
+/// by its file name, as the "Added" sections list it, or by the display name
+/// the first table shows.
+fn is_listed(path: &Path, report: &str, format: Format) -> bool {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    match format {
+        Format::Ndjson => report
+            .lines()
+            .filter_map(|line| json::Json::parse(line).ok())
+            .any(|t| t.get("file").and_then(json::Json::string) == Some(&*filename)),
+        Format::Csv => {
+            let mut lines = report.lines();
+            let header = export::csv_split(lines.next().unwrap_or(""));
+            let Some(column) = header.iter().position(|c| c == "file") else {
+                return false;
+            };
+            lines.any(|line| export::csv_split(line).get(column).map(String::as_str) == Some(&*filename))
+        }
+        _ => {
+            let tags = FlacReader::open(path).map(|r| Tags::read(&r)).unwrap_or_default();
+            let names = [filename.to_string(), tags.display_name(&filename)];
+            table_rows(report).any(|line| {
+                names.iter().any(|name| line.contains(&format!("  {}  ", name)) || line.ends_with(&format!("  {}", name)))
+            })
+        }
+    }
+}
+
+/// The files of `flac_files` that `--missing-only` measures: those without a
+/// DYNAMIC_RANGE tag that the --db database doesn't hold either.
+fn missing_files(flac_files: &mut Vec<PathBuf>, scanned: &HashMap<PathBuf, String>) {
+    flac_files.retain(|path| {
+        let tagged = FlacReader::open(path).is_ok_and(|r| r.get_tag(tagging::DR_TAG).next().is_some());
+        !tagged && !database::is_scanned(path, scanned)
+    });
+}
+
+/// Whether --filter-artist, --filter-genre and --filter-year are in use.
+fn filtering(args: &Args) -> bool {
+    args.filter_artist.is_some() || args.filter_genre.is_some() || args.filter_year.is_some()
+}
+
+/// Keeps the files whose tags match every --filter-* option.
+fn filter_files(flac_files: &mut Vec<PathBuf>, args: &Args) {
+    let contains = |values: Vec<&str>, text: &str| {
+        let text = text.to_lowercase();
+        values.iter().any(|v| v.to_lowercase().contains(&text))
+    };
+    flac_files.retain(|path| {
+        let Ok(reader) = FlacReader::open(path) else {
+            // Kept, so the report shows why it can't be read
+            return true;
+        };
+        let artist = args.filter_artist.as_deref().is_none_or(|text| {
+            contains(reader.get_tag("ARTIST").chain(reader.get_tag("ALBUMARTIST")).collect(), text)
+        });
+        let genre = args.filter_genre.as_deref().is_none_or(|text| contains(reader.get_tag("GENRE").collect(), text));
+        let year = args.filter_year.is_none_or(|range| Tags::read(&reader).year.is_some_and(|y| range.contains(y)));
+        artist && genre && year
+    });
+}
+
+/// The database tracks `--missing-only` skips, read once per run.
+fn scanned_tracks(args: &Args) -> HashMap<PathBuf, String> {
+    let Some(db) = args.db.as_ref().filter(|_| args.missing_only) else {
+        return HashMap::new();
+    };
+    database::scanned_tracks(db).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", db.display(), e);
+        std::process::exit(1);
+    })
+}
+
+/// The track rows of a text report's tables: the main one and those added
+/// by earlier `--append` runs.
+fn table_rows(report: &str) -> impl Iterator<Item = &str> {
+    let mut in_table = false;
+    report.lines().filter(move |line| {
+        if line.trim_start().starts_with('─') && line.chars().count() > 40 {
+            in_table = !in_table;
+            return false;
+        }
+        in_table
+    })
+}
+
+/// Appends the tracks of an `--append` run to a text report, with the album
+/// DR over the old and new tracks (the mean of the listed DR values).
+fn append_report(results: &[Outcome], report: &str, output_path: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    let out = append_output(output_path)?;
+    let out: Box<dyn Write> = if opts.ascii { Box::new(AsciiWriter { inner: out, pending: Vec::new() }) } else { out };
+    let mut f = BufWriter::new(out);
+    let s = opts.lang.strings();
+
+    // "DR9", or "DR9.40" with --precision, "~" marking a --quick estimate
+    // and "*" an outlier
+    let mut values: Vec<f64> = table_rows(report)
+        .filter_map(|line| {
+            let dr = line.split_whitespace().next()?.trim_start_matches('~').strip_prefix("DR")?;
+            dr.trim_end_matches('*').parse().ok()
+        })
+        .collect();
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    values.extend(tracks.iter().map(|t| t.dr_exact));
+
+    let estimate = if opts.quick { "~" } else { "" };
+    let dr_width = if opts.precision == 0 { 5 } else { 5 + opts.precision } + opts.quick as usize;
+    writeln!(f)?;
+    writeln!(f, "  Added {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(f)?;
+    writeln!(f, "  {:<w$}  {:<8}  {:<8}  File", "DR", "LUFS", "TP dBTP", w = dr_width)?;
+    writeln!(f, "  ─────────────────────────────────────────────────────────────────────────")?;
+    for t in &tracks {
+        let dr = format!("{}{}", estimate, format_dr(t.dr_exact, opts.precision, opts.dr.algorithm));
+        writeln!(f, "  {:<w$}  {:>8.2}  {:>8.2}  {}", dr, t.lufs, t.true_peak_db, t.filename, w = dr_width)?;
+    }
+    writeln!(f, "  ─────────────────────────────────────────────────────────────────────────")?;
+    for (filename, error) in results.iter().filter_map(|r| r.as_ref().err()) {
+        writeln!(f, "  ✗ {} — {}", filename, error)?;
+    }
+    if !values.is_empty() {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let dr = opts.dr.algorithm.round(mean);
+        writeln!(f)?;
+        writeln!(f, "  {:<15} : {}", s.tracks_analysed, values.len())?;
+        writeln!(
+            f,
+            "  {:<15} : {}{}  ({})",
+            s.album_dr,
+            estimate,
+            format_dr(mean, opts.precision, opts.dr.algorithm),
+            s.mean_of_tracks
+        )?;
+        writeln!(f)?;
+        writeln!(f, "  {} : {} ({})", s.dr_rating, s.rating(dr), rating(dr).0)?;
+    }
+    f.flush()
+}
+
+/// The folder's MusicBrainz release with --musicbrainz; a failed lookup is
+/// reported and leaves it out.
+fn lookup_release(results: &[Outcome], folder: &Path, args: &Args) -> Option<musicbrainz::Release> {
+    if !args.musicbrainz {
+        return None;
+    }
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    musicbrainz::lookup(&tracks).unwrap_or_else(|e| {
+        eprintln!("MusicBrainz lookup failed for {}: {}", folder.display(), e);
+        None
+    })
+}
+
+/// Writes one folder's report in `format`, or through --template;
+/// `existing` is the report being amended with `--append`.
+fn write_folder_report(
+    results: &[Outcome],
+    folder: &Path,
+    format: Format,
+    output_path: &Path,
+    args: &Args,
+    report_opts: &ReportOptions,
+    existing: Option<&str>,
+) -> std::io::Result<()> {
+    if let Some(template) = &args.template {
+        return template::write_template(results, folder, template, output_path, report_opts);
+    }
+    write_format(results, folder, format, output_path, args, report_opts, existing)?;
+    if args.hash || args.sign_key.is_some() {
+        seal::seal(output_path, args.sign_key.as_deref())?;
+    }
+    Ok(())
+}
+
+/// The report itself, without the --hash / --sign-key seal.
+fn write_format(
+    results: &[Outcome],
+    folder: &Path,
+    format: Format,
+    output_path: &Path,
+    args: &Args,
+    report_opts: &ReportOptions,
+    existing: Option<&str>,
+) -> std::io::Result<()> {
+    match format {
+        Format::Text => match existing {
+            Some(report) => append_report(results, report, output_path, report_opts),
+            None => write_report(results, folder, output_path, report_opts),
+        },
+        Format::Json => document::write_json(&document::build(results, folder, report_opts), output_path),
+        Format::Csv => {
+            // Appended rows follow the existing report's columns
+            let header = existing.map(|report| export::csv_split(report.lines().next().unwrap_or("")));
+            let columns = match &header {
+                Some(header) => Some(header.iter().map(String::as_str).collect()),
+                None => args.columns.as_ref().map(|c| c.iter().flat_map(|c| c.document_keys()).copied().collect()),
+            };
+            document::write_csv(&document::build(results, folder, report_opts), output_path, columns, existing.is_some())
+        }
+        Format::Xml => document::write_xml(&document::build(results, folder, report_opts), output_path),
+        Format::Html => html::write_html(results, folder, output_path, report_opts),
+        Format::Md => markdown::write_markdown(results, folder, output_path, report_opts),
+        Format::Yaml => document::write_yaml(&document::build(results, folder, report_opts), output_path),
+        Format::Foobar => foobar::write_foobar_log(results, folder, output_path, report_opts.dr.algorithm, args.submission),
+        Format::Pdf => pdf::write_pdf(results, folder, output_path, report_opts),
+        Format::Xlsx => xlsx::write_xlsx(&[xlsx::Album { folder, results }], output_path, report_opts),
+        Format::Ndjson => document::write_ndjson(results, output_path),
+    }
+}
+
+/// The exports kept per folder: the library database, the sidecar files and
+/// what is written into the FLAC files (tags, embedded results).
+fn export_folder(
+    tracks: &[&TrackResult],
+    folder: &Path,
+    args: &Args,
+    analysis_opts: &AnalysisOptions,
+    report_opts: &ReportOptions,
+) {
+    if let Some(path) = &args.db {
+        let result = database::write_database(tracks, folder, path, analysis_opts.dr.algorithm);
+        finish_export("Database", path, result, args.quiet);
+    }
+    if args.sidecar {
+        finish_export("Sidecars", folder, document::write_sidecars(tracks, folder, report_opts), args.quiet);
+    }
+    if args.tag {
+        finish_export("DR tags", folder, tagging::write_dr_tags(tracks, folder, report_opts), args.quiet);
+    }
+    if args.write_replaygain {
+        finish_export("ReplayGain tags", folder, tagging::write_replaygain_tags(tracks, folder), args.quiet);
+    }
+    if args.embed {
+        finish_export("Embedded results", folder, document::embed_results(tracks, folder, report_opts), args.quiet);
+    }
+    // Last, so that it records the files as the tags above left them
+    if args.cache && cache::applies(analysis_opts) {
+        if let Err(e) = cache::save(tracks, folder, analysis_opts) {
+            eprintln!("Failed to write the analysis cache in {}: {}", folder.display(), e);
+        }
+    }
+}
+
+/// `--recursive`: a report in every folder under the root that holds FLAC
+/// files, then with --combined (or --output) a summary of them all.
+fn scan_recursive(args: &Args, analysis_opts: &AnalysisOptions, report_opts: &ReportOptions) {
+    let root = &args.folder;
+    if !root.is_dir() {
+        eprintln!("Error: '{}' is not a valid directory.", root.display());
+        std::process::exit(1);
+    }
+    let mut albums = collect_album_folders(root);
+    if albums.is_empty() {
+        eprintln!("No FLAC files found under '{}'.", root.display());
+        std::process::exit(0);
+    }
+    if filtering(args) {
+        for (_, flac_files) in &mut albums {
+            filter_files(flac_files, args);
+        }
+        albums.retain(|(_, flac_files)| !flac_files.is_empty());
+        if albums.is_empty() {
+            eprintln!("No FLAC files under '{}' match the --filter options.", root.display());
+            std::process::exit(0);
+        }
+    }
+    if args.missing_only {
+        let scanned = scanned_tracks(args);
+        for (_, flac_files) in &mut albums {
+            missing_files(flac_files, &scanned);
+        }
+        albums.retain(|(_, flac_files)| !flac_files.is_empty());
+        if albums.is_empty() {
+            if !args.quiet {
+                progress!("Nothing to measure: every FLAC file under {} is already measured\n", root.display());
+            }
+            return;
+        }
+    }
+
+    let format = report_format(args);
+    let file_name = report_file_name(args, format);
+    let summary_path = args.output.clone().or_else(|| {
+        let name = if format == Format::Xlsx { "dr_summary.xlsx" } else { "dr_summary.txt" };
+        (args.combined || args.worst.is_some()).then(|| root.join(name))
+    });
+    let to_stdout = summary_path.as_deref() == Some(Path::new("-"));
+    PROGRESS_ON_STDERR.store(to_stdout, Ordering::Relaxed);
+    let quiet = args.quiet;
+
+    if !quiet {
+        let total: usize = albums.iter().map(|(_, files)| files.len()).sum();
+        progress!(
+            "DR Measure — found {} FLAC file(s) in {} folder(s) under {}\n",
+            total,
+            albums.len(),
+            root.display()
+        );
+    }
+
+    let mut all_results = Vec::with_capacity(albums.len());
+    let mut release_years = Vec::with_capacity(albums.len());
+    for (folder, flac_files) in &albums {
+        if !quiet {
+            progress!("\n{}\n", folder.display());
+        }
+        let mut results = analyse_files(flac_files, analysis_opts, args, quiet, &mut |_| {});
+        sort_results(&mut results, args.sort, args.reverse);
+        if args.chart && !quiet {
+            print_dr_chart(&results, args, report_opts);
+        }
+
+        let release = lookup_release(&results, folder, args);
+        release_years.push(release.as_ref().and_then(|r| r.year));
+        let folder_opts = ReportOptions { release, ..report_opts.clone() };
+        let output_path = folder.join(&file_name);
+        match write_folder_report(&results, folder, format, &output_path, args, &folder_opts, None) {
+            Ok(()) => {
+                if !quiet {
+                    progress!("  Report written → {}\n", output_path.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to write report: {}", e);
+                std::process::exit(1);
+            }
+        }
+        let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        export_folder(&tracks, folder, args, analysis_opts, report_opts);
+        all_results.push(results);
+    }
+
+    if let Some(summary_path) = summary_path {
+        let written = if format == Format::Xlsx {
+            let sheets: Vec<xlsx::Album> =
+                albums.iter().zip(&all_results).map(|((folder, _), results)| xlsx::Album { folder, results }).collect();
+            xlsx::write_xlsx(&sheets, &summary_path, report_opts)
+        } else {
+            let folders: Vec<&Path> = albums.iter().map(|(folder, _)| folder.as_path()).collect();
+            write_summary(&folders, &all_results, &release_years, root, &summary_path, report_opts)
+        };
+        match written {
+            Ok(()) => {
+                if !quiet && !to_stdout {
+                    progress!("\n  Summary written → {}\n", summary_path.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to write summary: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.verify {
+        exit_if_corrupt(all_results.iter().flatten(), quiet);
+    }
+}
+
+fn main() {
+    let mut args = Args::parse();
+    if args.stdout {
+        args.output = Some(PathBuf::from("-"));
+    }
+    ASCII_CONSOLE.store(args.ascii, Ordering::Relaxed);
+    flacmeta::PRESERVE_MTIME.store(args.preserve_mtime, Ordering::Relaxed);
+    // The DR database takes foobar2000 logs with DR14 T.T. Meter values
+    if args.submission {
+        args.format = Some(Format::Foobar);
+        args.algorithm = Algorithm::Dr14tt;
+    }
+
+    if let (Some(start), Some(end)) = (args.start, args.end) {
+        if end <= start {
+            eprintln!("Error: --end must be after --start.");
+            std::process::exit(1);
+        }
+    }
+
+    let analysis_opts = analysis_options(&args);
+    let report_opts = report_options(&args, analysis_opts.dr);
+
+    match &args.command {
+        Some(Command::Compare { a, b }) => {
+            compare_folders([a, b], &args, &analysis_opts, &report_opts);
+            return;
+        }
+        Some(Command::Merge { reports }) => {
+            merge_reports(reports, &args, &report_opts);
+            return;
+        }
+        Some(Command::Diff { old, new }) => {
+            diff_reports([old, new], &args);
+            return;
+        }
+        Some(Command::Untag { folder }) => {
+            untag_files(folder, &args);
+            return;
+        }
+        Some(Command::Schema) => {
+            print!("{}", document::SCHEMA);
+            return;
+        }
+        Some(Command::Verify { report, pubkey }) => {
+            verify_report(report, pubkey.as_deref());
+            return;
+        }
+        None => {}
+    }
+
+    if args.hash || args.sign_key.is_some() {
+        if !matches!(report_format(&args), Format::Text | Format::Foobar) {
+            eprintln!("Error: --hash and --sign-key work with text reports and foobar2000 logs");
+            std::process::exit(1);
+        }
+        if args.output.as_deref() == Some(Path::new("-")) {
+            eprintln!("Error: --hash and --sign-key need a report file");
+            std::process::exit(1);
+        }
+    }
+
+    if args.recursive {
+        scan_recursive(&args, &analysis_opts, &report_opts);
+        return;
+    }
+
+    let (folder, mut flac_files) = collect_flac_files(&args.folder);
+    let folder = &folder;
+
+    let format = report_format(&args);
+    // NDJSON streams to stdout unless --output is given
+    let output_path = match &args.output {
+        Some(path) => path.clone(),
+        None if format == Format::Ndjson => PathBuf::from("-"),
+        None => folder.join(report_file_name(&args, format)),
+    };
+    // Keep stdout for the report alone
+    let to_stdout = output_path == Path::new("-");
+    PROGRESS_ON_STDERR.store(to_stdout, Ordering::Relaxed);
+    let quiet = args.quiet;
+
+    // --append: analyse only the files the existing report doesn't list
+    if args.append && !matches!(format, Format::Text | Format::Csv | Format::Ndjson) {
+        eprintln!("Error: --append works with text, CSV and NDJSON reports");
+        std::process::exit(1);
+    }
+    if args.append && to_stdout {
+        eprintln!("Error: --append needs a report file; pass --output");
+        std::process::exit(1);
+    }
+    let existing = if args.append && output_path.is_file() {
+        match fs::read_to_string(&output_path) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", output_path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    if let Some(report) = &existing {
+        flac_files.retain(|path| !is_listed(path, report, format));
+        if flac_files.is_empty() {
+            if !quiet {
+                progress!("Nothing to add: {} already lists every FLAC file in {}\n", output_path.display(), folder.display());
+            }
+            return;
+        }
+    }
+
+    if filtering(&args) {
+        filter_files(&mut flac_files, &args);
+        if flac_files.is_empty() {
+            eprintln!("No FLAC files in '{}' match the --filter options.", folder.display());
+            std::process::exit(0);
+        }
+    }
+    if args.missing_only {
+        missing_files(&mut flac_files, &scanned_tracks(&args));
+        if flac_files.is_empty() {
+            if !quiet {
+                progress!("Nothing to measure: every FLAC file in {} is already measured\n", folder.display());
+            }
+            return;
+        }
+    }
+
+    if !quiet {
+        match existing {
+            Some(_) => progress!("DR Measure — {} new FLAC file(s) in {}\n\n", flac_files.len(), folder.display()),
+            None => progress!("DR Measure — found {} FLAC file(s) in {}\n\n", flac_files.len(), folder.display()),
+        }
+    }
+
+    let open = if existing.is_some() { append_output } else { create_output };
+    let mut stream: Option<Box<dyn Write>> = match format {
+        Format::Ndjson => match open(&output_path) {
+            Ok(out) => Some(out),
+            Err(e) => {
+                eprintln!("Failed to write report: {}", e);
+                std::process::exit(1);
+            }
+        },
+        _ => None,
+    };
+    let mut results = analyse_files(&flac_files, &analysis_opts, &args, quiet, &mut |result| {
+        if let Some(out) = stream.as_mut() {
+            let line = document::ndjson_line(result);
+            if let Err(e) = writeln!(out, "{}", line).and_then(|_| out.flush()) {
+                eprintln!("Failed to write report: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+
+    sort_results(&mut results, args.sort, args.reverse);
+    if args.chart && !quiet {
+        print_dr_chart(&results, &args, &report_opts);
+    }
+
+    let written = match format {
+        // Already streamed
+        Format::Ndjson => Ok(()),
+        _ => {
+            let report_opts = ReportOptions { release: lookup_release(&results, folder, &args), ..report_opts.clone() };
+            write_folder_report(&results, folder, format, &output_path, &args, &report_opts, existing.as_deref())
+        }
+    };
+    match written {
+        Ok(()) => {
+            if !quiet && !to_stdout {
+                progress!("\n  Report written → {}\n", output_path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to write report: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+
+    export_folder(&tracks, folder, &args, &analysis_opts, &report_opts);
+    if let Some(path) = &args.timeline {
+        finish_export("Loudness timeline", path, export::write_loudness_timeline(&tracks, path), quiet);
+    }
+    if let Some(path) = &args.dr_timeline {
+        let dr = analysis_opts.dr;
+        let window = ((args.dr_window / dr.block_seconds).round() as usize).max(dr.nth_peak);
+        let result = export::write_dr_timeline(&tracks, path, window, dr);
+        finish_export("DR timeline", path, result, quiet);
+    }
+    if let Some(path) = &args.dump_blocks {
+        let result = export::write_block_dump(&tracks, path);
+        finish_export("Block dump", path, result, quiet);
+    }
+    if let Some(dir) = &args.export_blocks {
+        let result = export::write_block_csvs(&tracks, dir);
+        finish_export("Block CSVs", dir, result, quiet);
+    }
+    if let Some(path) = &args.psr_timeline {
+        finish_export("PSR timeline", path, export::write_psr_timeline(&tracks, path), quiet);
+    }
+    if let Some(path) = &args.clip_log {
+        finish_export("Clip log", path, export::write_clip_log(&tracks, path, WORST_CLIPS), quiet);
+    }
+    if let Some(dir) = &args.labels {
+        finish_export("Labels", dir, export::write_labels(&tracks, dir), quiet);
+    }
+    if let Some(dir) = &args.waveforms {
+        finish_export("Waveforms", dir, export::write_waveforms(&tracks, dir), quiet);
+    }
+    if let Some(dir) = &args.spectrograms {
+        finish_export("Spectrograms", dir, export::write_spectrograms(&tracks, dir), quiet);
+    }
+    if args.verify {
+        exit_if_corrupt(&results, quiet);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit FLAC file in the temporary folder, written with verbatim
+    /// subframes, removed when dropped.
+    pub(crate) struct TestFlac(pub(crate) PathBuf);
+
+    impl TestFlac {
+        const BLOCK: usize = 4096;
+
+        /// `channels` holds the samples of each channel.
+        pub(crate) fn new(name: &str, sample_rate: u32, channels: &[Vec<i16>]) -> TestFlac {
+            fn crc8(data: &[u8]) -> u8 {
+                data.iter().fold(0u8, |crc, &b| {
+                    (0..8).fold(crc ^ b, |crc, _| if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 })
+                })
+            }
+            fn crc16(data: &[u8]) -> u16 {
+                data.iter().fold(0u16, |crc, &b| {
+                    (0..8).fold(crc ^ (b as u16) << 8, |crc, _| {
+                        if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 }
+                    })
+                })
+            }
+            let frames = channels[0].len();
+            let mut bytes = b"fLaC".to_vec();
+            // STREAMINFO, the last metadata block; no MD5
+            bytes.extend_from_slice(&[0x80, 0, 0, 34]);
+            bytes.extend_from_slice(&(Self::BLOCK as u16).to_be_bytes());
+            bytes.extend_from_slice(&(Self::BLOCK as u16).to_be_bytes());
+            bytes.extend_from_slice(&[0; 6]);
+            let packed = (sample_rate as u64) << 44 | ((channels.len() as u64 - 1) << 41) | (15 << 36) | frames as u64;
+            bytes.extend_from_slice(&packed.to_be_bytes());
+            bytes.extend_from_slice(&[0; 16]);
+            for (n, first) in (0..frames).step_by(Self::BLOCK).enumerate() {
+                let len = Self::BLOCK.min(frames - first);
+                // Fixed block size, block length at the end of the header,
+                // STREAMINFO rate, independent channels, 16 bits
+                let mut frame = vec![0xff, 0xf8, 0x70, ((channels.len() as u8 - 1) << 4) | 0x08];
+                // Frame number, UTF-8 coded
+                match n {
+                    0..=0x7f => frame.push(n as u8),
+                    0x80..=0x7ff => frame.extend_from_slice(&[0xc0 | (n >> 6) as u8, 0x80 | (n & 0x3f) as u8]),
+                    _ => frame.extend_from_slice(&[
+                        0xe0 | (n >> 12) as u8,
+                        0x80 | ((n >> 6) & 0x3f) as u8,
+                        0x80 | (n & 0x3f) as u8,
+                    ]),
+                }
+                frame.extend_from_slice(&(len as u16 - 1).to_be_bytes());
+                frame.push(crc8(&frame));
+                for samples in channels {
+                    frame.push(0x02);
+                    for &x in &samples[first..first + len] {
+                        frame.extend_from_slice(&x.to_be_bytes());
+                    }
+                }
+                frame.extend_from_slice(&crc16(&frame).to_be_bytes());
+                bytes.extend(frame);
+            }
+            let path = std::env::temp_dir().join(format!("dr-measure-{}-{}.flac", name, std::process::id()));
+            std::fs::write(&path, bytes).unwrap();
+            TestFlac(path)
+        }
+
+        pub(crate) fn analyse(&self, opts: &AnalysisOptions) -> TrackResult {
+            process_flac(&self.0, opts, &AtomicU64::new(0)).unwrap()
+        }
+    }
+
+    impl Drop for TestFlac {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// A 440 Hz tone at 44.1 kHz with a one-sample peak eight times its level
+    /// in the middle of every second: DR18.
+    pub(crate) fn peaky_tone(seconds: usize) -> Vec<i16> {
+        (0..44100 * seconds)
+            .map(|i| match i % 44100 {
+                22050 => 24000,
+                _ => (3000.0 * (i as f64 * 440.0 * std::f64::consts::TAU / 44100.0).sin()) as i16,
+            })
+            .collect()
+    }
+
+    /// Reproducible samples in -1..1.
+    fn noise(n: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lane_sums_agree_with_the_scalar_lanes() {
+        let samples = noise(LANES * 1000);
+        for len in [0, LANES, LANES * 3, LANES * 1000] {
+            let (mut sum_sq, mut peak) = ([0.5, 0.0, 1.0, 2.0], [0.25, 0.0, 0.75, 0.0]);
+            let (mut scalar_sum_sq, mut scalar_peak) = (sum_sq, peak);
+            lane_sums(&mut sum_sq, &mut peak, &samples[..len]);
+            lane_sums_scalar(&mut scalar_sum_sq, &mut scalar_peak, &samples[..len]);
+            assert_eq!(sum_sq.map(f64::to_bits), scalar_sum_sq.map(f64::to_bits), "sums over {} samples", len);
+            assert_eq!(peak.map(f64::to_bits), scalar_peak.map(f64::to_bits), "peaks over {} samples", len);
+        }
+    }
+
+    #[test]
+    fn block_stats_do_not_depend_on_how_the_frames_arrive() {
+        let left = noise(10_007);
+        let right: Vec<f64> = left.iter().map(|x| -0.5 * x).collect();
+        let block_len = 1001;
+        let split = |gap_every: usize| {
+            let mut splitter = BlockSplitter::new(2, block_len);
+            // A gap in the file positions flushes the staged frames early
+            let mut at = 0;
+            for (i, (&l, &r)) in left.iter().zip(&right).enumerate() {
+                if gap_every > 0 && i % gap_every == 0 {
+                    at += 5;
+                }
+                splitter.push(&[l, r], at);
+                at += 1;
+            }
+            splitter.finish(Algorithm::Drmeter).0
+        };
+        let whole = split(0);
+        assert_eq!(whole[0].len(), 10);
+        for gap_every in [7, 1000, 4097] {
+            let gapped = split(gap_every);
+            for (a, b) in whole.iter().flatten().zip(gapped.iter().flatten()) {
+                assert_eq!((a.rms.to_bits(), a.peak.to_bits()), (b.rms.to_bits(), b.peak.to_bits()));
+            }
+        }
+        for (i, block) in whole[0].iter().enumerate() {
+            let samples = &left[i * block_len..((i + 1) * block_len).min(left.len())];
+            let rms = (2.0 * samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64).sqrt();
+            let peak = samples.iter().fold(0.0f64, |a, x| a.max(x.abs()));
+            assert!((block.rms - rms).abs() < 1e-12, "block {} RMS", i);
+            assert_eq!(block.peak, peak, "block {} peak", i);
+            assert_eq!(block.start, (i * block_len) as u64);
+        }
+    }
+
+    #[test]
+    fn renders_the_text_report_in_german() {
+        let tone = peaky_tone(10);
+        let file = TestFlac::new("german", 44100, &[tone.clone(), tone]);
+        let dr = DrParams { algorithm: Algorithm::Dr14tt, block_seconds: 0.5, ..DrParams::default() };
+        let analysis = AnalysisOptions {
+            dr,
+            quick: true,
+            end: Some(8.0),
+            silence_gate: Some(-70.0),
+            weighting: Some(Weighting::K),
+            ..AnalysisOptions::default()
+        };
+        let track = file.analyse(&analysis);
+        let opts = ReportOptions {
+            dr,
+            quick: true,
+            end: Some(8.0),
+            silence_gate: Some(-70.0),
+            columns: vec![Column::Dr, Column::DrK, Column::Peak, Column::File],
+            lang: Lang::De,
+            ..ReportOptions::default()
+        };
+        let output = file.0.with_extension("txt");
+        write_report(&[Ok(track)], &std::env::temp_dir(), &output, &opts).unwrap();
+        let report = std::fs::read_to_string(&output).unwrap();
+        let _ = std::fs::remove_file(&output);
+        for line in [
+            "  Dynamikumfang-Bericht",
+            "  Verfahren : DR14 T.T. Meter compatible",
+            "  Parameter : NICHT STANDARD — 0.5 s Blöcke, obere 20% RMS, Spitze #2 (Norm: 3 s, 20%, #2)",
+            "  Modus     : Schnellschätzung (1 Block von 4), mit ~ markierte Werte sind Näherungen",
+            "  Bereich   : 00:00 – 00:08",
+            "  Gate      : DR-Blöcke unter -70.0 dBFS RMS ausgenommen",
+            "  Titel gemessen  : 1",
+        ] {
+            assert!(report.lines().any(|l| l == line), "no {:?} in\n{}", line, report);
+        }
+        assert!(report.contains("  DR K-gewichtet  : ~"), "{}", report);
+        for english in ["Algorithm", "Params", "Mode ", "Range", "K-weighted", "Summary", "Tracks analysed", "mean of tracks"] {
+            assert!(!report.contains(english), "{:?} in\n{}", english, report);
+        }
+    }
+
+    #[test]
+    fn events_keep_their_file_positions_under_quick() {
+        // Flat tops at 1.2 s and 13.5 s, both in blocks that --quick analyses
+        // whether it starts at 0 s or 1 s
+        let mut tone: Vec<i16> = peaky_tone(20).iter().map(|x| x / 2).collect();
+        let clips = [52_920, 595_350];
+        for &at in &clips {
+            tone[at..at + 10].fill(i16::MAX);
+        }
+        let file = TestFlac::new("quick-events", 44100, &[tone.clone(), tone]);
+        for start in [None, Some(1.0)] {
+            let track = file.analyse(&AnalysisOptions { quick: true, start, ..AnalysisOptions::default() });
+            let starts: Vec<u64> = track.clipping.regions.iter().map(|r| r.start).collect();
+            assert_eq!(starts, clips.map(|at| at as u64), "--start {:?}", start);
+        }
+    }
+
+    fn params(algorithm: Algorithm) -> DrParams {
+        DrParams { algorithm, ..DrParams::default() }
+    }
+
+    #[test]
+    fn algorithms_round_halves_their_own_way() {
+        let cases = [
+            // value, drmeter (half away from zero), dr14tt (half to even), pmf-official (half up)
+            (12.5, 13, 12, 13),
+            (13.5, 14, 14, 14),
+            (12.49, 12, 12, 12),
+            (-2.5, -3, -2, -2),
+        ];
+        for (value, drmeter, dr14tt, pmf) in cases {
+            assert_eq!(Algorithm::Drmeter.round(value), drmeter, "drmeter {}", value);
+            assert_eq!(Algorithm::Dr14tt.round(value), dr14tt, "dr14tt {}", value);
+            assert_eq!(Algorithm::PmfOfficial.round(value), pmf, "pmf-official {}", value);
+        }
+    }
+
+    #[test]
+    fn algorithms_average_their_own_number_of_loudest_blocks() {
+        // Quiet blocks at 0.1 RMS, then the two loudest at 0.25 and 0.5. The
+        // highest peak (1.0) is skipped for the second highest, 0.8.
+        let blocks = |total: usize| -> Vec<BlockStats> {
+            let mut blocks: Vec<BlockStats> =
+                (0..total - 2).map(|i| BlockStats { rms: 0.1, peak: 0.8, start: i as u64 }).collect();
+            blocks.push(BlockStats { rms: 0.25, peak: 1.0, start: 0 });
+            blocks.push(BlockStats { rms: 0.5, peak: 0.8, start: 0 });
+            blocks
+        };
+        // The loudest block alone, and the two loudest
+        let top_1 = 20.0 * (0.8f64 / 0.5).log10();
+        let top_2 = 20.0 * (0.8 / ((0.5f64 * 0.5 + 0.25 * 0.25) / 2.0).sqrt()).log10();
+        let cases = [
+            // 20% of 7 blocks is 1.4: rounded 1, floored 1, ceiled 2
+            (7, Algorithm::Drmeter, top_1),
+            (7, Algorithm::Dr14tt, top_1),
+            (7, Algorithm::PmfOfficial, top_2),
+            // 20% of 8 blocks is 1.6: rounded 2, floored 1, ceiled 2
+            (8, Algorithm::Drmeter, top_2),
+            (8, Algorithm::Dr14tt, top_1),
+            (8, Algorithm::PmfOfficial, top_2),
+        ];
+        for (total, algorithm, dr) in cases {
+            let measured = dr_for_channel(&blocks(total), params(algorithm));
+            assert!((measured - dr).abs() < 1e-9, "{:?} over {} blocks: {} for {}", algorithm, total, measured, dr);
+        }
+    }
+
+    #[test]
+    fn dr14tt_needs_three_blocks() {
+        let blocks = vec![BlockStats { rms: 0.1, peak: 0.5, start: 0 }; 2];
+        assert_eq!(dr_for_channel(&blocks, params(Algorithm::Dr14tt)), 0.0);
+        assert!(dr_for_channel(&blocks, params(Algorithm::Drmeter)) > 0.0);
+        assert!(dr_for_channel(&blocks, params(Algorithm::PmfOfficial)) > 0.0);
+    }
+
+    #[test]
+    fn algorithms_close_the_last_block_their_own_way() {
+        // Blocks of 4 full-scale frames: the RMS of each block kept (√2 for
+        // a full or partial one, 0 for an empty one) and whether an empty
+        // tail was added
+        let split = |frames: u64, algorithm: Algorithm| {
+            let mut splitter = BlockSplitter::new(1, 4);
+            for at in 0..frames {
+                splitter.push(&[1.0], at);
+            }
+            let (blocks, empty_tail) = splitter.finish(algorithm);
+            let rms: Vec<f64> = blocks[0].iter().map(|b| b.rms).collect();
+            (rms, empty_tail)
+        };
+        let full = 2f64.sqrt();
+        // A partial tail of 2 frames
+        assert_eq!(split(10, Algorithm::Drmeter), (vec![full, full, full], false));
+        assert_eq!(split(10, Algorithm::Dr14tt), (vec![full, full, full], false));
+        assert_eq!(split(10, Algorithm::PmfOfficial), (vec![full, full], false));
+        // No partial tail: DR14 T.T. still closes with an empty block
+        assert_eq!(split(8, Algorithm::Drmeter), (vec![full, full], false));
+        assert_eq!(split(8, Algorithm::Dr14tt), (vec![full, full, 0.0], true));
+        assert_eq!(split(8, Algorithm::PmfOfficial), (vec![full, full], false));
+        // A file shorter than a block keeps its only, partial, block
+        assert_eq!(split(3, Algorithm::PmfOfficial), (vec![full], false));
+    }
+}
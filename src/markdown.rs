@@ -0,0 +1,104 @@
+// ─── Markdown report ──────────────────────────────────────────────────────────
+//
+// GitHub-flavored Markdown: a heading, the track table and the album summary,
+// short enough to paste into a forum post, wiki page or pull request.
+
+use crate::{
+    album_dr_values, album_lufs, create_output, format_dr, format_duration, rating, AlbumMethod, ReportOptions, TrackResult,
+};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Escapes the characters that would end a table cell or start emphasis,
+/// code or links.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn write_markdown(
+    results: &[Result<TrackResult, (String, String)>],
+    folder: &Path,
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    let dr = |value: f64| format_dr(value, opts.precision, opts.dr.algorithm);
+    let title = folder
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| folder.display().to_string());
+
+    let mut f = BufWriter::new(create_output(output_path)?);
+    writeln!(f, "## Dynamic Range Report — {}", escape(&title))?;
+    writeln!(f)?;
+    if let Some(label) = opts.dr.algorithm.label() {
+        writeln!(f, "_Algorithm: {}_", label)?;
+        writeln!(f)?;
+    }
+
+    writeln!(f, "| # | File | DR | Grade | Peak SP | Peak TP | RMS dB | LUFS | Duration | Info |")?;
+    writeln!(f, "|--:|:-----|---:|:-----:|--------:|--------:|-------:|-----:|---------:|:-----|")?;
+    for (i, t) in tracks.iter().enumerate() {
+        writeln!(
+            f,
+            "| {} | {} | **{}** | {} | {:+.2} | {:+.2} | {:+.2} | {:+.1} | {} | {}/{}/{} |",
+            i + 1,
+            escape(&t.display_name()),
+            dr(t.dr_exact),
+            rating(t.dr).0,
+            t.peak_db,
+            t.true_peak_db,
+            t.rms_db,
+            t.lufs,
+            format_duration(t.duration_secs),
+            t.sample_rate / 1000,
+            t.bit_depth,
+            t.channels
+        )?;
+    }
+    writeln!(f)?;
+
+    if !tracks.is_empty() {
+        let (dr_mean, dr_pooled) = album_dr_values(&tracks, opts);
+        let (album_value, method) = match opts.album_method {
+            AlbumMethod::Pooled => (dr_pooled, "pooled blocks"),
+            AlbumMethod::Mean => (dr_mean, "mean of tracks"),
+        };
+        let dr_album = opts.dr.algorithm.round(album_value);
+        let (grade, label) = (rating(dr_album).0, opts.lang.strings().rating(dr_album));
+        let lufs = album_lufs(&tracks);
+        let true_peak = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+
+        writeln!(f, "- **Album DR:** {} ({})", dr(album_value), method)?;
+        writeln!(f, "- **Rating:** {} — {}", grade, label)?;
+        writeln!(f, "- **Album loudness:** {:.1} LUFS, true peak {:+.1} dBTP", lufs, true_peak)?;
+        writeln!(f, "- **Tracks:** {}", tracks.len())?;
+        writeln!(f)?;
+    }
+
+    let errors: Vec<&(String, String)> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    if !errors.is_empty() {
+        writeln!(f, "**Errors**")?;
+        writeln!(f)?;
+        for (name, err) in errors {
+            writeln!(f, "- {} — {}", escape(name), escape(err))?;
+        }
+        writeln!(f)?;
+    }
+
+    writeln!(
+        f,
+        "<sub>{} {} · [DR Loudness Standard](https://www.dynamicrange.de)</sub>",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    f.flush()
+}
@@ -0,0 +1,355 @@
+// ─── Library report from existing reports ─────────────────────────────────────
+//
+// `merge REPORT…` lists the albums of many per-album reports written earlier
+// in one library-wide report, without decoding any audio again. It reads the
+// text report, the foobar2000-style log and the JSON document; a folder
+// stands for every report found below it. When a folder holds several
+// reports of the same album, the JSON one is preferred, then the text report,
+// then the log, then a --submission log.
+
+use crate::json::Json;
+use crate::lang::{Lang, Strings};
+use crate::{create_text_output, rating, ReportOptions};
+use chrono::Local;
+use clap::ValueEnum;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Report names looked for in folders, most informative first.
+const REPORT_NAMES: [&str; 4] = ["dr_report.json", "dr_report.txt", "dr_report.log", "foo_dr.txt"];
+
+/// One album as recorded by its report.
+pub struct Album {
+    pub name: String,
+    pub source: PathBuf,
+    pub tracks: Option<usize>,
+    /// Album DR as the report printed it (with decimals under --precision).
+    pub dr: f64,
+    pub dr_text: String,
+    pub lufs: Option<f64>,
+    pub year: Option<u32>,
+}
+
+/// The reports named on the command line, with folders expanded to the
+/// reports found anywhere below them.
+pub fn find_reports(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            found.push(path.clone());
+            continue;
+        }
+        let mut pending = vec![path.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else { continue };
+            for entry in entries.filter_map(|e| e.ok()) {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    pending.push(entry.path());
+                }
+            }
+            if let Some(report) = REPORT_NAMES.iter().map(|name| dir.join(name)).find(|p| p.is_file()) {
+                found.push(report);
+            }
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+pub fn read_report(path: &Path) -> Result<Album, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let album = if text.trim_start().starts_with('{') {
+        read_json(&text)
+    } else if text.lines().any(|l| l.starts_with("Official DR value:")) {
+        read_foobar(&text)
+    } else {
+        read_text(&text)
+    }?;
+    Ok(Album { source: path.to_path_buf(), ..album })
+}
+
+/// "DR12" or "~DR12.34" as printed in the reports.
+fn parse_dr(text: &str) -> Option<(f64, String)> {
+    let text = text.trim().trim_start_matches('~');
+    let value = text.strip_prefix("DR")?.trim_end_matches('*').parse().ok()?;
+    Some((value, text.trim_end_matches('*').to_string()))
+}
+
+/// "Artist — Album" and the year from "Artist — Album (1979)".
+fn split_year(album: &str) -> (String, Option<u32>) {
+    let parsed = album.strip_suffix(')').and_then(|rest| {
+        let (name, year) = rest.rsplit_once(" (")?;
+        Some((name.to_string(), year.parse().ok().filter(|_| year.len() == 4)?))
+    });
+    match parsed {
+        Some((name, year)) => (name, Some(year)),
+        None => (album.to_string(), None),
+    }
+}
+
+/// The last path component of a folder as written in a report.
+fn folder_name(folder: &str) -> String {
+    let folder = folder.trim().trim_end_matches(['/', '\\']);
+    folder.rsplit(['/', '\\']).next().unwrap_or(folder).to_string()
+}
+
+/// Whether `key` is the label `label` picks out of the --lang tables, so that
+/// reports in every language are read.
+fn is_label(key: &str, label: fn(&Strings) -> &'static str) -> bool {
+    Lang::value_variants().iter().any(|lang| label(lang.strings()) == key)
+}
+
+fn read_text<'a>(text: &'a str) -> Result<Album, String> {
+    let value = |line: &'a str, label: fn(&Strings) -> &'static str| {
+        let (key, value) = line.trim().split_once(':')?;
+        is_label(key.trim(), label).then(|| value.trim())
+    };
+    let field = |label| text.lines().find_map(|line| value(line, label));
+    // The album DR and track count of the last --append section, if any
+    let last_field = |label| text.lines().rev().find_map(|line| value(line, label));
+    let (dr, dr_text) = last_field(|s| s.album_dr)
+        .and_then(|v| parse_dr(v.split_whitespace().next()?))
+        .ok_or("no album DR (not a dr-measure text report?)")?;
+    // "Artist — Album (1979)", with "-" for the dash in an --ascii report
+    let (name, year) = match field(|s| s.album) {
+        Some(album) => split_year(album),
+        None => (field(|s| s.folder).map(folder_name).unwrap_or_default(), None),
+    };
+    // Else the year of the --musicbrainz release: "Title — Label (GB, 1979)"
+    let release_year = || {
+        let origin = field(|s| s.release)?.strip_suffix(')')?.rsplit_once('(')?.1;
+        let year = origin.rsplit(", ").next()?;
+        year.parse().ok().filter(|_| year.len() == 4)
+    };
+    let year = year.or_else(release_year);
+    Ok(Album {
+        name,
+        source: PathBuf::new(),
+        tracks: last_field(|s| s.tracks_analysed).and_then(|v| v.parse().ok()),
+        dr,
+        dr_text,
+        lufs: field(|s| s.album_loudness).and_then(|v| v.split_whitespace().next()?.parse().ok()),
+        year,
+    })
+}
+
+fn read_foobar(text: &str) -> Result<Album, String> {
+    let field = |label: &str| text.lines().find_map(|line| line.strip_prefix(label).map(str::trim));
+    let (dr, dr_text) = field("Official DR value:").and_then(parse_dr).ok_or("unreadable official DR value")?;
+    // "Artist / Album" from tagged files, else the folder
+    let name = field("Analyzed:").map(|v| v.replacen(" / ", " — ", 1)).unwrap_or_default();
+    Ok(Album {
+        name,
+        source: PathBuf::new(),
+        tracks: field("Number of tracks:").and_then(|v| v.parse().ok()),
+        dr,
+        dr_text,
+        lufs: None,
+        year: None,
+    })
+}
+
+fn read_json(text: &str) -> Result<Album, String> {
+    let document = Json::parse(text)?;
+    let album = document.get("album").filter(|a| !matches!(a, Json::Null)).ok_or("no album (no track analysed?)")?;
+    let dr = album.get("dr").and_then(Json::number).ok_or("no album DR (not a dr-measure JSON report?)")?;
+
+    // The album tag shared by the tracks, as in the text report header
+    let tracks = match document.get("tracks") {
+        Some(Json::Array(tracks)) => tracks.as_slice(),
+        _ => &[],
+    };
+    let common = |key: &str| {
+        let values: Vec<Option<&str>> =
+            tracks.iter().map(|t| t.get("tags").and_then(|tags| tags.get(key)).and_then(Json::string)).collect();
+        let first = (*values.first()?)?;
+        values.iter().all(|v| *v == Some(first)).then_some(first)
+    };
+    let name = match common("album") {
+        Some(title) => {
+            let artist = common("album_artist").or_else(|| common("artist")).unwrap_or("Various Artists");
+            format!("{} — {}", artist, title)
+        }
+        None => document
+            .get("meta")
+            .and_then(|m| m.get("folder"))
+            .and_then(Json::string)
+            .map(folder_name)
+            .unwrap_or_default(),
+    };
+    Ok(Album {
+        name,
+        source: PathBuf::new(),
+        tracks: album.get("tracks").and_then(Json::number).map(|n| n as usize),
+        dr,
+        dr_text: format!("DR{}", dr),
+        lufs: album.get("lufs").and_then(Json::number),
+        year: tracks
+            .iter()
+            .filter_map(|t| t.path("tags.year").and_then(Json::number))
+            .min_by(f64::total_cmp)
+            .or_else(|| document.path("meta.release.year").and_then(Json::number))
+            .map(|y| y as u32),
+    })
+}
+
+/// Width of the longest histogram bar.
+const BAR_WIDTH: usize = 40;
+
+/// What the library statistics need of an album.
+pub struct AlbumStats {
+    pub dr: f64,
+    /// Integrated loudness (LUFS), when the report has it.
+    pub lufs: Option<f64>,
+    pub year: Option<u32>,
+}
+
+/// Album count, mean DR and mean loudness ("–" without any) of `albums`.
+fn period(albums: &[&AlbumStats]) -> (usize, f64, String) {
+    let n = albums.len();
+    let mean = albums.iter().map(|a| a.dr).sum::<f64>() / n as f64;
+    let lufs: Vec<f64> = albums.iter().filter_map(|a| a.lufs).collect();
+    let lufs = if lufs.is_empty() {
+        "–".to_string()
+    } else {
+        format!("{:+.1}", lufs.iter().sum::<f64>() / lufs.len() as f64)
+    };
+    (n, mean, lufs)
+}
+
+/// The statistics of a library report, from each album's DR, loudness and
+/// release year: mean and median album DR, the DR histogram, the albums per
+/// rating, and the mean album DR and loudness per year and per decade (the
+/// loudness war timeline).
+pub fn write_statistics(f: &mut dyn Write, albums: &[AlbumStats]) -> std::io::Result<()> {
+    if albums.is_empty() {
+        return Ok(());
+    }
+    let mut drs: Vec<f64> = albums.iter().map(|a| a.dr).collect();
+    drs.sort_by(f64::total_cmp);
+    let n = drs.len();
+    let mean = drs.iter().sum::<f64>() / n as f64;
+    let median = if n % 2 == 1 { drs[n / 2] } else { (drs[n / 2 - 1] + drs[n / 2]) / 2.0 };
+    writeln!(f, "  Statistics")?;
+    writeln!(f, "  ───────────────────────────────")?;
+    writeln!(f, "  Mean album DR   : DR{:.1}", mean)?;
+    writeln!(f, "  Median album DR : DR{:.1}", median)?;
+    writeln!(f)?;
+
+    let rounded: Vec<i32> = drs.iter().map(|dr| dr.round() as i32).collect();
+    let counts: Vec<(i32, usize)> =
+        (rounded[0]..=rounded[n - 1]).map(|dr| (dr, rounded.iter().filter(|&&r| r == dr).count())).collect();
+    let most = counts.iter().map(|&(_, count)| count).max().unwrap_or(1);
+    writeln!(f, "  DR distribution (albums)")?;
+    for (dr, count) in counts {
+        let bar = "█".repeat((count * BAR_WIDTH).div_ceil(most));
+        writeln!(f, "{}", format!("  DR{:<3} {:>4}  {}", dr, count, bar).trim_end())?;
+    }
+    writeln!(f)?;
+
+    writeln!(f, "  Albums per rating")?;
+    // One DR value inside each band of the rating scale
+    for dr in [14, 10, 8, 6, 0] {
+        let (grade, label) = rating(dr);
+        let count = rounded.iter().filter(|&&r| rating(r).0 == grade).count();
+        writeln!(f, "  {}  {:<32} {:>4}  ({:.0}%)", grade, label, count, 100.0 * count as f64 / n as f64)?;
+    }
+    writeln!(f)?;
+
+    let mut years: Vec<u32> = albums.iter().filter_map(|a| a.year).collect();
+    years.sort_unstable();
+    years.dedup();
+    if !years.is_empty() {
+        writeln!(f, "  {:<29}{:>6}", "DR by release year", "LUFS")?;
+        for &year in &years {
+            let of_year: Vec<&AlbumStats> = albums.iter().filter(|a| a.year == Some(year)).collect();
+            let (n, mean, lufs) = period(&of_year);
+            let bar = "█".repeat(mean.round().max(0.0) as usize);
+            writeln!(f, "  {}  {:>4} album(s)  DR{:<5.1} {:>6}  {}", year, n, mean, lufs, bar)?;
+        }
+        let unknown = albums.iter().filter(|a| a.year.is_none()).count();
+        if unknown > 0 {
+            writeln!(f, "  (no year: {} album(s))", unknown)?;
+        }
+        writeln!(f)?;
+
+        let mut decades: Vec<u32> = years.iter().map(|y| y / 10 * 10).collect();
+        decades.dedup();
+        if decades.len() > 1 {
+            writeln!(f, "  {:<29}{:>6}", "DR by decade", "LUFS")?;
+            for decade in decades {
+                let of_decade: Vec<&AlbumStats> =
+                    albums.iter().filter(|a| a.year.is_some_and(|y| y / 10 * 10 == decade)).collect();
+                let (n, mean, lufs) = period(&of_decade);
+                let bar = "█".repeat(mean.round().max(0.0) as usize);
+                writeln!(f, "  {}s {:>4} album(s)  DR{:<5.1} {:>6}  {}", decade, n, mean, lufs, bar)?;
+            }
+            writeln!(f)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_library(
+    albums: &[Album],
+    skipped: &[(PathBuf, String)],
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let mut f = BufWriter::new(create_text_output(output_path, opts.ascii)?);
+
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f, "  Dynamic Range Library Report")?;
+    writeln!(f, "  Generated : {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(f, "  Reports   : {}", albums.len() + skipped.len())?;
+    writeln!(f, "═══════════════════════════════════════════════════════════════════════════")?;
+    writeln!(f)?;
+
+    let dr_width = albums.iter().map(|a| a.dr_text.chars().count()).max().unwrap_or(0).max(4);
+    writeln!(f, "  {:<w$}  Grade  {:>6}  {:>7}  Album", "DR", "Tracks", "LUFS", w = dr_width)?;
+    writeln!(f, "  {}", "─".repeat(73))?;
+    for album in albums {
+        let tracks = album.tracks.map_or("–".to_string(), |n| n.to_string());
+        let lufs = album.lufs.map_or("–".to_string(), |l| format!("{:+.1}", l));
+        let name = if album.name.is_empty() { album.source.display().to_string() } else { album.name.clone() };
+        writeln!(
+            f,
+            "  {:<w$}  {:^5}  {:>6}  {:>7}  {}",
+            album.dr_text,
+            rating(album.dr.round() as i32).0,
+            tracks,
+            lufs,
+            name,
+            w = dr_width
+        )?;
+    }
+    writeln!(f, "  {}", "─".repeat(73))?;
+    writeln!(f)?;
+
+    if !albums.is_empty() {
+        let drs: Vec<f64> = albums.iter().map(|a| a.dr).collect();
+        let min = drs.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = drs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        writeln!(f, "  Summary")?;
+        writeln!(f, "  ───────────────────────────────")?;
+        writeln!(f, "  Albums          : {}", albums.len())?;
+        let tracks: usize = albums.iter().filter_map(|a| a.tracks).sum();
+        writeln!(f, "  Tracks          : {}", tracks)?;
+        writeln!(f, "  Album DR range  : DR{} – DR{}", min, max)?;
+        writeln!(f)?;
+        let stats: Vec<AlbumStats> = albums.iter().map(|a| AlbumStats { dr: a.dr, lufs: a.lufs, year: a.year }).collect();
+        write_statistics(&mut f, &stats)?;
+    }
+
+    if !skipped.is_empty() {
+        writeln!(f, "  Skipped reports")?;
+        writeln!(f, "  ───────────────────────────────")?;
+        for (path, error) in skipped {
+            writeln!(f, "  ✗ {} — {}", path.display(), error)?;
+        }
+        writeln!(f)?;
+    }
+    f.flush()
+}
@@ -0,0 +1,146 @@
+// ─── MusicBrainz release lookup ───────────────────────────────────────────────
+//
+// `--musicbrainz` identifies the release a folder was ripped from, so that DR
+// logs of different pressings of an album can be told apart: the report
+// header gains its title, label and catalogue number, country and year.
+//
+// Files tagged by MusicBrainz Picard carry the release's MBID in
+// MUSICBRAINZ_ALBUMID and are looked up directly; otherwise the ALBUM and
+// artist tags are searched for, and the best match is used only when
+// MusicBrainz scores it at least SEARCH_MIN_SCORE. Requests go to the public
+// web service, at most one a second, through the `curl` command-line tool,
+// which must be on the PATH. A failed lookup only leaves the release out.
+
+use crate::{album_tags, json::Json, TrackResult};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const API: &str = "https://musicbrainz.org/ws/2/release";
+
+/// Lowest search score (0–100) accepted as the folder's release.
+const SEARCH_MIN_SCORE: f64 = 90.0;
+
+/// The release a folder's tracks come from.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub id: String,
+    pub title: String,
+    pub country: Option<String>,
+    pub year: Option<u32>,
+    pub label: Option<String>,
+    pub catalog_number: Option<String>,
+}
+
+impl Release {
+    /// "Title — Label CAT-123 (GB, 1979)", leaving out what is unknown.
+    pub fn describe(&self) -> String {
+        let mut text = self.title.clone();
+        let label: Vec<&str> = [&self.label, &self.catalog_number].into_iter().flatten().map(String::as_str).collect();
+        if !label.is_empty() {
+            text.push_str(&format!(" — {}", label.join(" ")));
+        }
+        let year = self.year.map(|y| y.to_string());
+        let origin: Vec<&str> = [&self.country, &year].into_iter().flatten().map(String::as_str).collect();
+        if !origin.is_empty() {
+            text.push_str(&format!(" ({})", origin.join(", ")));
+        }
+        text
+    }
+}
+
+/// Percent-encodes `text` for a URL query.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A Lucene phrase for the search query.
+fn phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// When the last request was sent, to keep to MusicBrainz's limit of one
+/// request per second during --recursive scans.
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// GETs `url` from the web service and parses the JSON answer.
+fn get(url: &str) -> Result<Json, String> {
+    let mut last = LAST_REQUEST.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(wait) = last.and_then(|at| Duration::from_secs(1).checked_sub(at.elapsed())) {
+        std::thread::sleep(wait);
+    }
+    *last = Some(Instant::now());
+    curl(&[url])
+}
+
+/// Runs `curl` with `args` after the common options and parses the JSON it
+/// downloads; also used by acoustid.
+pub fn curl(args: &[&str]) -> Result<Json, String> {
+    let user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail-with-body", "--location", "--max-time", "20", "--user-agent"])
+        .arg(user_agent)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("cannot run curl ({})", e))?;
+    if !output.status.success() {
+        // The service's own explanation ({"error": "…"} from MusicBrainz,
+        // {"error": {"message": "…"}} from AcoustID), else curl's
+        let body = Json::parse(&String::from_utf8_lossy(&output.stdout)).ok();
+        let error = body.as_ref().and_then(|b| b.get("error")).and_then(|e| e.string().or(e.path("message")?.string()));
+        return Err(match error {
+            Some(message) => message.to_string(),
+            // curl prefixes its messages with "curl:"
+            None => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Json::parse(&String::from_utf8_lossy(&output.stdout)).map_err(|e| format!("unexpected answer ({})", e))
+}
+
+fn release(json: &Json) -> Option<Release> {
+    let text = |value: Option<&Json>| value.and_then(Json::string).filter(|s| !s.is_empty()).map(str::to_string);
+    // The first label with a catalogue number, else the first label
+    let labels: Vec<&Json> = match json.get("label-info") {
+        Some(Json::Array(labels)) => labels.iter().collect(),
+        _ => Vec::new(),
+    };
+    let label_info = labels.iter().find(|l| text(l.get("catalog-number")).is_some()).or(labels.first());
+    Some(Release {
+        id: text(json.get("id"))?,
+        title: text(json.get("title"))?,
+        country: text(json.get("country")),
+        year: text(json.get("date")).and_then(|d| d.get(..4)?.parse().ok()),
+        label: label_info.and_then(|l| text(l.path("label.name"))),
+        catalog_number: label_info.and_then(|l| text(l.get("catalog-number"))),
+    })
+}
+
+/// The release of `tracks`: by their MUSICBRAINZ_ALBUMID, else by searching
+/// their album and artist tags. None when they have neither or nothing
+/// matches well enough.
+pub fn lookup(tracks: &[&TrackResult]) -> Result<Option<Release>, String> {
+    if let Some(id) = tracks.iter().find_map(|t| t.tags.release_id.as_deref()) {
+        return Ok(release(&get(&format!("{}/{}?inc=labels&fmt=json", API, encode(id)))?));
+    }
+    let Some((artist, album)) = album_tags(tracks.iter().copied()) else {
+        return Ok(None);
+    };
+    let mut query = format!("release:{}", phrase(&album));
+    if artist != "Various Artists" {
+        query.push_str(&format!(" AND artist:{}", phrase(&artist)));
+    }
+    let answer = get(&format!("{}?query={}&limit=1&fmt=json", API, encode(&query)))?;
+    let best = match answer.get("releases") {
+        Some(Json::Array(releases)) => releases.first(),
+        _ => None,
+    };
+    Ok(best
+        .filter(|r| r.get("score").and_then(Json::number).unwrap_or(0.0) >= SEARCH_MIN_SCORE)
+        .and_then(release))
+}
@@ -0,0 +1,53 @@
+// ─── Noise floor ──────────────────────────────────────────────────────────────
+//
+// The signal is cut into 100 ms windows and the plain RMS of each (all
+// channels pooled) is taken. Windows of pure digital silence are ignored, and
+// the NOISE_FLOOR_PERCENTILE-th percentile of the rest estimates the level of
+// the quietest passages: tape hiss, surface noise or the dither floor.
+
+const WINDOW_SECONDS: f64 = 0.1;
+const NOISE_FLOOR_PERCENTILE: f64 = 0.05;
+
+pub struct NoiseFloorMeter {
+    window_len: usize,
+    pos: usize,
+    acc: f64,
+    count: usize,
+    window_rms: Vec<f64>,
+}
+
+impl NoiseFloorMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        NoiseFloorMeter {
+            window_len: ((WINDOW_SECONDS * sample_rate as f64).round() as usize).max(1),
+            pos: 0,
+            acc: 0.0,
+            count: 0,
+            window_rms: Vec::new(),
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        self.acc += frame.iter().map(|x| x * x).sum::<f64>();
+        self.count += frame.len();
+        self.pos += 1;
+        if self.pos == self.window_len {
+            if self.acc > 0.0 {
+                self.window_rms.push((self.acc / self.count as f64).sqrt());
+            }
+            self.pos = 0;
+            self.acc = 0.0;
+            self.count = 0;
+        }
+    }
+
+    /// Noise floor as a linear RMS fraction of full scale (0.0 for pure silence).
+    pub fn finish(mut self) -> f64 {
+        if self.window_rms.is_empty() {
+            return 0.0;
+        }
+        self.window_rms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let idx = ((self.window_rms.len() - 1) as f64 * NOISE_FLOOR_PERCENTILE).round() as usize;
+        self.window_rms[idx]
+    }
+}
@@ -0,0 +1,338 @@
+// ─── PDF report ───────────────────────────────────────────────────────────────
+//
+// A printable A4 report: header, album summary and rating, then the track
+// table, repeated across as many pages as it needs. It uses only the standard
+// Type 1 fonts every PDF reader ships (Helvetica and Courier), so nothing is
+// embedded and the file stays a few kilobytes; text is WinAnsi-encoded and
+// characters outside it print as '?'.
+
+use crate::{
+    album_dr_values, album_lufs, create_output, format_dr, format_duration, rating, AlbumMethod, ReportOptions, TrackResult,
+};
+use chrono::Local;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// A4 in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+/// Courier advance width per point of font size.
+const MONO_ADVANCE: f64 = 0.6;
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+    MonoBold,
+}
+
+impl Font {
+    const ALL: [(Font, &'static str); 4] = [
+        (Font::Regular, "Helvetica"),
+        (Font::Bold, "Helvetica-Bold"),
+        (Font::Mono, "Courier"),
+        (Font::MonoBold, "Courier-Bold"),
+    ];
+
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Mono => "F3",
+            Font::MonoBold => "F4",
+        }
+    }
+}
+
+/// A PDF string literal in WinAnsiEncoding.
+fn pdf_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('(');
+    for c in s.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                c as u8
+            }
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        };
+        if byte.is_ascii() {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("\\{:03o}", byte));
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Truncates to `width` characters, marking the cut with an ellipsis.
+fn fit(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(width - 1).collect();
+        out.push('…');
+        out
+    }
+}
+
+/// Content streams of the pages laid out so far, top to bottom.
+struct Layout {
+    pages: Vec<String>,
+    y: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout { pages: vec![String::new()], y: PAGE_HEIGHT - MARGIN }
+    }
+
+    fn page(&mut self) -> &mut String {
+        self.pages.last_mut().expect("at least one page")
+    }
+
+    fn fits(&self, height: f64) -> bool {
+        self.y - height >= MARGIN + 20.0
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Moves down one line of `size` points, breaking the page if needed.
+    fn advance(&mut self, size: f64) {
+        let height = size * 1.4;
+        if !self.fits(height) {
+            self.new_page();
+        }
+        self.y -= height;
+    }
+
+    fn text_at(&mut self, font: Font, size: f64, x: f64, text: &str) {
+        let y = self.y;
+        let command = format!("BT /{} {} Tf {:.2} {:.2} Td {} Tj ET\n", font.resource(), size, x, y, pdf_string(text));
+        self.page().push_str(&command);
+    }
+
+    fn line(&mut self, font: Font, size: f64, text: &str) {
+        self.advance(size);
+        self.text_at(font, size, MARGIN, text);
+    }
+
+    fn gap(&mut self, height: f64) {
+        self.y -= height;
+    }
+
+    fn rule(&mut self) {
+        self.gap(4.0);
+        let command = format!("0.5 w {} {:.2} m {} {:.2} l S\n", MARGIN, self.y, PAGE_WIDTH - MARGIN, self.y);
+        self.page().push_str(&command);
+        self.gap(2.0);
+    }
+}
+
+const TABLE_SIZE: f64 = 8.0;
+
+/// Column headers and widths (in characters) of the track table.
+const COLUMNS: [(&str, usize); 10] = [
+    ("#", 3),
+    ("File", 38),
+    ("DR", 6),
+    ("Grade", 5),
+    ("Peak SP", 8),
+    ("Peak TP", 8),
+    ("RMS dB", 8),
+    ("LUFS", 7),
+    ("Duration", 9),
+    ("Info", 9),
+];
+
+fn table_row(cells: &[String]) -> String {
+    let mut row = String::new();
+    for (i, (cell, (_, width))) in cells.iter().zip(COLUMNS).enumerate() {
+        if i > 0 {
+            row.push(' ');
+        }
+        // Only the file name is left-aligned
+        if i == 1 {
+            row.push_str(&format!("{:<w$}", fit(cell, width), w = width));
+        } else {
+            row.push_str(&format!("{:>w$}", fit(cell, width), w = width));
+        }
+    }
+    row
+}
+
+fn table_header(layout: &mut Layout) {
+    let headers: Vec<String> = COLUMNS.iter().map(|(h, _)| h.to_string()).collect();
+    layout.line(Font::MonoBold, TABLE_SIZE, &table_row(&headers));
+    layout.rule();
+}
+
+pub fn write_pdf(
+    results: &[Result<TrackResult, (String, String)>],
+    folder: &Path,
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let tracks: Vec<&TrackResult> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    let dr = |value: f64| format_dr(value, opts.precision, opts.dr.algorithm);
+    let folder_str = folder.canonicalize().unwrap_or_else(|_| folder.to_path_buf()).display().to_string();
+    let title = folder
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| folder_str.clone());
+
+    let mut layout = Layout::new();
+    layout.line(Font::Bold, 18.0, "Dynamic Range Report");
+    layout.gap(4.0);
+    layout.line(Font::Bold, 12.0, &title);
+    layout.line(Font::Regular, 9.0, &format!("Folder: {}", folder_str));
+    layout.line(Font::Regular, 9.0, &format!("Generated: {}", Local::now().format("%Y-%m-%d %H:%M:%S")));
+    if let Some(label) = opts.dr.algorithm.label() {
+        layout.line(Font::Regular, 9.0, &format!("Algorithm: {}", label));
+    }
+    layout.gap(6.0);
+    layout.rule();
+
+    // Summary
+    if !tracks.is_empty() {
+        let (dr_mean, dr_pooled) = album_dr_values(&tracks, opts);
+        let (album_value, method) = match opts.album_method {
+            AlbumMethod::Pooled => (dr_pooled, "pooled blocks"),
+            AlbumMethod::Mean => (dr_mean, "mean of tracks"),
+        };
+        let dr_album = opts.dr.algorithm.round(album_value);
+        let (grade, label) = (rating(dr_album).0, opts.lang.strings().rating(dr_album));
+        let lufs = album_lufs(&tracks);
+        let true_peak = tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max);
+        let duration: f64 = tracks.iter().map(|t| t.duration_secs).sum();
+
+        layout.gap(4.0);
+        layout.line(Font::Bold, 14.0, &format!("Album {} · Rating {} — {}", dr(album_value), grade, label));
+        layout.gap(2.0);
+        layout.line(Font::Regular, 9.0, &format!("Method: {}", method));
+        layout.line(Font::Regular, 9.0, &format!("Loudness: {:.1} LUFS, true peak {:+.1} dBTP", lufs, true_peak));
+        layout.line(
+            Font::Regular,
+            9.0,
+            &format!("Tracks: {}, total {}", tracks.len(), format_duration(duration)),
+        );
+        layout.gap(6.0);
+        layout.rule();
+    }
+
+    // Track table
+    layout.gap(8.0);
+    table_header(&mut layout);
+    for (i, t) in tracks.iter().enumerate() {
+        if !layout.fits(TABLE_SIZE * 1.4) {
+            layout.new_page();
+            table_header(&mut layout);
+        }
+        let row = table_row(&[
+            (i + 1).to_string(),
+            t.display_name(),
+            dr(t.dr_exact),
+            rating(t.dr).0.to_string(),
+            format!("{:+.2}", t.peak_db),
+            format!("{:+.2}", t.true_peak_db),
+            format!("{:+.2}", t.rms_db),
+            format!("{:+.1}", t.lufs),
+            format_duration(t.duration_secs),
+            format!("{}/{}/{}", t.sample_rate / 1000, t.bit_depth, t.channels),
+        ]);
+        layout.line(Font::Mono, TABLE_SIZE, &row);
+    }
+
+    let errors: Vec<&(String, String)> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+    if !errors.is_empty() {
+        layout.gap(12.0);
+        layout.line(Font::Bold, 11.0, "Errors");
+        let width = ((PAGE_WIDTH - 2.0 * MARGIN) / (TABLE_SIZE * MONO_ADVANCE)) as usize;
+        for (name, err) in errors {
+            layout.line(Font::Mono, TABLE_SIZE, &fit(&format!("{} — {}", name, err), width));
+        }
+    }
+
+    // Footer with page numbers, now that the page count is known
+    let total = layout.pages.len();
+    for (i, page) in layout.pages.iter_mut().enumerate() {
+        let footer = format!(
+            "{} {} · DR Loudness Standard · page {} of {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            i + 1,
+            total
+        );
+        page.push_str(&format!("BT /F1 7 Tf {} {} Td {} Tj ET\n", MARGIN, MARGIN - 15.0, pdf_string(&footer)));
+    }
+
+    write_document(&layout.pages, output_path)
+}
+
+/// Writes the page content streams as a PDF 1.4 file, with the cross-reference
+/// table of byte offsets readers need to find each object.
+fn write_document(pages: &[String], output_path: &Path) -> std::io::Result<()> {
+    // Objects: 1 catalog, 2 page tree, 3.. fonts, then a page and its content per page
+    let first_page = 3 + Font::ALL.len();
+    let mut objects: Vec<String> = Vec::new();
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", first_page + 2 * i)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()));
+    for (_, name) in Font::ALL {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            name
+        ));
+    }
+    let fonts: Vec<String> =
+        Font::ALL.iter().enumerate().map(|(i, (font, _))| format!("/{} {} 0 R", font.resource(), 3 + i)).collect();
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            fonts.join(" "),
+            first_page + 2 * i + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut out = Vec::new();
+    // The binary comment marks the file as 8-bit for transfer tools
+    out.extend_from_slice(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+    );
+
+    let mut f = BufWriter::new(create_output(output_path)?);
+    f.write_all(&out)?;
+    f.flush()
+}
@@ -0,0 +1,196 @@
+// ─── PNG encoding ─────────────────────────────────────────────────────────────
+//
+// Just enough PNG to write the report images: 8-bit palette colour, no
+// interlacing, every row unfiltered. The pixel data is deflated with the fixed
+// Huffman code and a greedy search for repeats of the previous pixel or of the
+// row above, which is where plots keep almost all their redundancy.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// An image of palette indices.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Up to 256 RGB colours.
+    pub palette: Vec<[u8; 3]>,
+    /// Row-major palette indices.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, palette: Vec<[u8; 3]>) -> Self {
+        Image { width, height, palette, pixels: vec![0; width * height] }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: u8) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        f.write_all(&self.encode())?;
+        f.flush()
+    }
+
+    /// The complete PNG file.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut f = Vec::new();
+        f.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // Bit depth 8, colour type 3 (palette), default compression/filter, no interlace
+        header.extend_from_slice(&[8, 3, 0, 0, 0]);
+        write_chunk(&mut f, b"IHDR", &header);
+
+        let palette: Vec<u8> = self.palette.iter().flatten().copied().collect();
+        write_chunk(&mut f, b"PLTE", &palette);
+
+        // Each row is preceded by its filter type (0 = none)
+        let mut raw = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        write_chunk(&mut f, b"IDAT", &zlib_compress(&raw, self.width + 1));
+        write_chunk(&mut f, b"IEND", &[]);
+        f
+    }
+}
+
+fn write_chunk(f: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    f.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    f.extend_from_slice(kind);
+    f.extend_from_slice(data);
+    let crc = crc32(kind.iter().chain(data));
+    f.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in bytes {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// ─── Deflate (fixed Huffman code) ─────────────────────────────────────────────
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_DISTANCE: usize = 32768;
+
+/// LSB-first bit writer.
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, bits: u32) {
+        self.acc |= value << self.bits;
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Huffman codes are stored most significant bit first.
+    fn put_code(&mut self, code: u32, bits: u32) {
+        let reversed = code.reverse_bits() >> (32 - bits);
+        self.put(reversed, bits);
+    }
+
+    fn put_symbol(&mut self, symbol: u16) {
+        match symbol {
+            0..=143 => self.put_code(0x30 + symbol as u32, 8),
+            144..=255 => self.put_code(0x190 + (symbol - 144) as u32, 9),
+            256..=279 => self.put_code((symbol - 256) as u32, 7),
+            _ => self.put_code(0xC0 + (symbol - 280) as u32, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+fn match_len(data: &[u8], pos: usize, distance: usize) -> usize {
+    if distance == 0 || distance > pos || distance > MAX_DISTANCE {
+        return 0;
+    }
+    let max = (data.len() - pos).min(MAX_MATCH);
+    (0..max).take_while(|&i| data[pos + i] == data[pos + i - distance]).count()
+}
+
+/// zlib stream of `data`, looking for repeats at distance 1 and `stride`.
+fn zlib_compress(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut w = BitWriter { out: vec![0x78, 0x01], acc: 0, bits: 0 };
+    // Single final block, fixed Huffman code
+    w.put(1, 1);
+    w.put(1, 2);
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (len, distance) = [1, stride]
+            .iter()
+            .map(|&d| (match_len(data, pos, d), d))
+            .max_by_key(|&(len, _)| len)
+            .unwrap();
+        if len < MIN_MATCH {
+            w.put_symbol(data[pos] as u16);
+            pos += 1;
+            continue;
+        }
+
+        let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap();
+        w.put_symbol(257 + code as u16);
+        w.put((len - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+        let code = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+        w.put_code(code as u32, 5);
+        w.put((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+        pos += len;
+    }
+    w.put_symbol(256);
+
+    let mut out = w.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
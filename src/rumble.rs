@@ -0,0 +1,52 @@
+// ─── Subsonic rumble ──────────────────────────────────────────────────────────
+//
+// Each channel is low-passed at RUMBLE_CUTOFF_HZ (8th-order Butterworth, four
+// biquads, steep enough to leave 40 Hz bass alone) and the energy that passes is compared with the full-band energy.
+// Turntable rumble, warped records and DC drift all land below the cutoff,
+// where a digital master has next to nothing.
+
+use crate::loudness::Biquad;
+
+const RUMBLE_CUTOFF_HZ: f64 = 20.0;
+/// Quality factors of the four sections of an 8th-order Butterworth filter.
+const BUTTERWORTH_Q: [f64; 4] = [
+    2.562_915_447_741_506,
+    0.899_976_223_136_415_6,
+    0.601_344_886_935_045_3,
+    0.509_795_579_104_159_2,
+];
+
+/// Streaming meter fed one normalised interleaved frame at a time.
+pub struct RumbleMeter {
+    filters: Vec<[Biquad; 4]>,
+    subsonic: f64,
+    total: f64,
+}
+
+impl RumbleMeter {
+    pub fn new(channels: u32, sample_rate: u32) -> Self {
+        let stages = BUTTERWORTH_Q.map(|q| Biquad::lowpass(sample_rate, RUMBLE_CUTOFF_HZ, q));
+        RumbleMeter {
+            filters: vec![stages; channels as usize],
+            subsonic: 0.0,
+            total: 0.0,
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        for (stages, &x) in self.filters.iter_mut().zip(frame) {
+            let y = stages.iter_mut().fold(x, |y, stage| stage.process(y));
+            self.subsonic += y * y;
+            self.total += x * x;
+        }
+    }
+
+    /// Subsonic energy relative to the full-band energy (dB), None for silence.
+    pub fn finish(self) -> Option<f64> {
+        if self.total > 0.0 {
+            Some(10.0 * (self.subsonic / self.total).max(1e-12).log10())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// ─── Sealed reports ───────────────────────────────────────────────────────────
+//
+// `--hash` appends the SHA-256 of a text report (or foobar2000 log) to its
+// end, and `--sign-key key.pem` an Ed25519 signature of the same bytes, so an
+// archived log can later be checked as untampered with `dr-measure verify`:
+//
+//       SHA-256   : 5d41402abc4b2a76b9719d911017c592…
+//       Signature : ed25519 9f0c…
+//
+// Both cover everything above the SHA-256 line. Signing and signature checks
+// are done by the `openssl` command-line tool (3.0 or later), which must be
+// on the PATH; keys are PEM files as written by
+//
+//     openssl genpkey -algorithm ed25519 -out dr-key.pem
+//     openssl pkey -in dr-key.pem -pubout -out dr-key.pub.pem
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const HASH_LABEL: &str = "  SHA-256   : ";
+const SIGNATURE_LABEL: &str = "  Signature : ed25519 ";
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4) of `data`.
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (out, word) in digest.chunks_mut(4).zip(h) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Runs `openssl` with `args`, returning its stdout.
+fn openssl(args: &[&std::ffi::OsStr]) -> Result<Vec<u8>, String> {
+    let output = Command::new("openssl")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("cannot run openssl ({})", e))?;
+    if output.status.success() {
+        return Ok(output.stdout);
+    }
+    // pkeyutl -verify reports a bad signature on stdout, other failures on stderr
+    let message = String::from_utf8_lossy(if output.stdout.is_empty() { &output.stderr } else { &output.stdout });
+    Err(format!("openssl: {}", message.trim()))
+}
+
+/// Appends the SHA-256 of the report at `path`, and its Ed25519 signature
+/// with the private key at `key` if one is given.
+pub fn seal(path: &Path, key: Option<&Path>) -> std::io::Result<()> {
+    let content = std::fs::read(path)?;
+    let mut footer = format!("\n{}{}\n", HASH_LABEL, hex(&sha256(&content)));
+    if let Some(key) = key {
+        let signature = openssl(&[
+            "pkeyutl".as_ref(),
+            "-sign".as_ref(),
+            "-rawin".as_ref(),
+            "-inkey".as_ref(),
+            key.as_os_str(),
+            "-in".as_ref(),
+            path.as_os_str(),
+        ])
+        .map_err(std::io::Error::other)?;
+        footer.push_str(&format!("{}{}\n", SIGNATURE_LABEL, hex(&signature)));
+    }
+    std::fs::OpenOptions::new().append(true).open(path)?.write_all(footer.as_bytes())
+}
+
+/// What `verify` found.
+pub enum Verdict {
+    /// The content matches its hash; the signature, if any, was checked
+    /// when `signed` is true.
+    Intact { signed: bool, has_signature: bool },
+    Tampered(String),
+}
+
+/// Checks a sealed report's hash and, given the public key, its signature.
+pub fn verify(path: &Path, public_key: Option<&Path>) -> Result<Verdict, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let text = String::from_utf8_lossy(&data);
+    let marker = format!("\n\n{}", HASH_LABEL);
+    let Some(at) = text.rfind(&marker) else {
+        return Err(format!("{}: no SHA-256 line (write the report with --hash)", path.display()));
+    };
+    let content = &data[..at + 1];
+    let mut footer = text[at + 2..].lines();
+    let hash = footer.next().and_then(|l| l.strip_prefix(HASH_LABEL)).unwrap_or("").trim();
+    let signature = footer.next().and_then(|l| l.strip_prefix(SIGNATURE_LABEL)).map(str::trim);
+
+    if hash != hex(&sha256(content)) {
+        return Ok(Verdict::Tampered("the content does not match its SHA-256".to_string()));
+    }
+    let (Some(signature), Some(public_key)) = (signature, public_key) else {
+        return Ok(Verdict::Intact { signed: false, has_signature: signature.is_some() });
+    };
+    let signature = unhex(signature).ok_or_else(|| format!("{}: malformed signature line", path.display()))?;
+
+    // openssl reads the message and the signature from files
+    let dir = std::env::temp_dir().join(format!("dr-measure-verify-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let (content_path, signature_path) = (dir.join("report"), dir.join("signature"));
+    let written = std::fs::write(&content_path, content).and_then(|_| std::fs::write(&signature_path, &signature));
+    let checked = written.map_err(|e| e.to_string()).and_then(|_| {
+        openssl(&[
+            "pkeyutl".as_ref(),
+            "-verify".as_ref(),
+            "-rawin".as_ref(),
+            "-pubin".as_ref(),
+            "-inkey".as_ref(),
+            public_key.as_os_str(),
+            "-in".as_ref(),
+            content_path.as_os_str(),
+            "-sigfile".as_ref(),
+            signature_path.as_os_str(),
+        ])
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+    match checked {
+        Ok(_) => Ok(Verdict::Intact { signed: true, has_signature: true }),
+        Err(e) if e.contains("Signature Verification Failure") => {
+            Ok(Verdict::Tampered("the signature does not match the public key".to_string()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_matches_the_fips_180_examples() {
+        let vectors: [(&[u8], &str); 3] = [
+            (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+        ];
+        for (input, digest) in vectors {
+            assert_eq!(hex(&sha256(input)), digest, "SHA-256 of {:?}", String::from_utf8_lossy(input));
+        }
+        assert_eq!(
+            hex(&sha256(&vec![b'a'; 1_000_000])),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn sha256_pads_messages_near_a_block_boundary() {
+        // 55 bytes fit the length in the same block, 56 need another
+        assert_eq!(hex(&sha256(&[b'a'; 55])), "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318");
+        assert_eq!(hex(&sha256(&[b'a'; 56])), "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a");
+    }
+}
@@ -0,0 +1,295 @@
+// ─── Spectral analysis ────────────────────────────────────────────────────────
+//
+//  1. Downmix each frame to mono and collect non-overlapping FFT_SIZE windows.
+//  2. Apply a Hann window, FFT, and accumulate |X|² into an average spectrum.
+//  3. Smooth the average spectrum (in dB) over SMOOTHING_HZ and take the level
+//     of the REFERENCE_BAND as the programme level.
+//  4. The cutoff is the highest frequency still within CUTOFF_RANGE_DB of the
+//     reference. A steep cliff just below a cutoff that sits under ~20.5 kHz is
+//     the signature of an MP3/AAC encoder's low-pass, so the track is flagged.
+//  5. For hi-res files (≥ 88.2 kHz) a cliff at or below 24 kHz means the
+//     ultrasonic band is empty: the material was upsampled from 44.1/48 kHz.
+//
+// On request the same FFT windows also feed a spectrogram: consecutive windows
+// are averaged into SPECTROGRAM_WIDTH columns of SPECTROGRAM_HEIGHT linear
+// frequency bands.
+
+use crate::png::Image;
+
+const FFT_SIZE: usize = 4096;
+const SMOOTHING_HZ: f64 = 200.0;
+const REFERENCE_BAND: (f64, f64) = (1000.0, 4000.0);
+const CUTOFF_RANGE_DB: f64 = 60.0;
+/// Level drop across CLIFF_WIDTH_HZ that counts as an encoder low-pass.
+const CLIFF_DB: f64 = 25.0;
+const CLIFF_WIDTH_HZ: f64 = 1000.0;
+/// Encoders seldom low-pass above this; real band-limited masters do.
+const LOSSY_MAX_CUTOFF_HZ: f64 = 20_500.0;
+const HI_RES_MIN_SAMPLE_RATE: u32 = 88_200;
+/// Cutoff ceilings (Hz) of the usual upsampling sources: 44.1 and 48 kHz.
+const UPSAMPLING_SOURCES: [(f64, u32); 2] = [(22_500.0, 44_100), (24_500.0, 48_000)];
+
+const SPECTROGRAM_WIDTH: usize = 800;
+const SPECTROGRAM_HEIGHT: usize = 256;
+/// Levels this far below the loudest cell are drawn black.
+const SPECTROGRAM_RANGE_DB: f32 = 100.0;
+
+// ─── FFT ──────────────────────────────────────────────────────────────────────
+
+/// In-place iterative radix-2 FFT. `re.len()` must be a power of two.
+pub fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        let (w_im, w_re) = angle.sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+pub fn hann_window(n: usize) -> Vec<f64> {
+    (0..n)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos())
+        .collect()
+}
+
+// ─── Analyzer ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    /// Highest frequency with content within CUTOFF_RANGE_DB of the programme level.
+    pub cutoff_hz: f64,
+    /// Steep encoder-style low-pass detected below LOSSY_MAX_CUTOFF_HZ.
+    pub lossy_suspect: bool,
+    /// Likely original sample rate of a hi-res file with an empty ultrasonic band.
+    pub upsampled_from: Option<u32>,
+    /// Time/frequency levels, when requested.
+    pub spectrogram: Option<Spectrogram>,
+}
+
+impl Spectrum {
+    /// Bitrate class typically associated with an encoder low-pass at this cutoff.
+    pub fn lossy_guess(&self) -> &'static str {
+        match self.cutoff_hz {
+            f if f < 15_500.0 => "≤ 96 kbps",
+            f if f < 17_000.0 => "~128 kbps",
+            f if f < 19_000.0 => "~160–192 kbps",
+            f if f < 20_000.0 => "~256 kbps",
+            _ => "~320 kbps",
+        }
+    }
+}
+
+pub struct SpectrumAnalyzer {
+    sample_rate: u32,
+    window: Vec<f64>,
+    buffer: Vec<f64>,
+    power: Vec<f64>,
+    windows: usize,
+    spectrogram: Option<SpectrogramBuilder>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        SpectrumAnalyzer {
+            sample_rate,
+            window: hann_window(FFT_SIZE),
+            buffer: Vec::with_capacity(FFT_SIZE),
+            power: vec![0.0; FFT_SIZE / 2 + 1],
+            windows: 0,
+            spectrogram: None,
+        }
+    }
+
+    /// Also build a spectrogram of the `frames` frames that will be pushed
+    /// (0 if unknown).
+    pub fn with_spectrogram(mut self, frames: u64) -> Self {
+        let windows = frames as usize / FFT_SIZE;
+        self.spectrogram = Some(SpectrogramBuilder {
+            windows_per_column: windows.div_ceil(SPECTROGRAM_WIDTH).max(1),
+            pending: 0,
+            column: vec![0.0; SPECTROGRAM_HEIGHT],
+            columns: Vec::new(),
+        });
+        self
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        self.buffer.push(frame.iter().sum::<f64>() / frame.len() as f64);
+        if self.buffer.len() == FFT_SIZE {
+            let mut re: Vec<f64> = self.buffer.iter().zip(&self.window).map(|(x, w)| x * w).collect();
+            let mut im = vec![0.0; FFT_SIZE];
+            fft(&mut re, &mut im);
+            for (k, p) in self.power.iter_mut().enumerate() {
+                *p += re[k] * re[k] + im[k] * im[k];
+            }
+            if let Some(spectrogram) = self.spectrogram.as_mut() {
+                spectrogram.push_window(&re, &im);
+            }
+            self.windows += 1;
+            self.buffer.clear();
+        }
+    }
+
+    pub fn finish(self) -> Spectrum {
+        let bin_hz = self.sample_rate as f64 / FFT_SIZE as f64;
+        let power: Vec<f64> = self.power.iter().map(|p| p / self.windows.max(1) as f64).collect();
+
+        // Smoothed level in dB
+        let half = ((SMOOTHING_HZ / bin_hz / 2.0).round() as usize).max(1);
+        let smoothed: Vec<f64> = (0..power.len())
+            .map(|k| {
+                let lo = k.saturating_sub(half);
+                let hi = (k + half).min(power.len() - 1);
+                let mean = power[lo..=hi].iter().sum::<f64>() / (hi - lo + 1) as f64;
+                10.0 * (mean + 1e-30).log10()
+            })
+            .collect();
+
+        let bin = |hz: f64| ((hz / bin_hz).round() as usize).min(power.len() - 1);
+        let (ref_lo, ref_hi) = (bin(REFERENCE_BAND.0), bin(REFERENCE_BAND.1));
+        let reference = smoothed[ref_lo..=ref_hi].iter().sum::<f64>() / (ref_hi - ref_lo + 1) as f64;
+
+        let cutoff_bin = (ref_hi..smoothed.len())
+            .rev()
+            .find(|&k| smoothed[k] > reference - CUTOFF_RANGE_DB)
+            .unwrap_or(ref_hi);
+        let cutoff_hz = cutoff_bin as f64 * bin_hz;
+
+        // Level just below vs. just above the cutoff
+        let width = bin(CLIFF_WIDTH_HZ).max(1);
+        let below = smoothed[cutoff_bin.saturating_sub(width)];
+        let above = smoothed[(cutoff_bin + width).min(smoothed.len() - 1)];
+        let has_cliff = cutoff_bin + width < smoothed.len() && below - above > CLIFF_DB;
+
+        let upsampled_from = if self.windows > 0 && has_cliff && self.sample_rate >= HI_RES_MIN_SAMPLE_RATE {
+            UPSAMPLING_SOURCES
+                .iter()
+                .find(|(ceiling, _)| cutoff_hz <= *ceiling)
+                .map(|&(_, rate)| rate)
+        } else {
+            None
+        };
+
+        Spectrum {
+            cutoff_hz,
+            lossy_suspect: self.windows > 0 && has_cliff && cutoff_hz < LOSSY_MAX_CUTOFF_HZ,
+            upsampled_from,
+            spectrogram: self.spectrogram.map(SpectrogramBuilder::finish),
+        }
+    }
+}
+
+// ─── Spectrogram ──────────────────────────────────────────────────────────────
+
+struct SpectrogramBuilder {
+    windows_per_column: usize,
+    pending: usize,
+    /// Power per band of the column being averaged.
+    column: Vec<f64>,
+    /// Level (dB) per band, lowest band first, for every finished column.
+    columns: Vec<Vec<f32>>,
+}
+
+impl SpectrogramBuilder {
+    fn push_window(&mut self, re: &[f64], im: &[f64]) {
+        let bins_per_band = (FFT_SIZE / 2) / SPECTROGRAM_HEIGHT;
+        for (band, p) in self.column.iter_mut().enumerate() {
+            let lo = band * bins_per_band;
+            *p += (lo..lo + bins_per_band).map(|k| re[k] * re[k] + im[k] * im[k]).sum::<f64>();
+        }
+        self.pending += 1;
+        if self.pending == self.windows_per_column {
+            self.close_column();
+        }
+    }
+
+    fn close_column(&mut self) {
+        let n = self.pending as f64;
+        self.columns
+            .push(self.column.iter().map(|p| (10.0 * (p / n + 1e-30).log10()) as f32).collect());
+        self.column.iter_mut().for_each(|p| *p = 0.0);
+        self.pending = 0;
+    }
+
+    fn finish(mut self) -> Spectrogram {
+        if self.pending > 0 {
+            self.close_column();
+        }
+        Spectrogram { columns: self.columns }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    columns: Vec<Vec<f32>>,
+}
+
+impl Spectrogram {
+    /// Heat map: time left to right, frequency (linear, up to Nyquist) bottom
+    /// to top, black to white over SPECTROGRAM_RANGE_DB.
+    pub fn render(&self) -> Image {
+        let top = self.columns.iter().flatten().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let mut image = Image::new(self.columns.len().max(1), SPECTROGRAM_HEIGHT, heat_palette());
+        for (x, column) in self.columns.iter().enumerate() {
+            for (band, &db) in column.iter().enumerate() {
+                let level = ((db - top + SPECTROGRAM_RANGE_DB) / SPECTROGRAM_RANGE_DB).clamp(0.0, 1.0);
+                image.set(x, SPECTROGRAM_HEIGHT - 1 - band, (level * 255.0).round() as u8);
+            }
+        }
+        image
+    }
+}
+
+/// 256 colours: black → blue → magenta → orange → yellow → white.
+fn heat_palette() -> Vec<[u8; 3]> {
+    const STOPS: [(f32, [f32; 3]); 6] = [
+        (0.0, [0.0, 0.0, 0.0]),
+        (0.25, [20.0, 10.0, 120.0]),
+        (0.5, [170.0, 30.0, 130.0]),
+        (0.7, [240.0, 110.0, 30.0]),
+        (0.9, [255.0, 220.0, 60.0]),
+        (1.0, [255.0, 255.0, 255.0]),
+    ];
+    (0..256)
+        .map(|i| {
+            let t = i as f32 / 255.0;
+            let hi = STOPS.iter().position(|&(at, _)| at >= t).unwrap_or(STOPS.len() - 1).max(1);
+            let ((t0, c0), (t1, c1)) = (STOPS[hi - 1], STOPS[hi]);
+            let f = (t - t0) / (t1 - t0);
+            [0, 1, 2].map(|k| (c0[k] + (c1[k] - c0[k]) * f).round() as u8)
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+// ─── Writing tags ─────────────────────────────────────────────────────────────
+//
+// `--tag` and `--write-replaygain` write the measured values back into the
+// analysed files as Vorbis comments, for players and taggers that use them
+// (foobar2000 displays DYNAMIC_RANGE and ALBUM_DYNAMIC_RANGE). An existing
+// value is replaced and the other tags are kept; see flacmeta for how the
+// files are rewritten. `dr-measure untag` takes them out again.
+
+use crate::{album_dr, album_lufs, album_sections, document, flacmeta, loudness, ReportOptions, TrackResult};
+use std::path::Path;
+
+pub const DR_TAG: &str = "DYNAMIC_RANGE";
+pub const ALBUM_DR_TAG: &str = "ALBUM_DYNAMIC_RANGE";
+const TRACK_GAIN_TAG: &str = "REPLAYGAIN_TRACK_GAIN";
+const TRACK_PEAK_TAG: &str = "REPLAYGAIN_TRACK_PEAK";
+const ALBUM_GAIN_TAG: &str = "REPLAYGAIN_ALBUM_GAIN";
+const ALBUM_PEAK_TAG: &str = "REPLAYGAIN_ALBUM_PEAK";
+
+/// Every comment `--tag`, `--write-replaygain` and `--embed` write.
+const WRITTEN_TAGS: [&str; 7] =
+    [DR_TAG, ALBUM_DR_TAG, TRACK_GAIN_TAG, TRACK_PEAK_TAG, ALBUM_GAIN_TAG, ALBUM_PEAK_TAG, document::EMBED_TAG];
+
+/// Replaces every `key` comment with one holding `value`.
+fn set(comments: &mut flacmeta::Comments, key: &str, value: String) {
+    comments.retain(|(k, _)| k != key);
+    comments.push((key.to_string(), value));
+}
+
+/// `tracks` split by album, as in the report's Albums section, so that
+/// album values in a mixed folder are those of each track's own album.
+fn albums<'a>(tracks: &[&'a TrackResult]) -> Vec<Vec<&'a TrackResult>> {
+    album_sections(tracks).into_iter().map(|(_, group)| group).collect()
+}
+
+/// Writes DYNAMIC_RANGE (the track DR) and ALBUM_DYNAMIC_RANGE (the DR of the
+/// track's album) into each file.
+pub fn write_dr_tags(tracks: &[&TrackResult], folder: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    for group in albums(tracks) {
+        write_album_dr_tags(&group, folder, opts)?;
+    }
+    Ok(())
+}
+
+fn write_album_dr_tags(tracks: &[&TrackResult], folder: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    let album = opts.dr.algorithm.round(album_dr(tracks, opts));
+    for t in tracks {
+        flacmeta::edit_comments(&folder.join(&t.filename), |comments| {
+            set(comments, DR_TAG, t.dr.to_string());
+            set(comments, ALBUM_DR_TAG, album.to_string());
+        })
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", t.filename, e)))?;
+    }
+    Ok(())
+}
+
+/// Writes the ReplayGain 2.0 track and album gain (dB, as "+1.52 dB") and
+/// peak (linear sample peak) into each file, album values per album. Gains
+/// of digital silence, which has no loudness, are left out.
+pub fn write_replaygain_tags(tracks: &[&TrackResult], folder: &Path) -> std::io::Result<()> {
+    for group in albums(tracks) {
+        write_album_replaygain_tags(&group, folder)?;
+    }
+    Ok(())
+}
+
+fn write_album_replaygain_tags(tracks: &[&TrackResult], folder: &Path) -> std::io::Result<()> {
+    let album_gain = loudness::replaygain(album_lufs(tracks));
+    let album_peak = tracks.iter().map(|t| t.sample_peak).fold(0.0f64, f64::max);
+    let gain = |db: f64| db.is_finite().then(|| format!("{:+.2} dB", db));
+    for t in tracks {
+        flacmeta::edit_comments(&folder.join(&t.filename), |comments| {
+            let values = [
+                (TRACK_GAIN_TAG, gain(loudness::replaygain(t.lufs))),
+                (TRACK_PEAK_TAG, Some(format!("{:.6}", t.sample_peak))),
+                (ALBUM_GAIN_TAG, gain(album_gain)),
+                (ALBUM_PEAK_TAG, Some(format!("{:.6}", album_peak))),
+            ];
+            for (key, value) in values {
+                match value {
+                    Some(value) => set(comments, key, value),
+                    None => comments.retain(|(k, _)| k != key),
+                }
+            }
+        })
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", t.filename, e)))?;
+    }
+    Ok(())
+}
+
+/// Removes the DR, ReplayGain and embedded-result comments from the FLAC file
+/// at `path`. Returns whether it had any.
+pub fn strip_tags(path: &Path) -> std::io::Result<bool> {
+    flacmeta::edit_comments(path, |comments| comments.retain(|(key, _)| !WRITTEN_TAGS.contains(&key.as_str())))
+}
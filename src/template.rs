@@ -0,0 +1,272 @@
+// ─── Report templates ─────────────────────────────────────────────────────────
+//
+// `--template FILE` renders the JSON report document (see document.rs) through
+// a Handlebars-style template, for layouts the built-in formats don't cover.
+// The supported subset:
+//
+//     {{album.dr}}             value at a dotted path (HTML-escaped)
+//     {{{meta.folder}}}        the same, unescaped
+//     {{#each tracks}}…{{/each}}     once per item; inside, {{this}}, {{@index}},
+//                                    {{@first}}, {{@last}}, {{@key}} (objects)
+//     {{#if x}}…{{else}}…{{/if}}     {{#unless x}}…{{/unless}}
+//     {{#with album}}…{{/with}}      narrows the context
+//     {{../file}}              the enclosing context
+//     {{! comment }}
+//
+// Names not found in the current context are looked up in the enclosing ones,
+// so `{{meta.algorithm}}` also works inside `{{#each tracks}}`. Missing values
+// render as nothing. As in Handlebars, null, false, 0, "" and [] are falsy.
+
+use crate::document::{self, Value};
+use crate::{create_output, ReportOptions, TrackResult};
+use std::io::Write;
+use std::path::Path;
+
+enum Node {
+    Text(String),
+    Var { path: String, escape: bool },
+    Block { helper: Helper, path: String, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Helper {
+    Each,
+    If,
+    Unless,
+    With,
+}
+
+impl Helper {
+    fn parse(name: &str) -> Option<Helper> {
+        match name {
+            "each" => Some(Helper::Each),
+            "if" => Some(Helper::If),
+            "unless" => Some(Helper::Unless),
+            "with" => Some(Helper::With),
+            _ => None,
+        }
+    }
+}
+
+enum Tag<'a> {
+    Else,
+    Close(&'a str),
+}
+
+/// Nodes parsed so far, and the closing or else tag (with its line) that
+/// ended them, if any.
+type Parsed<'a> = (Vec<Node>, Option<(Tag<'a>, usize)>);
+
+/// Parses until the end of input or a closing / else tag, which is returned
+/// for the caller to match against its opening tag.
+fn parse_nodes<'a>(src: &'a str, pos: &mut usize) -> Result<Parsed<'a>, String> {
+    let mut nodes = Vec::new();
+    while *pos < src.len() {
+        let rest = &src[*pos..];
+        let Some(start) = rest.find("{{") else {
+            nodes.push(Node::Text(rest.to_string()));
+            *pos = src.len();
+            break;
+        };
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let line = src[..*pos + start].matches('\n').count() + 1;
+        let raw = rest[start..].starts_with("{{{");
+        let (open, close) = if raw { ("{{{", "}}}") } else { ("{{", "}}") };
+        let inner_start = start + open.len();
+        let Some(len) = rest[inner_start..].find(close) else {
+            return Err(format!("line {}: unclosed '{}'", line, open));
+        };
+        let inner = rest[inner_start..inner_start + len].trim();
+        *pos += inner_start + len + close.len();
+
+        if raw {
+            nodes.push(Node::Var { path: inner.to_string(), escape: false });
+        } else if inner.starts_with('!') {
+            // Comment
+        } else if let Some(block) = inner.strip_prefix('#') {
+            let (name, path) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+            nodes.push(parse_block(src, pos, name, path.trim(), line)?);
+        } else if let Some(name) = inner.strip_prefix('/') {
+            return Ok((nodes, Some((Tag::Close(name.trim()), line))));
+        } else if inner == "else" {
+            return Ok((nodes, Some((Tag::Else, line))));
+        } else {
+            nodes.push(Node::Var { path: inner.to_string(), escape: true });
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_block(src: &str, pos: &mut usize, name: &str, path: &str, line: usize) -> Result<Node, String> {
+    let helper = Helper::parse(name).ok_or_else(|| format!("line {}: unknown block helper '#{}'", line, name))?;
+    if path.is_empty() {
+        return Err(format!("line {}: '#{}' needs a value", line, name));
+    }
+    let (body, end) = parse_nodes(src, pos)?;
+    let (otherwise, end) = match end {
+        Some((Tag::Else, _)) => parse_nodes(src, pos)?,
+        end => (Vec::new(), end),
+    };
+    match end {
+        Some((Tag::Close(closed), _)) if closed == name => {
+            Ok(Node::Block { helper, path: path.to_string(), body, otherwise })
+        }
+        Some((Tag::Close(closed), at)) => {
+            Err(format!("line {}: '/{}' closes '#{}' from line {}", at, closed, name, line))
+        }
+        Some((Tag::Else, at)) => Err(format!("line {}: second 'else' in '#{}'", at, name)),
+        None => Err(format!("line {}: '#{}' is never closed", line, name)),
+    }
+}
+
+fn parse(src: &str) -> Result<Vec<Node>, String> {
+    let mut pos = 0;
+    match parse_nodes(src, &mut pos)? {
+        (nodes, None) => Ok(nodes),
+        (_, Some((Tag::Close(name), line))) => Err(format!("line {}: '/{}' without an opening block", line, name)),
+        (_, Some((_, line))) => Err(format!("line {}: 'else' outside a block", line)),
+    }
+}
+
+/// One level of context: the value `this` refers to, the loop variables
+/// when it is an `#each` item, and the enclosing context.
+struct Frame<'a> {
+    this: &'a Value,
+    index: Option<(usize, usize)>,
+    key: Option<&'static str>,
+    parent: Option<&'a Frame<'a>>,
+}
+
+/// A looked-up value: part of the document, or a loop variable.
+enum Lookup<'a> {
+    Found(&'a Value),
+    Local(Value),
+}
+
+impl Lookup<'_> {
+    fn value(&self) -> &Value {
+        match self {
+            Lookup::Found(value) => value,
+            Lookup::Local(value) => value,
+        }
+    }
+}
+
+fn resolve<'a>(frame: &'a Frame<'a>, path: &str) -> Option<Lookup<'a>> {
+    let mut frame = frame;
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("../") {
+        frame = frame.parent?;
+        path = rest;
+    }
+    match path {
+        "this" | "." => return Some(Lookup::Found(frame.this)),
+        "@index" => return frame.index.map(|(i, _)| Lookup::Local(i.into())),
+        "@first" => return frame.index.map(|(i, _)| Lookup::Local((i == 0).into())),
+        "@last" => return frame.index.map(|(i, n)| Lookup::Local((i + 1 == n).into())),
+        "@key" => return frame.key.map(|k| Lookup::Local(k.into())),
+        _ => {}
+    }
+    let path = path.strip_prefix("this.").unwrap_or(path);
+    let mut segments = path.split('.');
+    let first = segments.next()?;
+    // Implicit lookup walks out through the enclosing contexts
+    let mut scope = Some(frame);
+    let mut value = loop {
+        let f = scope?;
+        if let Some(value) = f.this.get(first) {
+            break value;
+        }
+        scope = f.parent;
+    };
+    for segment in segments {
+        value = match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => value.get(segment)?,
+        };
+    }
+    Some(Lookup::Found(value))
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) | Some(Value::Int(0)) => false,
+        Some(Value::Num(x)) => *x != 0.0,
+        Some(Value::Str(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+fn render_nodes(nodes: &[Node], frame: &Frame, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { path, escape } => {
+                let text = resolve(frame, path).map(|v| v.value().to_text()).unwrap_or_default();
+                out.push_str(&if *escape { document::xml_escape(&text) } else { text });
+            }
+            Node::Block { helper, path, body, otherwise } => {
+                let lookup = resolve(frame, path);
+                let value = lookup.as_ref().map(Lookup::value);
+                match (helper, value) {
+                    (Helper::If | Helper::Unless, _) => {
+                        let branch = if truthy(value) == (*helper == Helper::If) { body } else { otherwise };
+                        render_nodes(branch, frame, out);
+                    }
+                    (_, Some(value)) if truthy(Some(value)) => {
+                        let items: Vec<(Option<&'static str>, &Value)> = match (helper, value) {
+                            (Helper::Each, Value::Array(items)) => items.iter().map(|v| (None, v)).collect(),
+                            (Helper::Each, Value::Object(fields)) => {
+                                fields.iter().map(|(k, v)| (Some(*k), v)).collect()
+                            }
+                            _ => vec![(None, value)],
+                        };
+                        let n = items.len();
+                        for (i, (key, item)) in items.into_iter().enumerate() {
+                            let index = (*helper == Helper::Each).then_some((i, n));
+                            let child = Frame { this: item, index, key, parent: Some(frame) };
+                            render_nodes(body, &child, out);
+                        }
+                    }
+                    _ => render_nodes(otherwise, frame, out),
+                }
+            }
+        }
+    }
+}
+
+fn render(nodes: &[Node], data: &Value) -> String {
+    let mut out = String::new();
+    render_nodes(nodes, &Frame { this: data, index: None, key: None, parent: None }, &mut out);
+    out
+}
+
+/// Extension for the default output name: "html" for `report.html.hbs`,
+/// else "txt".
+pub fn output_extension(template: &Path) -> &str {
+    template
+        .file_stem()
+        .map(Path::new)
+        .and_then(Path::extension)
+        .and_then(|e| e.to_str())
+        .unwrap_or("txt")
+}
+
+pub fn write_template(
+    results: &[Result<TrackResult, (String, String)>],
+    folder: &Path,
+    template: &Path,
+    output_path: &Path,
+    opts: &ReportOptions,
+) -> std::io::Result<()> {
+    let src = std::fs::read_to_string(template)?;
+    let nodes = parse(&src)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", template.display(), e)))?;
+    let data = document::build(results, folder, opts);
+    let mut f = create_output(output_path)?;
+    f.write_all(render(&nodes, &data).as_bytes())?;
+    f.flush()
+}
@@ -0,0 +1,132 @@
+// ─── Waveform thumbnails ──────────────────────────────────────────────────────
+//
+// The analysed frames are split into WIDTH columns; each column keeps the
+// lowest and highest sample over all channels and the RMS. The rendering draws
+// the min/max envelope with the RMS band on top, and columns that reach full
+// scale in red, so a brick-walled master shows up as a solid red slab.
+
+use crate::png::Image;
+
+const WIDTH: usize = 600;
+const HEIGHT: usize = 100;
+/// Columns whose peak reaches this are drawn as clipped.
+const FULL_SCALE: f32 = 0.999;
+
+// Palette indices; 0, the fill colour, is the background
+const AXIS: u8 = 1;
+const PEAK: u8 = 2;
+const RMS: u8 = 3;
+const CLIPPED: u8 = 4;
+const PALETTE: [[u8; 3]; 5] = [
+    [255, 255, 255],
+    [200, 200, 200],
+    [130, 170, 220],
+    [40, 80, 160],
+    [210, 40, 40],
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    min: f32,
+    max: f32,
+    rms: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    columns: Vec<Column>,
+}
+
+/// Streaming builder fed one normalised interleaved frame at a time.
+pub struct WaveformBuilder {
+    frames_per_column: u64,
+    count: u64,
+    min: f64,
+    max: f64,
+    sum_sq: f64,
+    columns: Vec<Column>,
+}
+
+impl WaveformBuilder {
+    /// `frames` is the number of frames that will be pushed (0 if unknown).
+    pub fn new(frames: u64, sample_rate: u32) -> Self {
+        let frames_per_column = if frames > 0 {
+            frames.div_ceil(WIDTH as u64)
+        } else {
+            (sample_rate as u64 / 10).max(1)
+        };
+        WaveformBuilder {
+            frames_per_column,
+            count: 0,
+            min: 0.0,
+            max: 0.0,
+            sum_sq: 0.0,
+            columns: Vec::with_capacity(WIDTH),
+        }
+    }
+
+    fn close_column(&mut self) {
+        self.columns.push(Column {
+            min: self.min as f32,
+            max: self.max as f32,
+            rms: (self.sum_sq / self.count.max(1) as f64).sqrt() as f32,
+        });
+        self.count = 0;
+        self.min = 0.0;
+        self.max = 0.0;
+        self.sum_sq = 0.0;
+    }
+
+    pub fn push_frame(&mut self, frame: &[f64]) {
+        let mut sq = 0.0;
+        for &x in frame {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+            sq += x * x;
+        }
+        self.sum_sq += sq / frame.len() as f64;
+        self.count += 1;
+        if self.count == self.frames_per_column {
+            self.close_column();
+        }
+    }
+
+    pub fn finish(mut self) -> Waveform {
+        if self.count > 0 {
+            self.close_column();
+        }
+        // Unknown length: fold the columns down to WIDTH
+        let group = self.columns.len().div_ceil(WIDTH).max(1);
+        let columns = self
+            .columns
+            .chunks(group)
+            .map(|c| Column {
+                min: c.iter().map(|c| c.min).fold(0.0, f32::min),
+                max: c.iter().map(|c| c.max).fold(0.0, f32::max),
+                rms: (c.iter().map(|c| c.rms * c.rms).sum::<f32>() / c.len() as f32).sqrt(),
+            })
+            .collect();
+        Waveform { columns }
+    }
+}
+
+impl Waveform {
+    pub fn render(&self) -> Image {
+        let mut image = Image::new(self.columns.len().max(1), HEIGHT, PALETTE.to_vec());
+        let y = |v: f32| ((1.0 - v.clamp(-1.0, 1.0)) / 2.0 * (HEIGHT - 1) as f32).round() as usize;
+
+        for x in 0..image.width {
+            image.set(x, HEIGHT / 2, AXIS);
+        }
+        for (x, c) in self.columns.iter().enumerate() {
+            let clipped = c.max >= FULL_SCALE || c.min <= -FULL_SCALE;
+            for row in y(c.max)..=y(c.min) {
+                image.set(x, row, if clipped { CLIPPED } else { PEAK });
+            }
+            for row in y(c.rms)..=y(-c.rms) {
+                image.set(x, row, RMS);
+            }
+        }
+        image
+    }
+}
@@ -0,0 +1,352 @@
+// ─── Excel workbook ───────────────────────────────────────────────────────────
+//
+// An Office Open XML workbook: a Summary sheet with one row per album, then one
+// sheet per album with a row per track. The parts are plain SpreadsheetML with
+// inline strings, packed into a ZIP archive without compression; Excel,
+// LibreOffice and Numbers all open it.
+
+use crate::{album_dr_values, album_lufs, create_output, document::xml_escape, png, rating, AlbumMethod, ReportOptions, TrackResult};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One album sheet: its folder and the results of its files.
+pub struct Album<'a> {
+    pub folder: &'a Path,
+    pub results: &'a [Result<TrackResult, (String, String)>],
+}
+
+enum Cell {
+    Empty,
+    Num(f64),
+    Str(String),
+}
+
+/// A number rounded to `decimals`; the infinite levels of silence are left
+/// empty rather than written as "inf".
+fn num(x: f64, decimals: i32) -> Cell {
+    if x.is_finite() {
+        let scale = 10f64.powi(decimals);
+        Cell::Num((x * scale).round() / scale + 0.0)
+    } else {
+        Cell::Empty
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(s: &str) -> Self {
+        Cell::Str(s.to_string())
+    }
+}
+
+/// "A", "B", …, "Z", "AA", … for a zero-based column.
+fn column_name(mut col: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (col % 26) as u8);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("ASCII")
+}
+
+/// A worksheet part: a bold, frozen header row and the data rows.
+fn sheet_xml(header: &[&str], widths: &[f64], rows: &[Vec<Cell>]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <sheetViews><sheetView workbookViewId=\"0\">\
+         <pane ySplit=\"1\" topLeftCell=\"A2\" activePane=\"bottomLeft\" state=\"frozen\"/>\
+         </sheetView></sheetViews><cols>",
+    );
+    for (i, width) in widths.iter().enumerate() {
+        xml.push_str(&format!("<col min=\"{n}\" max=\"{n}\" width=\"{}\" customWidth=\"1\"/>", width, n = i + 1));
+    }
+    xml.push_str("</cols><sheetData>");
+
+    let header: Vec<Cell> = header.iter().map(|&h| h.into()).collect();
+    for (r, row) in std::iter::once(&header).chain(rows).enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", r + 1));
+        // Style 1 is the bold header font
+        let style = if r == 0 { " s=\"1\"" } else { "" };
+        for (c, cell) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(c), r + 1);
+            match cell {
+                Cell::Empty => {}
+                Cell::Num(x) => xml.push_str(&format!("<c r=\"{}\"{}><v>{}</v></c>", reference, style, x)),
+                Cell::Str(s) => xml.push_str(&format!(
+                    "<c r=\"{}\"{} t=\"inlineStr\"><is><t>{}</t></is></c>",
+                    reference,
+                    style,
+                    xml_escape(s)
+                )),
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Sheet names are at most 31 characters, without []:*?/\, and unique.
+fn sheet_name(title: &str, taken: &[String]) -> String {
+    let clean: String = title
+        .chars()
+        .map(|c| if matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') { '_' } else { c })
+        .collect();
+    let clean = clean.trim_matches('\'').trim();
+    let clean = if clean.is_empty() || clean.eq_ignore_ascii_case("Summary") { "Album" } else { clean };
+    let mut n = 1;
+    loop {
+        let suffix = if n == 1 { String::new() } else { format!(" ({})", n) };
+        let name: String = clean.chars().take(31 - suffix.chars().count()).collect::<String>() + &suffix;
+        if !taken.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+fn album_title(folder: &Path) -> String {
+    folder
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| folder.display().to_string())
+}
+
+const TRACK_HEADER: [&str; 16] = [
+    "File",
+    "DR",
+    "DR (exact)",
+    "Grade",
+    "Peak dBFS",
+    "True peak dBTP",
+    "RMS dB",
+    "LUFS",
+    "PLR dB",
+    "Crest dB",
+    "Duration (s)",
+    "Sample rate",
+    "Bit depth",
+    "Channels",
+    "Clipped regions",
+    "Error",
+];
+const TRACK_WIDTHS: [f64; 16] = [40.0, 6.0, 10.0, 7.0, 10.0, 14.0, 9.0, 9.0, 9.0, 9.0, 12.0, 11.0, 9.0, 9.0, 14.0, 40.0];
+
+fn track_row(t: &TrackResult) -> Vec<Cell> {
+    vec![
+        t.filename.as_str().into(),
+        Cell::Num(t.dr as f64),
+        num(t.dr_exact, 2),
+        rating(t.dr).0.into(),
+        num(t.peak_db, 2),
+        num(t.true_peak_db, 2),
+        num(t.rms_db, 2),
+        num(t.lufs, 1),
+        num(t.true_peak_db - t.lufs, 1),
+        num(t.crest_db, 2),
+        num(t.duration_secs, 3),
+        Cell::Num(t.sample_rate as f64),
+        Cell::Num(t.bit_depth as f64),
+        Cell::Num(t.channels as f64),
+        Cell::Num(t.clipping.regions.len() as f64),
+    ]
+}
+
+const SUMMARY_HEADER: [&str; 9] =
+    ["Album", "Folder", "Tracks", "Album DR", "Grade", "LUFS", "True peak dBTP", "Duration (s)", "Errors"];
+const SUMMARY_WIDTHS: [f64; 9] = [30.0, 50.0, 8.0, 10.0, 7.0, 9.0, 14.0, 12.0, 8.0];
+
+pub fn write_xlsx(albums: &[Album], output_path: &Path, opts: &ReportOptions) -> std::io::Result<()> {
+    let mut names: Vec<String> = vec!["Summary".to_string()];
+    let mut summary = Vec::new();
+    let mut sheets = Vec::new();
+
+    for album in albums {
+        let title = album_title(album.folder);
+        let name = sheet_name(&title, &names);
+        let tracks: Vec<&TrackResult> = album.results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        let errors = album.results.len() - tracks.len();
+
+        let folder = album.folder.canonicalize().unwrap_or_else(|_| album.folder.to_path_buf());
+        let mut row: Vec<Cell> =
+            vec![title.as_str().into(), folder.display().to_string().as_str().into(), Cell::Num(tracks.len() as f64)];
+        if tracks.is_empty() {
+            row.extend([Cell::Empty, Cell::Empty, Cell::Empty, Cell::Empty, Cell::Empty]);
+        } else {
+            let (dr_mean, dr_pooled) = album_dr_values(&tracks, opts);
+            let album_value = match opts.album_method {
+                AlbumMethod::Pooled => dr_pooled,
+                AlbumMethod::Mean => dr_mean,
+            };
+            let dr = opts.dr.algorithm.round(album_value);
+            row.extend([
+                Cell::Num(dr as f64),
+                rating(dr).0.into(),
+                num(album_lufs(&tracks), 1),
+                num(tracks.iter().map(|t| t.true_peak_db).fold(f64::NEG_INFINITY, f64::max), 2),
+                num(tracks.iter().map(|t| t.duration_secs).sum::<f64>(), 3),
+            ]);
+        }
+        row.push(Cell::Num(errors as f64));
+        summary.push(row);
+
+        let rows: Vec<Vec<Cell>> = album
+            .results
+            .iter()
+            .map(|r| match r {
+                Ok(t) => track_row(t),
+                Err((file, error)) => {
+                    let mut row: Vec<Cell> = vec![file.as_str().into()];
+                    row.extend((1..TRACK_HEADER.len() - 1).map(|_| Cell::Empty));
+                    row.push(error.as_str().into());
+                    row
+                }
+            })
+            .collect();
+        sheets.push(sheet_xml(&TRACK_HEADER, &TRACK_WIDTHS, &rows));
+        names.push(name);
+    }
+    sheets.insert(0, sheet_xml(&SUMMARY_HEADER, &SUMMARY_WIDTHS, &summary));
+
+    let mut zip = Zip::default();
+    let mut content_types = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/xl/workbook.xml\" \
+         ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+         <Override PartName=\"/xl/styles.xml\" \
+         ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml\"/>",
+    );
+    for i in 1..=sheets.len() {
+        content_types.push_str(&format!(
+            "<Override PartName=\"/xl/worksheets/sheet{}.xml\" \
+             ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>",
+            i
+        ));
+    }
+    content_types.push_str("</Types>");
+    zip.add("[Content_Types].xml", content_types.as_bytes());
+
+    zip.add(
+        "_rels/.rels",
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+          <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+          <Relationship Id=\"rId1\" \
+          Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" \
+          Target=\"xl/workbook.xml\"/></Relationships>",
+    );
+
+    let mut workbook = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+         xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"><sheets>",
+    );
+    let mut workbook_rels = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+    );
+    for (i, name) in names.iter().enumerate() {
+        workbook.push_str(&format!("<sheet name=\"{}\" sheetId=\"{n}\" r:id=\"rId{n}\"/>", xml_escape(name), n = i + 1));
+        workbook_rels.push_str(&format!(
+            "<Relationship Id=\"rId{n}\" \
+             Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" \
+             Target=\"worksheets/sheet{n}.xml\"/>",
+            n = i + 1
+        ));
+    }
+    workbook.push_str("</sheets></workbook>");
+    workbook_rels.push_str(&format!(
+        "<Relationship Id=\"rId{}\" \
+         Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" \
+         Target=\"styles.xml\"/></Relationships>",
+        names.len() + 1
+    ));
+    zip.add("xl/workbook.xml", workbook.as_bytes());
+    zip.add("xl/_rels/workbook.xml.rels", workbook_rels.as_bytes());
+
+    zip.add(
+        "xl/styles.xml",
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+          <styleSheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+          <fonts count=\"2\"><font><sz val=\"11\"/><name val=\"Calibri\"/></font>\
+          <font><b/><sz val=\"11\"/><name val=\"Calibri\"/></font></fonts>\
+          <fills count=\"2\"><fill><patternFill patternType=\"none\"/></fill>\
+          <fill><patternFill patternType=\"gray125\"/></fill></fills>\
+          <borders count=\"1\"><border><left/><right/><top/><bottom/><diagonal/></border></borders>\
+          <cellStyleXfs count=\"1\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/></cellStyleXfs>\
+          <cellXfs count=\"2\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\"/>\
+          <xf numFmtId=\"0\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyFont=\"1\"/></cellXfs>\
+          </styleSheet>",
+    );
+    for (i, sheet) in sheets.iter().enumerate() {
+        zip.add(&format!("xl/worksheets/sheet{}.xml", i + 1), sheet.as_bytes());
+    }
+
+    let mut f = BufWriter::new(create_output(output_path)?);
+    f.write_all(&zip.finish())?;
+    f.flush()
+}
+
+// ─── ZIP container (stored entries) ───────────────────────────────────────────
+
+#[derive(Default)]
+struct Zip {
+    data: Vec<u8>,
+    directory: Vec<u8>,
+    entries: u16,
+}
+
+impl Zip {
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let crc = png::crc32(contents);
+        let offset = self.data.len() as u32;
+        // Version 2.0, no flags, stored, DOS time/date 1980-01-01 00:00
+        let common = |out: &mut Vec<u8>| {
+            out.extend_from_slice(&20u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&0x21u16.to_le_bytes());
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+        };
+
+        self.data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        common(&mut self.data);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(contents);
+
+        self.directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.directory.extend_from_slice(&20u16.to_le_bytes());
+        common(&mut self.directory);
+        // Comment length, disk, internal and external attributes, offset
+        self.directory.extend_from_slice(&[0; 6]);
+        self.directory.extend_from_slice(&0u32.to_le_bytes());
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.data.len() as u32;
+        self.data.extend_from_slice(&self.directory);
+        self.data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&(self.directory.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(&directory_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data
+    }
+}